@@ -1,3 +1,5 @@
+use std::time::{Duration, Instant};
+
 use crate::{result::Result, tag::JarTag};
 
 pub trait IArena {
@@ -14,5 +16,23 @@ pub trait IArena {
     fn borrow_mut_element(&self, tag: &JarTag) -> Result<Self::RefMut> {
         Ok(self.try_borrow_mut_element(tag)?.unwrap())
     }
+    /// Emprunte l'élément en lecture, en attendant la libération du verrou jusqu'à l'échéance.
+    ///
+    /// Sans échéance, l'attente n'est pas bornée.
+    /// Retourne [crate::error::ErrorKind::LockTimeout] si l'échéance est dépassée.
+    fn borrow_element_until(&self, tag: &JarTag, deadline: Option<Instant>) -> Result<Self::Ref>;
+    /// Emprunte l'élément en écriture, en attendant la libération du verrou jusqu'à l'échéance.
+    ///
+    /// Sans échéance, l'attente n'est pas bornée.
+    /// Retourne [crate::error::ErrorKind::LockTimeout] si l'échéance est dépassée.
+    fn borrow_mut_element_until(&self, tag: &JarTag, deadline: Option<Instant>) -> Result<Self::RefMut>;
+    /// Emprunte l'élément en lecture, en attendant au plus *timeout*.
+    fn borrow_element_timeout(&self, tag: &JarTag, timeout: Duration) -> Result<Self::Ref> {
+        self.borrow_element_until(tag, Some(Instant::now() + timeout))
+    }
+    /// Emprunte l'élément en écriture, en attendant au plus *timeout*.
+    fn borrow_mut_element_timeout(&self, tag: &JarTag, timeout: Duration) -> Result<Self::RefMut> {
+        self.borrow_mut_element_until(tag, Some(Instant::now() + timeout))
+    }
     fn size_of(&self) -> usize;
 }
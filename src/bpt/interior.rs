@@ -250,7 +250,7 @@ where
             .map(|cell| unsafe { std::mem::transmute(cell) })
     }
 
-    fn set_tail(&mut self, tail: Option<PageId>) {
+    pub fn set_tail(&mut self, tail: Option<PageId>) {
         self.as_mut_meta().set_tail(tail);
     }
 
@@ -361,6 +361,10 @@ where
         }
     }
 
+    pub fn cid(&self) -> CellId {
+        self.as_cell().id()
+    }
+
     /// Retourne le pointeur vers le noeud à gauche.
     pub fn left(&self) -> Option<PageId> {
         OptionalPageId::read_from_bytes(self.as_left_slice())
@@ -15,7 +15,7 @@ use crate::{
     }, page::{
         AsMutPageSlice, AsRefPage, AsRefPageSlice, IntoRefPageSlice, MutPage, OptionalPageId,
         PageId, PageKind, PageSize, PageSlice, RefPage, RefPageSlice,
-    }, pager::IPager, result::Result, tag::{DataArea, JarTag}, utils::Shift, var::{MaybeSpilled, MaybeSpilledRef, Var}
+    }, pager::IPager, result::Result, tag::{DataArea, JarTag}, utils::Shift, var::{free_overflow_pages, MaybeSpilled, MaybeSpilledRef, Var}
 };
 
 use super::descriptor::BPlusTreeDescription;
@@ -107,6 +107,14 @@ where
         self.as_meta().get_parent()
     }
 
+    pub fn get_prev(&self) -> Option<PageId> {
+        self.as_meta().get_prev()
+    }
+
+    pub fn get_next(&self) -> Option<PageId> {
+        self.as_meta().get_next()
+    }
+
     pub fn search_cell<Key>(&self, key: &Key) -> Option<&BPlusTreeLeafCell<PageSlice>> 
         where Key: AsComparable<Kernel=Knack>
     {
@@ -186,6 +194,33 @@ where
         self.0.as_mut()
     }
 
+    /// Remplace la valeur associée à une clé de la feuille.
+    ///
+    /// Les pages de débordement de l'ancienne valeur sont libérées.
+    /// Retourne faux si la clé n'est pas dans la feuille.
+    pub fn replace<'a, Pager: IPager<'a> + ?std::marker::Sized>(
+        &mut self,
+        key: &ComparableAndFixedSized<Knack>,
+        value: &Knack,
+        desc: &BPlusTreeDescription,
+        pager: &Pager,
+    ) -> Result<bool> {
+        let found = self.iter().find(|cell| cell.borrow_key().as_comparable() == key.as_comparable());
+
+        let Some(cid) = found.map(|cell| cell.cid()) else {
+            return Ok(false);
+        };
+
+        let cell = &mut self[&cid];
+
+        if desc.value_will_spill() {
+            cell.borrow_mut_var().spill_page().iter().try_for_each(|&head| free_overflow_pages(head, pager))?;
+        }
+
+        cell.set_value(value, desc, pager)?;
+        Ok(true)
+    }
+
     fn borrow_mut_cell(&mut self, cid: &CellId) -> Option<&mut BPlusTreeLeafCell<PageSlice>> {
         self.0
             .borrow_mut_cell(cid)
@@ -325,6 +360,12 @@ impl BPTreeLeafMeta {
     pub fn set_parent(&mut self, parent: Option<PageId>) {
         self.parent = parent.into();
     }
+    pub fn get_prev(&self) -> Option<PageId> {
+        self.prev.into()
+    }
+    pub fn get_next(&self) -> Option<PageId> {
+        self.next.into()
+    }
     pub fn set_prev(&mut self, prev: Option<PageId>) {
         self.prev = prev.into()
     }
@@ -362,6 +403,12 @@ where
         Ok(())
     }
 
+    /// Emprunte la valeur de taille variable de la cellule.
+    pub(crate) fn borrow_mut_var(&mut self) -> &mut Var<PageSlice> {
+        let range = self.value_area();
+        Var::from_mut_slice(&mut self.0.as_mut_content_slice()[range])
+    }
+
     #[allow(dead_code)]
     pub(crate) fn borrow_mut_key(&mut self) -> &mut Comparable<FixedSized<Knack>> {
         let range: Range<usize> = self.key_area();
@@ -457,6 +504,11 @@ where
         
     }
 
+    /// Emprunte la valeur de taille variable de la cellule.
+    pub(crate) fn borrow_var(&self) -> &Var<PageSlice> {
+        Var::from_ref_slice(&self.0.as_content_slice()[self.value_area()])
+    }
+
     pub fn key_area(&self) -> Range<usize> {
         0..self.key_kind().as_fixed_sized().outer_size()
     }
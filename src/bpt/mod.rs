@@ -69,6 +69,26 @@ where
         Ok(Self { arena, tag })
     }
 
+    /// Ouvre un arbre existant depuis la page de son descripteur.
+    pub fn open(arena: &'nodes Arena, tag: JarTag) -> Result<Self> {
+        arena.borrow_element(&tag).and_then(BPTreeDescriptor::try_from)?;
+        Ok(Self { arena, tag })
+    }
+
+    /// Page du descripteur de l'arbre.
+    pub fn tag(&self) -> &JarTag {
+        &self.tag
+    }
+
+    /// Le nombre d'éléments stockés dans l'arbre
+    pub fn len(&self) -> u64 {
+        self.as_descriptor().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Recherche une valeur associée à la clé
     pub fn search(&self, key: &Knack) -> Result<Option<MaybeSpilled<RefPageSlice<'nodes>>>> {
         let maybe_tag = self.search_leaf(key)?;
@@ -128,6 +148,30 @@ where
         Ok(())
     }
 
+    /// Remplace la valeur associée à une clé existante.
+    ///
+    /// Retourne faux si la clé n'existe pas.
+    pub fn replace(&mut self, key: &Knack, value: &Knack) -> Result<bool> {
+        assert_eq!(
+            value.kind(),
+            self.as_descriptor().value_kind(),
+            "wrong value kind"
+        );
+
+        let Some(tag) = self.search_leaf(key)? else {
+            return Ok(false);
+        };
+
+        let mut leaf = self.borrow_mut_leaf(&tag)?;
+
+        leaf.replace(
+            <&ComparableAndFixedSized::<Knack>>::try_from(key).expect("key must be comparable"),
+            value,
+            self.as_descriptor().as_description(),
+            self.arena,
+        )
+    }
+
     /// Divise un noeud
     ///
     /// Cette opération est utilisée lors d'une insertion si le noeud est plein.
@@ -520,7 +564,7 @@ mod tests {
     use std::borrow::Borrow;
     use rand::Rng;
 
-    use crate::{knack::marker::kernel::{AsKernelRef, IntoKernel}, pager::stub::StubPager, prelude::IntoKnackBuf};
+    use crate::{knack::{fixed_str::FixedStr, marker::kernel::{AsKernelRef, IntoKernel}, timestamp::Timestamp}, pager::stub::StubPager, prelude::IntoKnackBuf};
 
     use super::{BPlusTree, BPlusTreeArgs};
 
@@ -562,6 +606,47 @@ mod tests {
         assert_eq!(value.cast::<u64>(), &19u64)
     }
 
+    #[test]
+    fn test_timestamp_keys() {
+        let pager = StubPager::<4096>::new();
+        let args = BPlusTreeArgs::new::<Timestamp, u64>(None);
+        let mut tree = BPlusTree::new(&pager, args).unwrap();
+
+        // Les horodatages négatifs doivent précéder les positifs.
+        for micros in -500..500i64 {
+            let key = Timestamp::from_micros(micros * 1_000).into_knack_buf();
+            let value = micros.unsigned_abs().into_knack_buf();
+            tree.insert(key.borrow(), &value.into_kernel()).unwrap();
+        }
+
+        let value = tree.search(&Timestamp::from_micros(-42_000).into_knack_buf()).unwrap().unwrap().into_unspilled();
+        assert_eq!(value.cast::<u64>(), &42u64);
+        assert!(tree.search(&Timestamp::from_micros(1).into_knack_buf()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_fixed_str_keys() {
+        let pager = StubPager::<4096>::new();
+        let args = BPlusTreeArgs::new::<FixedStr<3>, u32>(None);
+        let mut tree = BPlusTree::new(&pager, args).unwrap();
+
+        // Tous les codes de deux lettres, de quoi provoquer des éclatements de noeuds.
+        let codes: Vec<String> = (b'A'..=b'Z')
+            .flat_map(|a| (b'A'..=b'Z').map(move |b| String::from_utf8(vec![a, b]).unwrap()))
+            .collect();
+
+        for (i, code) in codes.iter().enumerate() {
+            let key = FixedStr::<3>::new(code).unwrap().into_knack_buf();
+            let value = u32::try_from(i).unwrap().into_knack_buf();
+            tree.insert(key.borrow(), &value.into_kernel()).unwrap();
+        }
+
+        let value = tree.search(&FixedStr::<3>::new("FR").unwrap().into_knack_buf()).unwrap().unwrap().into_unspilled();
+        let expected = codes.iter().position(|c| c == "FR").unwrap();
+        assert_eq!(value.cast::<u32>().to_owned(), u32::try_from(expected).unwrap());
+        assert!(tree.search(&FixedStr::<3>::new("FRA").unwrap().into_knack_buf()).unwrap().is_none());
+    }
+
     #[test]
     fn test_multiple_insert() {
         let mut rng = rand::rng();
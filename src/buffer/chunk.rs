@@ -0,0 +1,103 @@
+use std::{
+    alloc::{alloc_zeroed, dealloc, Layout},
+    mem::MaybeUninit,
+    ptr::NonNull,
+};
+
+use crate::{
+    page::{descriptor::PageDescriptorInner, PageSize, PageSlice},
+    tag::JarTag,
+};
+
+/// Un bloc de mémoire contigu découpé en emplacements de pages.
+///
+/// Un emplacement contient le descripteur de la page suivi de son contenu.
+/// Les emplacements sont distribués au fur et à mesure des besoins.
+pub(super) struct Chunk {
+    layout: Layout,
+    ptr: NonNull<u8>,
+    /// Identifiant du premier emplacement du bloc
+    first: usize,
+    /// Nombre d'emplacements du bloc
+    frames: usize,
+    /// Nombre d'emplacements distribués
+    used: usize,
+    page_size: PageSize,
+}
+
+impl Chunk {
+    pub fn new(first: usize, frames: usize, page_size: PageSize) -> Self {
+        let frame_size = Self::frame_size(page_size);
+        let layout =
+            Layout::from_size_align(frames * frame_size, frame_size.next_power_of_two()).unwrap();
+
+        unsafe {
+            let ptr = NonNull::new(alloc_zeroed(layout)).unwrap();
+
+            Self {
+                layout,
+                ptr,
+                first,
+                frames,
+                used: 0,
+                page_size,
+            }
+        }
+    }
+
+    /// Taille d'un emplacement de page.
+    pub fn frame_size(page_size: PageSize) -> usize {
+        usize::from(page_size) + size_of::<PageDescriptorInner>()
+    }
+
+    /// Identifiant de l'emplacement suivant le dernier emplacement du bloc.
+    pub fn end(&self) -> usize {
+        self.first + self.frames
+    }
+
+    /// Nombre d'emplacements distribués.
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    /// Vérifie si le descripteur est stocké dans le bloc.
+    pub fn contains(&self, desc: NonNull<PageDescriptorInner>) -> bool {
+        let start = self.ptr.as_ptr() as usize;
+        let addr = desc.as_ptr() as usize;
+        (start..start + self.layout.size()).contains(&addr)
+    }
+
+    /// Distribue un nouvel emplacement, s'il en reste.
+    pub fn alloc_frame(&mut self, tag: &JarTag) -> Option<NonNull<PageDescriptorInner>> {
+        if self.used >= self.frames {
+            return None;
+        }
+
+        let buf_id = self.first + self.used;
+        let offset = self.used * Self::frame_size(self.page_size);
+        self.used += 1;
+
+        unsafe {
+            let ptr = self.ptr.add(offset);
+            let mut cell_ptr = ptr.cast::<MaybeUninit<PageDescriptorInner>>();
+            let content_ptr = ptr.add(size_of::<PageDescriptorInner>());
+            let content = std::mem::transmute::<NonNull<[u8]>, NonNull<PageSlice>>(
+                NonNull::slice_from_raw_parts(content_ptr, self.page_size.into()),
+            );
+
+            Some(NonNull::new_unchecked(
+                cell_ptr
+                    .as_mut()
+                    .write(PageDescriptorInner::new(buf_id, *tag, content)),
+            ))
+        }
+    }
+}
+
+impl Drop for Chunk {
+    fn drop(&mut self) {
+        unsafe {
+            dealloc(self.ptr.as_mut(), self.layout);
+        }
+    }
+}
@@ -1,27 +1,35 @@
+mod chunk;
+pub mod quota;
+pub mod replacement;
+pub mod stats;
 pub mod stress;
 
 use std::{
-    alloc::{alloc_zeroed, dealloc, Layout},
-    mem::MaybeUninit,
+    collections::HashMap,
+    ops::Deref,
     ptr::NonNull,
     sync::{
         atomic::{AtomicUsize, Ordering},
         Arc, Mutex,
     },
+    time::Instant,
 };
 
+use chunk::Chunk;
 use dashmap::{DashMap, DashSet};
-use itertools::Itertools;
+use quota::{JarPriority, JarQuota};
+use replacement::{Clock, ReplacementPolicy};
+use stats::{BufferPoolCounters, BufferPoolStats, JarStats};
 use stress::BufferStressStrategy;
 
 use crate::{
     error::{Error, ErrorKind},
     page::{
         descriptor::{PageDescriptor, PageDescriptorInner, PageDescriptorPtr},
-        MutPage, PageSize, PageSlice, RefPage,
+        MutPage, PageSize, RefPage,
     },
     result::Result,
-    tag::JarTag,
+    tag::{JarId, JarTag},
     utils::Flip,
 };
 
@@ -47,6 +55,30 @@ pub trait IBufferPool {
         }
     }
 
+    /// Récupère une référence vers la page si elle est stockée dans le tampon,
+    /// en attendant la libération d'une éventuelle référence mutable.
+    ///
+    /// Sans échéance, l'attente n'est pas bornée.
+    /// Retourne [ErrorKind::LockTimeout] si l'échéance est dépassée.
+    fn get_ref_until<'buf>(&'buf self, tag: &JarTag, deadline: Option<Instant>) -> Result<Option<RefPage<'buf>>> {
+        unsafe {
+            let desc = self.try_get_descriptor(tag)?;
+            desc.map(|desc| RefPage::new_until(desc, deadline)).flip()
+        }
+    }
+
+    /// Récupère une référence mutable vers la page si elle est stockée dans le tampon,
+    /// en attendant la libération des références en cours.
+    ///
+    /// Sans échéance, l'attente n'est pas bornée.
+    /// Retourne [ErrorKind::LockTimeout] si l'échéance est dépassée.
+    fn get_mut_until<'buf>(&'buf self, tag: &JarTag, deadline: Option<Instant>) -> Result<Option<MutPage<'buf>>> {
+        unsafe {
+            let desc = self.try_get_descriptor(tag)?;
+            desc.map(|desc| MutPage::new_until(desc, deadline)).flip()
+        }
+    }
+
     /// Essaye de récupérer une page stocker dans le tampon.
     ///
     /// # Safety
@@ -87,20 +119,30 @@ impl IBufferPool for SharedBufferPool {
     }
 }
 
+impl Deref for SharedBufferPool {
+    type Target = BufferPool;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<BufferPool> for SharedBufferPool {
+    fn from(value: BufferPool) -> Self {
+        Self(Arc::new(value))
+    }
+}
+
 unsafe impl Sync for SharedBufferPool {}
 unsafe impl Send for SharedBufferPool {}
 
 pub struct BufferPool {
-    /// Le layout de l'espace allouée
-    layout: Layout,
-    /// L'espace allouée
-    ptr: NonNull<u8>,
+    /// Blocs de mémoire alloués, ordonnés par identifiant d'emplacement
+    chunks: Mutex<Vec<Chunk>>,
     /// La taille du tampon en octets.
-    size: usize,
-    /// The number of pages stored
+    size: AtomicUsize,
+    /// Le nombre d'emplacements occupés par une page
     length: AtomicUsize,
-    /// Le bout de l'espace des pages allouées
-    tail: AtomicUsize,
     /// Taille d'une page
     page_size: PageSize,
     /// Emplacements libres dans l'espace des pages allouées.
@@ -112,6 +154,15 @@ pub struct BufferPool {
     /// Stratégie de gestion du stress mémoire
     /// Employé si le tampon est plein
     stress: BufferStressStrategy,
+    /// Politique de remplacement des pages
+    /// Désigne les pages à évincer si le tampon est plein
+    policy: ReplacementPolicy,
+    /// Compteurs d'activité
+    counters: BufferPoolCounters,
+    /// Quotas de mémoire des jars
+    quotas: DashMap<JarId, JarQuota>,
+    /// Nombre de pages en mémoire par jar
+    jar_frames: DashMap<JarId, usize>,
 }
 
 unsafe impl Sync for BufferPool {}
@@ -124,29 +175,60 @@ impl BufferPool {
         page_size: PageSize,
         stress_strategy: BufferStressStrategy,
     ) -> Self {
-        unsafe {
-            let align: usize =
-                (usize::from(page_size) + size_of::<PageDescriptorInner>()).next_power_of_two();
-
-            let layout = Layout::from_size_align(buffer_size, align).unwrap();
-
-            let ptr = NonNull::new(alloc_zeroed(layout)).unwrap();
-
-            Self {
-                layout,
-                ptr,
-                size: buffer_size,
-                length: Default::default(),
-                tail: Default::default(),
-                page_size,
-                stored: Default::default(),
-                freelist: Default::default(),
-                in_memory: Default::default(),
-                stress: stress_strategy,
-            }
+        Self {
+            chunks: Default::default(),
+            size: AtomicUsize::new(buffer_size),
+            length: Default::default(),
+            page_size,
+            stored: Default::default(),
+            freelist: Default::default(),
+            in_memory: Default::default(),
+            stress: stress_strategy,
+            policy: Clock::default().into_boxed(),
+            counters: Default::default(),
+            quotas: Default::default(),
+            jar_frames: Default::default(),
         }
     }
 
+    /// Définit le quota de mémoire d'un jar.
+    ///
+    /// Le quota est pris en compte lors des prochaines évictions, les pages
+    /// déjà en mémoire ne sont pas évincées pour autant.
+    pub fn set_jar_quota(&self, jar_id: JarId, quota: JarQuota) {
+        self.quotas.insert(jar_id, quota);
+    }
+
+    /// Retire le quota de mémoire d'un jar.
+    pub fn remove_jar_quota(&self, jar_id: JarId) {
+        self.quotas.remove(&jar_id);
+    }
+
+    /// Quota de mémoire d'un jar (par défaut aucune réservation ni limite).
+    pub fn jar_quota(&self, jar_id: JarId) -> JarQuota {
+        self.quotas
+            .get(&jar_id)
+            .map(|quota| *quota)
+            .unwrap_or_default()
+    }
+
+    /// Nombre de pages d'un jar en mémoire.
+    pub fn jar_len(&self, jar_id: JarId) -> usize {
+        self.jar_frames
+            .get(&jar_id)
+            .map(|frames| *frames)
+            .unwrap_or_default()
+    }
+
+    /// Remplace la politique de remplacement des pages (par défaut [Clock]).
+    pub fn with_replacement_policy(mut self, policy: ReplacementPolicy) -> Self {
+        self.policy = policy;
+        self.in_memory
+            .iter()
+            .for_each(|kv| self.policy.admit(kv.key()));
+        self
+    }
+
     pub fn page_size(&self) -> PageSize {
         self.page_size
     }
@@ -164,18 +246,48 @@ impl BufferPool {
     pub fn contains(&self, tag: &JarTag) -> bool {
         self.stored.contains(tag)
     }
-}
 
-impl Drop for BufferPool {
-    fn drop(&mut self) {
-        unsafe {
-            dealloc(self.ptr.as_mut(), self.layout);
+    /// Retire une page du tampon sans la décharger, ses modifications sont perdues.
+    ///
+    /// Échoue si la page est empruntée.
+    pub fn discard(&self, tag: &JarTag) -> Result<()> {
+        if let Some(page) = self.try_get_from_memory(tag) {
+            if page.get_ref_counter() > 1 {
+                return Err(Error::new(ErrorKind::PageCurrentlyBorrowed));
+            }
+
+            unsafe {
+                self.remove_from_memory(page.get_raw_ptr());
+                self.push_free(page.get_raw_ptr());
+            }
+        } else if self.stress.contains(tag) {
+            self.stress.remove(tag)?;
         }
+
+        self.stored.remove(tag);
+        Ok(())
     }
-}
 
-impl IBufferPool for BufferPool {
-    fn alloc<'buf>(&'buf self, tag: &JarTag) -> Result<MutPage<'buf>> {
+    /// La page est actuellement empruntée.
+    pub fn is_borrowed(&self, tag: &JarTag) -> bool {
+        self.try_get_from_memory(tag)
+            .is_some_and(|page| page.get_ref_counter() > 1)
+    }
+
+    /// Liste les pages d'un jar stockées dans le tampon.
+    pub fn jar_tags(&self, jar_id: JarId) -> Vec<JarTag> {
+        self.stored
+            .iter()
+            .filter(|tag| tag.jar_id == jar_id)
+            .map(|tag| *tag)
+            .collect()
+    }
+
+    /// Alloue de l'espace pour tamponner une page.
+    ///
+    /// Si *dry* est vrai, les modifications ne salissent pas la page ; utile
+    /// pour charger une page depuis son stockage.
+    pub(crate) fn alloc_with_options(&self, tag: &JarTag, dry: bool) -> Result<MutPage<'_>> {
         // Déjà caché
         if self.contains(tag) {
             return Err(Error::new(ErrorKind::PageAlreadyCached(*tag)));
@@ -185,7 +297,93 @@ impl IBufferPool for BufferPool {
             .inspect(|_| {
                 self.stored.insert(*tag);
             })
-            .and_then(MutPage::try_new)
+            .and_then(|desc| MutPage::try_new_with_options(desc, dry))
+    }
+
+    /// Nombre de pages que la mémoire du tampon peut contenir.
+    pub fn capacity(&self) -> usize {
+        self.size.load(Ordering::Acquire) / Chunk::frame_size(self.page_size)
+    }
+
+    /// Redimensionne la mémoire du tampon.
+    ///
+    /// Un agrandissement est immédiat, la mémoire étant allouée à la demande.
+    /// Une réduction évince, ou décharge, les pages qui ne tiennent plus dans
+    /// le tampon, puis restitue les blocs de mémoire entièrement libérés.
+    ///
+    /// Si des pages empruntées empêchent la réduction, la nouvelle taille est
+    /// tout de même retenue, et l'erreur *BufferFull* est retournée.
+    pub fn resize(&self, buffer_size: usize) -> Result<()> {
+        self.size.store(buffer_size, Ordering::Release);
+        let capacity = self.capacity();
+
+        while self.length.load(Ordering::Acquire) > capacity {
+            let page = self.manage_stress(None)?;
+            unsafe {
+                self.push_free(page.get_raw_ptr());
+            }
+        }
+
+        self.release_chunks();
+        Ok(())
+    }
+
+    /// Prend un instantané des métriques du tampon.
+    ///
+    /// L'instantané n'est pas atomique : les compteurs peuvent évoluer
+    /// pendant sa construction.
+    pub fn stats(&self) -> BufferPoolStats {
+        let mut jars = self
+            .counters
+            .jars()
+            .into_iter()
+            .map(|(jar_id, activity)| {
+                (
+                    jar_id,
+                    JarStats {
+                        activity,
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut length = 0;
+        let mut dirty = 0;
+
+        for kv in self.in_memory.iter() {
+            let is_dirty = unsafe { kv.value().as_ref().is_dirty() };
+            let jar = jars.entry(kv.key().jar_id).or_default();
+
+            length += 1;
+            jar.in_memory += 1;
+
+            if is_dirty {
+                dirty += 1;
+                jar.dirty += 1;
+            }
+        }
+
+        let discharged = self.stress.discharged();
+
+        for tag in discharged.iter() {
+            jars.entry(tag.jar_id).or_default().stressed += 1;
+        }
+
+        BufferPoolStats {
+            activity: self.counters.global(),
+            length,
+            capacity: self.capacity(),
+            dirty,
+            stressed: discharged.len(),
+            jars,
+        }
+    }
+}
+
+impl IBufferPool for BufferPool {
+    fn alloc<'buf>(&'buf self, tag: &JarTag) -> Result<MutPage<'buf>> {
+        self.alloc_with_options(tag, false)
     }
 
     /// Essaye de récupérer une page stocker dans le tampon.
@@ -195,14 +393,27 @@ impl IBufferPool for BufferPool {
     ) -> Result<Option<PageDescriptor<'buf>>> {
         // La page est en mémoire, on la renvoie
         if let Some(stored) = self.try_get_from_memory(tag) {
-            return Ok(Some(PageDescriptor::from_raw_ptr(stored)));
+            self.counters.hit(tag);
+            self.policy.touch(tag);
+            return Ok(Some(stored));
         }
 
+        self.counters.miss(tag);
+
         // La page a été déchargée, on va essayer de la récupérer.
         if self.stress.contains(tag) {
             let mut page = self.alloc_from_memory(tag)?;
             assert_eq!(page.tag(), tag);
-            self.stress.retrieve(&mut page)?;
+
+            // L'emplacement est rendu, sinon la page serait ensuite lue comme une page vierge.
+            if let Err(error) = self.stress.retrieve(&mut page) {
+                let ptr = page.get_raw_ptr();
+                drop(page);
+                self.remove_from_memory(ptr);
+                self.push_free(ptr);
+                return Err(error);
+            }
+
             return Ok(Some(page));
         }
 
@@ -226,77 +437,68 @@ impl BufferPool {
                 return Err(Error::new(ErrorKind::PageAlreadyCached(*tag)));
             }
 
-            // On a un slot de libre
-            if let Some(free) = self.pop_free(tag) {
-                self.add_in_memory(free.get_raw_ptr());
-                return Ok(free);
-            }
+            let capped = self.jar_quota(tag.jar_id).is_capped(self.jar_len(tag.jar_id));
 
-            let res = self.alloc_in_heap(tag);
+            if !capped && self.reserve_frame() {
+                // On a un slot de libre
+                if let Some(free) = self.pop_free(tag) {
+                    self.add_in_memory(free.get_raw_ptr());
+                    return Ok(free);
+                }
 
-            if let Err(ErrorKind::BufferFull) = res.as_ref().map_err(|err| &err.kind) {
-                // Le cache est plein, on est dans un cas de stress mémoire
-                // On va essayer de trouver de la place.
-                let mut page = self.manage_stress()?;
-                page.initialise(*tag);
-                self.add_in_memory(page.get_raw_ptr());
-                return Ok(page);
+                return Ok(self.alloc_in_heap(tag));
             }
 
-            res
+            // Le cache, ou le quota du jar, est plein, on est dans un cas de stress mémoire
+            // On va essayer de trouver de la place.
+            let mut page = self.manage_stress(Some(tag))?;
+            page.initialise(*tag);
+            self.add_in_memory(page.get_raw_ptr());
+            Ok(page)
         }
     }
 
-    /// Alloue sur le tas restant un emplacement pour stocker une page.
+    /// Réserve un emplacement dans la limite de la capacité du tampon.
+    fn reserve_frame(&self) -> bool {
+        let capacity = self.capacity();
+        self.length
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |length| {
+                (length < capacity).then_some(length + 1)
+            })
+            .is_ok()
+    }
+
+    /// Distribue un nouvel emplacement, en allouant un nouveau bloc de mémoire si nécessaire.
     ///
-    /// Cette fonction doit supposément être thread-safe via l'utilisation
-    /// de compteurs atomiques.
-    fn alloc_in_heap<'buf>(&'buf self, tag: &JarTag) -> Result<PageDescriptor<'buf>> {
-        let size = usize::from(self.page_size) + size_of::<PageDescriptorInner>();
-
-        loop {
-            let tail = self.tail.load(std::sync::atomic::Ordering::Acquire);
-            let current_tail = self
-                .tail
-                .fetch_add(size, std::sync::atomic::Ordering::Release);
-
-            if current_tail >= self.size {
-                return Err(Error::new(ErrorKind::BufferFull));
+    /// L'emplacement doit avoir été réservé (voir [Self::reserve_frame]).
+    fn alloc_in_heap<'buf>(&'buf self, tag: &JarTag) -> PageDescriptor<'buf> {
+        let mut chunks = self.chunks.lock().unwrap();
+
+        let ptr = match chunks.last_mut().and_then(|chunk| chunk.alloc_frame(tag)) {
+            Some(ptr) => ptr,
+            None => {
+                let first = chunks.last().map(Chunk::end).unwrap_or_default();
+                let frames = self.capacity().saturating_sub(first).max(1);
+                let mut chunk = Chunk::new(first, frames, self.page_size);
+                let ptr = chunk.alloc_frame(tag).unwrap();
+                chunks.push(chunk);
+                ptr
             }
+        };
 
-            // On est bon
-            if tail == current_tail {
-                if tail >= self.size {
-                    return Err(Error::new(ErrorKind::BufferFull));
-                }
-                let new_tail = tail + size;
-                unsafe {
-                    let buf_id = self.length.fetch_add(1, Ordering::Release);
-                    let ptr = self.ptr.add(new_tail);
-                    let mut cell_ptr = ptr.cast::<MaybeUninit<PageDescriptorInner>>();
-                    let content_ptr = ptr.add(size_of::<PageDescriptorInner>());
-                    let content = std::mem::transmute::<NonNull<[u8]>, NonNull<PageSlice>>(
-                        NonNull::slice_from_raw_parts(content_ptr, self.page_size.into()),
-                    );
-                    let ptr = NonNull::new_unchecked(
-                        cell_ptr
-                            .as_mut()
-                            .write(PageDescriptorInner::new(buf_id, *tag, content)),
-                    );
-
-                    self.add_in_memory(ptr);
-                    return Ok(PageDescriptor::from_raw_ptr(ptr));
-                }
-            }
-        }
+        self.add_in_memory(ptr);
+        unsafe { PageDescriptor::from_raw_ptr(ptr) }
     }
 
-    /// Récupère un pointeur vers un tampon d'une page donnée, s'il existe.
-    fn try_get_from_memory(&self, tag: &JarTag) -> Option<PageDescriptorPtr> {
+    /// Récupère le descripteur d'une page en mémoire, s'il existe.
+    ///
+    /// La référence est prise sous le verrou de la table : un emplacement référencé
+    /// ne peut être déplacé ni restitué (voir [Self::release_chunks]).
+    fn try_get_from_memory(&self, tag: &JarTag) -> Option<PageDescriptor<'_>> {
         self
             .in_memory
             .get(tag)
-            .map(|kv| kv.value().to_owned())
+            .map(|kv| unsafe { PageDescriptor::from_raw_ptr(*kv.value()) })
     }
 
     /// Récupère un emplacement libre
@@ -312,50 +514,158 @@ impl BufferPool {
             })
     }
 
+    /// Rend un emplacement, retiré de la mémoire, à la liste des emplacements libres.
+    unsafe fn push_free(&self, desc: NonNull<PageDescriptorInner>) {
+        self.freelist.lock().unwrap().push(desc);
+        self.length.fetch_sub(1, Ordering::AcqRel);
+    }
+
+    /// Restitue les derniers blocs de mémoire dont tous les emplacements sont libres.
+    ///
+    /// Les pages non référencées d'un bloc sont d'abord déplacées vers des
+    /// emplacements libres des blocs précédents.
+    fn release_chunks(&self) {
+        let mut chunks = self.chunks.lock().unwrap();
+        let mut freelist = self.freelist.lock().unwrap();
+
+        while let Some(last) = chunks.last() {
+            let (mut outside, inside): (Vec<_>, Vec<_>) =
+                freelist.drain(..).partition(|ptr| !last.contains(*ptr));
+
+            let mut moved = Vec::default();
+
+            for mut kv in self.in_memory.iter_mut() {
+                let src = *kv.value();
+
+                if !last.contains(src) {
+                    continue;
+                }
+
+                unsafe {
+                    let inner = src.as_ref();
+                    if inner.get_ref_counter() > 0 || inner.rw_counter.load(Ordering::Acquire) != 0 {
+                        continue;
+                    }
+
+                    let Some(dest) = outside.pop() else {
+                        break;
+                    };
+
+                    let src = PageDescriptor::from_raw_ptr(src);
+                    let mut dest = PageDescriptor::from_raw_ptr(dest);
+                    dest.initialise(*src.tag());
+                    dest.set_flags(src.get_flags());
+                    std::ptr::copy_nonoverlapping(
+                        src.get_content_ptr().as_ref().as_ptr(),
+                        dest.get_content_ptr().as_mut().as_mut_ptr(),
+                        usize::from(self.page_size),
+                    );
+
+                    *kv.value_mut() = dest.get_raw_ptr();
+                    moved.push(src.get_raw_ptr());
+                }
+            }
+
+            freelist.extend(outside);
+
+            if inside.len() + moved.len() < last.used() {
+                freelist.extend(inside);
+                freelist.extend(moved);
+                break;
+            }
+
+            // Le bloc n'est plus référencé, on le restitue.
+            chunks.pop();
+        }
+    }
+
     /// Libère de la place :
     /// - soit en libérant une entrée du cache contenant une page propre ;
     /// - soit en déchargeant des pages quelque part (voir [IPagerStress]).
     ///
+    /// La page libérée est désignée par la politique de remplacement (voir [IReplacementPolicy]),
+    /// dans le respect des quotas des jars (voir [JarQuota]) :
+    /// - un jar ayant atteint sa limite ne peut évincer que ses propres pages ;
+    /// - les pages d'un jar ne peuvent être évincées par un autre jar en deçà de sa réservation ;
+    /// - les pages des jars de moindre priorité sont évincées en premier.
+    ///
     /// Si aucune page n'est libérable ou déchargeable, principalement car elles sont
     /// toutes empruntées, alors l'opération échoue et retourne l'erreur *CacheFull*.
-    fn manage_stress(&self) -> Result<PageDescriptor<'_>> {
+    fn manage_stress(&self, requester: Option<&JarTag>) -> Result<PageDescriptor<'_>> {
+        let requester = requester.map(|tag| tag.jar_id);
+
+        let capped = requester
+            .filter(|jar_id| self.jar_quota(*jar_id).is_capped(self.jar_len(*jar_id)));
+
+        // Le jar a atteint sa limite, il doit évincer ses propres pages.
+        if let Some(jar_id) = capped {
+            return self
+                .evict(&|tag| tag.jar_id == jar_id)?
+                .ok_or_else(|| Error::new(ErrorKind::BufferFull));
+        }
+
+        for priority in JarPriority::ASCENDING {
+            let in_class = |tag: &JarTag| {
+                let quota = self.jar_quota(tag.jar_id);
+
+                quota.priority == priority
+                    && (requester == Some(tag.jar_id) || self.jar_len(tag.jar_id) > quota.reserved)
+            };
+
+            if let Some(page) = self.evict(&in_class)? {
+                return Ok(page);
+            }
+        }
+
+        Err(Error::new(ErrorKind::BufferFull))
+    }
+
+    /// Évince une page parmi celles satisfaisant le prédicat, en privilégiant
+    /// les pages propres aux pages sales.
+    fn evict(&self, candidate: &dyn Fn(&JarTag) -> bool) -> Result<Option<PageDescriptor<'_>>> {
         // On trouve une page propre non empruntée
         let maybe_clean_unborrowed_page = self
-            .in_memory
-            .iter()
-            .map(|kv| kv.value().to_owned())
-            .map(|ptr| unsafe { PageDescriptor::from_raw_ptr(ptr) })
-            .filter(|page| !page.is_dirty() && page.get_ref_counter() <= 1)
-            .sorted_by_key(|page| page.get_use_counter())
-            .next();
+            .policy
+            .victim(&|tag| {
+                candidate(tag)
+                    && self
+                        .try_get_from_memory(tag)
+                        .is_some_and(|page| !page.is_dirty() && page.get_ref_counter() <= 1)
+            })
+            .and_then(|tag| self.try_get_from_memory(&tag));
 
         if let Some(cleaned) = maybe_clean_unborrowed_page {
             unsafe {
+                // La page propre n'est plus stockée nulle part dans le tampon.
+                self.stored.remove(cleaned.tag());
+                self.counters.clean_eviction(cleaned.tag());
                 self.remove_from_memory(cleaned.get_raw_ptr());
             }
-            return Ok(cleaned);
+            return Ok(Some(cleaned));
         }
 
         // on trouve une page sale non empruntée qu'on va devoir décharger
         let maybe_dirty_unborrowed_page = self
-            .in_memory
-            .iter()
-            .map(|kv| kv.value().to_owned())
-            .map(|ptr| unsafe { PageDescriptor::from_raw_ptr(ptr) })
-            .filter(|page| page.get_ref_counter() <= 1)
-            .sorted_by_key(|page| page.get_use_counter())
-            .next();
+            .policy
+            .victim(&|tag| {
+                candidate(tag)
+                    && self
+                        .try_get_from_memory(tag)
+                        .is_some_and(|page| page.get_ref_counter() <= 1)
+            })
+            .and_then(|tag| self.try_get_from_memory(&tag));
 
         // on va décharger une page en mémoire
         if let Some(dischargeable) = maybe_dirty_unborrowed_page {
             self.stress.discharge(&dischargeable)?;
+            self.counters.discharge(dischargeable.tag());
             unsafe {
                 self.remove_from_memory(dischargeable.get_raw_ptr());
             }
-            return Ok(dischargeable);
+            return Ok(Some(dischargeable));
         }
 
-        Err(Error::new(ErrorKind::BufferFull))
+        Ok(None)
     }
 
     fn is_in_memory(&self, tag: &JarTag) -> bool {
@@ -365,21 +675,39 @@ impl BufferPool {
     fn add_in_memory(&self, desc: NonNull<PageDescriptorInner>) {
         unsafe {
             self.in_memory.insert(desc.as_ref().tag, desc);
+            *self.jar_frames.entry(desc.as_ref().tag.jar_id).or_default() += 1;
+            self.policy.admit(&desc.as_ref().tag);
         }
     }
 
     unsafe fn remove_from_memory(&self, desc: NonNull<PageDescriptorInner>) {
         unsafe {
             self.in_memory.remove(&desc.as_ref().tag);
+            if let Some(mut frames) = self.jar_frames.get_mut(&desc.as_ref().tag.jar_id) {
+                *frames -= 1;
+            }
+            self.policy.forget(&desc.as_ref().tag);
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::tag::JarTag;
-
-    use super::{stress::stubs::StressStub, BufferPool, IBufferPool};
+    use std::{
+        sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use crate::{error::ErrorKind, tag::JarTag};
+
+    use super::{
+        quota::{JarPriority, JarQuota},
+        replacement::LruK,
+        stress::stubs::StressStub,
+        BufferPool, IBufferPool,
+    };
+    use crate::page::descriptor::PageDescriptorInner;
 
     #[test]
     pub fn test_alloc() {
@@ -389,4 +717,198 @@ mod tests {
 
         assert!(buf_pool.try_get_ref(tag).unwrap().is_some())
     }
+
+    #[test]
+    pub fn test_borrow_until_timeout() {
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let tag = &JarTag::in_jar(100).in_page(90);
+        let _page = buf_pool.alloc(tag).unwrap();
+
+        let deadline = Some(Instant::now() + Duration::from_millis(10));
+        let err = buf_pool.get_ref_until(tag, deadline).err().unwrap();
+
+        assert!(matches!(err.kind, ErrorKind::LockTimeout));
+    }
+
+    #[test]
+    pub fn test_borrow_until_released() {
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let tag = &JarTag::in_jar(100).in_page(90);
+        let page = buf_pool.alloc(tag).unwrap();
+
+        thread::scope(|scope| {
+            let waiter = scope.spawn(|| buf_pool.get_mut_until(tag, None).map(|page| page.is_some()));
+            thread::sleep(Duration::from_millis(50));
+            drop(page);
+            assert!(waiter.join().unwrap().unwrap());
+        });
+    }
+
+    #[test]
+    pub fn test_replacement_policy() {
+        let frame_size = 4096 + size_of::<PageDescriptorInner>();
+        let buf_pool = BufferPool::new(2 * frame_size, 4096, StressStub::default().into_boxed())
+            .with_replacement_policy(LruK::new(2).into_boxed());
+
+        let tags = (1..=3).map(|pid| JarTag::in_jar(100).in_page(pid)).collect::<Vec<_>>();
+        buf_pool.alloc(&tags[0]).unwrap().fill(1);
+        buf_pool.alloc(&tags[1]).unwrap().fill(2);
+
+        // La page 1 est accédée une seconde fois, la page 2 est déchargée.
+        drop(buf_pool.try_get_ref(&tags[0]).unwrap());
+        drop(buf_pool.alloc(&tags[2]).unwrap());
+
+        assert!(buf_pool.is_in_memory(&tags[0]));
+        assert!(!buf_pool.is_in_memory(&tags[1]));
+        assert!(buf_pool.contains(&tags[1]));
+
+        // Les pages empruntées ne sont jamais évincées.
+        let _p1 = buf_pool.try_get_ref(&tags[0]).unwrap();
+        let _p3 = buf_pool.try_get_ref(&tags[2]).unwrap();
+        assert!(buf_pool.try_get_ref(&tags[1]).is_err());
+    }
+
+    #[test]
+    pub fn test_stats() {
+        let frame_size = 4096 + size_of::<PageDescriptorInner>();
+        let buf_pool = BufferPool::new(2 * frame_size, 4096, StressStub::default().into_boxed());
+
+        let t1 = JarTag::in_jar(1).in_page(1);
+        let t2 = JarTag::in_jar(2).in_page(1);
+        let t3 = JarTag::in_jar(2).in_page(2);
+
+        buf_pool.alloc(&t1).unwrap().fill(1);
+        drop(buf_pool.alloc(&t2).unwrap());
+        drop(buf_pool.try_get_ref(&t1).unwrap());
+        // La page 2 est propre, elle est évincée.
+        buf_pool.alloc(&t3).unwrap().fill(3);
+        // Aucune page n'est propre, la page 1 est déchargée.
+        drop(buf_pool.alloc(&t2).unwrap());
+        assert!(buf_pool.try_get_ref(&JarTag::in_jar(3)).unwrap().is_none());
+
+        let stats = buf_pool.stats();
+        assert_eq!(stats.capacity, 2);
+        assert_eq!(stats.length, 2);
+        assert_eq!(stats.dirty, 1);
+        assert_eq!(stats.stressed, 1);
+        assert_eq!(stats.activity.hits, 1);
+        assert_eq!(stats.activity.misses, 1);
+        assert_eq!(stats.activity.clean_evictions, 1);
+        assert_eq!(stats.activity.discharges, 1);
+
+        let jar1 = &stats.jars[&1];
+        assert_eq!(jar1.in_memory, 0);
+        assert_eq!(jar1.stressed, 1);
+        assert_eq!(jar1.activity.discharges, 1);
+
+        let jar2 = &stats.jars[&2];
+        assert_eq!(jar2.in_memory, 2);
+        assert_eq!(jar2.activity.clean_evictions, 1);
+        assert_eq!(stats.jars[&3].activity.misses, 1);
+    }
+
+    #[test]
+    pub fn test_resize() {
+        let frame_size = 4096 + size_of::<PageDescriptorInner>();
+        let buf_pool = BufferPool::new(2 * frame_size, 4096, StressStub::default().into_boxed());
+        let tags = (1..=4).map(|pid| JarTag::in_jar(100).in_page(pid)).collect::<Vec<_>>();
+
+        buf_pool.alloc(&tags[0]).unwrap().fill(1);
+        buf_pool.alloc(&tags[1]).unwrap().fill(2);
+
+        buf_pool.resize(4 * frame_size).unwrap();
+        buf_pool.alloc(&tags[2]).unwrap().fill(3);
+        buf_pool.alloc(&tags[3]).unwrap().fill(4);
+        assert_eq!(buf_pool.chunks.lock().unwrap().len(), 2);
+        assert_eq!(buf_pool.stats().stressed, 0);
+
+        // Deux pages sont déchargées, le second bloc est restitué.
+        buf_pool.resize(2 * frame_size).unwrap();
+        assert_eq!(buf_pool.chunks.lock().unwrap().len(), 1);
+
+        let stats = buf_pool.stats();
+        assert_eq!(stats.capacity, 2);
+        assert_eq!(stats.length, 2);
+        assert_eq!(stats.stressed, 2);
+
+        for (i, tag) in tags.iter().enumerate() {
+            let page = buf_pool.try_get_ref(tag).unwrap().unwrap();
+            assert!(page.iter().all(|b| usize::from(*b) == i + 1));
+        }
+    }
+
+    #[test]
+    pub fn test_resize_while_reading() {
+        let frame_size = 4096 + size_of::<PageDescriptorInner>();
+        let buf_pool = BufferPool::new(frame_size, 4096, StressStub::default().into_boxed());
+        let filler = JarTag::in_jar(1).in_page(0);
+        let hot = |i: usize| JarTag::in_jar(2).in_page(u64::try_from(i).unwrap());
+        let published = AtomicUsize::new(0);
+        let done = AtomicBool::new(false);
+
+        drop(buf_pool.alloc(&filler).unwrap());
+
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    while !done.load(Ordering::Acquire) {
+                        for i in 0..published.load(Ordering::Acquire) {
+                            let page = buf_pool.try_get_ref(&hot(i)).unwrap().unwrap();
+                            assert!(page.iter().all(|b| usize::from(*b) == i % 256));
+                        }
+                    }
+                });
+            }
+
+            // Chaque page est allouée dans un nouveau bloc, puis déplacée vers
+            // l'emplacement libéré par la page de remplissage lors de la réduction.
+            for i in 0..200 {
+                let chunks = buf_pool.chunks.lock().unwrap().len();
+
+                buf_pool.resize((i + 2) * frame_size).unwrap();
+                buf_pool.alloc(&hot(i)).unwrap().fill(u8::try_from(i % 256).unwrap());
+                published.store(i + 1, Ordering::Release);
+                buf_pool.discard(&filler).unwrap();
+
+                // La page ne peut être déplacée tant qu'elle est lue.
+                while buf_pool.chunks.lock().unwrap().len() > chunks {
+                    buf_pool.resize((i + 2) * frame_size).unwrap();
+                    thread::yield_now();
+                }
+
+                drop(buf_pool.alloc(&filler).unwrap());
+            }
+
+            done.store(true, Ordering::Release);
+        });
+    }
+
+    #[test]
+    pub fn test_jar_quotas() {
+        let frame_size = 4096 + size_of::<PageDescriptorInner>();
+        let buf_pool = BufferPool::new(3 * frame_size, 4096, StressStub::default().into_boxed());
+
+        buf_pool.set_jar_quota(1, JarQuota::default().reserved(2).priority(JarPriority::High));
+        buf_pool.set_jar_quota(3, JarQuota::default().max(1));
+
+        drop(buf_pool.alloc(&JarTag::in_jar(1).in_page(1)).unwrap());
+        drop(buf_pool.alloc(&JarTag::in_jar(1).in_page(2)).unwrap());
+
+        // Le jar 2 ne peut évincer les pages réservées du jar 1.
+        for pid in 1..=3 {
+            drop(buf_pool.alloc(&JarTag::in_jar(2).in_page(pid)).unwrap());
+        }
+
+        assert_eq!(buf_pool.jar_len(1), 2);
+        assert_eq!(buf_pool.jar_len(2), 1);
+
+        // Le jar 3 est limité à une page, il évince la sienne.
+        drop(buf_pool.alloc(&JarTag::in_jar(3).in_page(1)).unwrap());
+        drop(buf_pool.alloc(&JarTag::in_jar(3).in_page(2)).unwrap());
+
+        assert_eq!(buf_pool.jar_len(1), 2);
+        assert_eq!(buf_pool.jar_len(2), 0);
+        assert_eq!(buf_pool.jar_len(3), 1);
+        assert!(buf_pool.is_in_memory(&JarTag::in_jar(3).in_page(2)));
+    }
 }
@@ -0,0 +1,55 @@
+//! Quotas de mémoire des jars dans le tampon partagé.
+
+/// Classe de priorité d'un jar.
+///
+/// En cas de stress mémoire, les pages des jars de moindre priorité
+/// sont évincées en premier.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum JarPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl JarPriority {
+    /// Les classes de priorité, de la moins prioritaire à la plus prioritaire.
+    pub const ASCENDING: [Self; 3] = [Self::Low, Self::Normal, Self::High];
+}
+
+/// Quota de mémoire d'un jar dans le tampon.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct JarQuota {
+    /// Nombre d'emplacements réservés au jar.
+    ///
+    /// Les autres jars ne peuvent évincer ses pages en deçà de ce nombre.
+    pub reserved: usize,
+    /// Nombre maximal d'emplacements occupés par le jar.
+    ///
+    /// Au-delà, le jar doit évincer ses propres pages pour en charger de nouvelles.
+    pub max: Option<usize>,
+    /// Classe de priorité du jar
+    pub priority: JarPriority,
+}
+
+impl JarQuota {
+    pub fn reserved(mut self, reserved: usize) -> Self {
+        self.reserved = reserved;
+        self
+    }
+
+    pub fn max(mut self, max: usize) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    pub fn priority(mut self, priority: JarPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Vérifie si le jar a atteint son nombre maximal d'emplacements.
+    pub fn is_capped(&self, frames: usize) -> bool {
+        self.max.is_some_and(|max| frames >= max)
+    }
+}
@@ -0,0 +1,557 @@
+//! Politiques de remplacement des pages du tampon.
+//!
+//! Lorsque le tampon est plein, la politique désigne la page à évincer
+//! parmi celles qui sont en mémoire. Le tampon la tient informée des
+//! admissions, des accès et des retraits de pages.
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+
+use crate::tag::JarTag;
+
+/// Politique de remplacement des pages du tampon.
+///
+/// Le prédicat passé à [IReplacementPolicy::victim] indique si une page
+/// peut être évincée (non épinglée, et selon la passe, propre) ; la politique
+/// ne désigne jamais une page qui ne le satisfait pas.
+pub trait IReplacementPolicy {
+    /// Une page vient d'être chargée en mémoire.
+    fn admit(&self, tag: &JarTag);
+    /// Une page en mémoire vient d'être accédée.
+    fn touch(&self, tag: &JarTag);
+    /// Une page a été retirée de la mémoire.
+    fn forget(&self, tag: &JarTag);
+    /// Désigne une page à évincer parmi celles satisfaisant le prédicat.
+    fn victim(&self, evictable: &dyn Fn(&JarTag) -> bool) -> Option<JarTag>;
+}
+
+pub type ReplacementPolicy = Box<dyn IReplacementPolicy>;
+
+/// Algorithme de l'horloge (seconde chance).
+///
+/// Les pages sont disposées en anneau ; l'aiguille efface le bit de
+/// référence des pages qu'elle croise et s'arrête sur la première page
+/// non référencée évinçable.
+#[derive(Default)]
+pub struct Clock(Mutex<ClockInner>);
+
+#[derive(Default)]
+struct ClockInner {
+    ring: Vec<Option<ClockSlot>>,
+    slots: HashMap<JarTag, usize>,
+    free: Vec<usize>,
+    hand: usize,
+}
+
+struct ClockSlot {
+    tag: JarTag,
+    referenced: bool,
+}
+
+impl Clock {
+    pub fn into_boxed(self) -> ReplacementPolicy {
+        Box::new(self)
+    }
+}
+
+impl IReplacementPolicy for Clock {
+    fn admit(&self, tag: &JarTag) {
+        let mut inner = self.0.lock().unwrap();
+
+        if let Some(&slot) = inner.slots.get(tag) {
+            inner.ring[slot].as_mut().unwrap().referenced = true;
+            return;
+        }
+
+        let slot = ClockSlot {
+            tag: *tag,
+            referenced: true,
+        };
+
+        let idx = match inner.free.pop() {
+            Some(idx) => {
+                inner.ring[idx] = Some(slot);
+                idx
+            }
+            None => {
+                inner.ring.push(Some(slot));
+                inner.ring.len() - 1
+            }
+        };
+
+        inner.slots.insert(*tag, idx);
+    }
+
+    fn touch(&self, tag: &JarTag) {
+        let mut inner = self.0.lock().unwrap();
+        if let Some(&slot) = inner.slots.get(tag) {
+            inner.ring[slot].as_mut().unwrap().referenced = true;
+        }
+    }
+
+    fn forget(&self, tag: &JarTag) {
+        let mut inner = self.0.lock().unwrap();
+        if let Some(slot) = inner.slots.remove(tag) {
+            inner.ring[slot] = None;
+            inner.free.push(slot);
+        }
+    }
+
+    fn victim(&self, evictable: &dyn Fn(&JarTag) -> bool) -> Option<JarTag> {
+        let mut inner = self.0.lock().unwrap();
+        let len = inner.ring.len();
+
+        // Deux tours suffisent : le premier efface les bits de référence.
+        for _ in 0..2 * len {
+            let hand = inner.hand;
+            inner.hand = (hand + 1) % len;
+
+            // Les pages non évinçables conservent leur bit de référence.
+            if let Some(slot) = inner.ring[hand].as_mut().filter(|slot| evictable(&slot.tag)) {
+                if !slot.referenced {
+                    return Some(slot.tag);
+                }
+                slot.referenced = false;
+            }
+        }
+
+        None
+    }
+}
+
+/// Algorithme LRU-K.
+///
+/// Évince la page dont le K-ième accès le plus récent est le plus ancien ;
+/// les pages accédées moins de K fois passent en premier, par ordre de
+/// dernier accès.
+///
+/// Chaque accès est consigné dans un journal chronologique ; le K-ième
+/// accès d'une page y figure donc déjà à sa place quand il le devient,
+/// et l'ordre d'éviction est celui des entrées du journal. Comme pour
+/// [TwoQ], les files sont à suppression paresseuse : les opérations sont
+/// en O(1) amorti.
+pub struct LruK(Mutex<LruKInner>);
+
+struct LruKInner {
+    k: usize,
+    clock: u64,
+    /// K derniers accès de chaque page en mémoire.
+    history: HashMap<JarTag, VecDeque<u64>>,
+    /// Journal des accès, par ordre chronologique.
+    ///
+    /// Une entrée antérieure au K-ième accès de sa page est périmée.
+    accesses: VecDeque<(JarTag, u64)>,
+    /// Dernier accès des pages accédées moins de K fois.
+    infants: VecDeque<(JarTag, u64)>,
+}
+
+impl Default for LruK {
+    fn default() -> Self {
+        Self::new(2)
+    }
+}
+
+impl LruK {
+    pub fn new(k: usize) -> Self {
+        assert!(k > 0, "LRU-K requires k > 0");
+        Self(Mutex::new(LruKInner {
+            k,
+            clock: 0,
+            history: Default::default(),
+            accesses: Default::default(),
+            infants: Default::default(),
+        }))
+    }
+
+    pub fn into_boxed(self) -> ReplacementPolicy {
+        Box::new(self)
+    }
+}
+
+impl LruKInner {
+    /// L'entrée est le dernier accès d'une page accédée moins de K fois.
+    fn is_infant(&self, tag: &JarTag, at: u64) -> bool {
+        self.history
+            .get(tag)
+            .is_some_and(|history| history.len() < self.k && history.back() == Some(&at))
+    }
+
+    /// L'entrée est le K-ième accès le plus récent de sa page.
+    fn is_kth(&self, tag: &JarTag, at: u64) -> bool {
+        self.history
+            .get(tag)
+            .is_some_and(|history| history.len() == self.k && history[0] == at)
+    }
+
+    /// L'entrée ne sera plus jamais le K-ième accès de sa page.
+    fn is_stale(&self, tag: &JarTag, at: u64) -> bool {
+        self.history.get(tag).is_none_or(|history| at < history[0])
+    }
+
+    fn record(&mut self, tag: &JarTag) {
+        self.clock += 1;
+        let now = self.clock;
+
+        let history = self.history.entry(*tag).or_default();
+        history.push_back(now);
+        if history.len() > self.k {
+            history.pop_front();
+        }
+
+        if history.len() < self.k {
+            self.infants.push_back((*tag, now));
+        }
+        self.accesses.push_back((*tag, now));
+
+        self.compact();
+    }
+
+    /// Purge les entrées périmées quand elles deviennent majoritaires.
+    fn compact(&mut self) {
+        let live = self.history.len();
+        if self.accesses.len() + self.infants.len() <= 2 * (self.k + 1) * live + 16 {
+            return;
+        }
+
+        let accesses = std::mem::take(&mut self.accesses);
+        self.accesses = accesses
+            .into_iter()
+            .filter(|(tag, at)| !self.is_stale(tag, *at))
+            .collect();
+
+        let infants = std::mem::take(&mut self.infants);
+        self.infants = infants
+            .into_iter()
+            .filter(|(tag, at)| self.is_infant(tag, *at))
+            .collect();
+    }
+
+    fn victim(&mut self, evictable: &dyn Fn(&JarTag) -> bool) -> Option<JarTag> {
+        while let Some(&(tag, at)) = self.infants.front() {
+            if self.is_infant(&tag, at) {
+                break;
+            }
+            self.infants.pop_front();
+        }
+
+        while let Some(&(tag, at)) = self.accesses.front() {
+            if !self.is_stale(&tag, at) {
+                break;
+            }
+            self.accesses.pop_front();
+        }
+
+        self.infants
+            .iter()
+            .find(|(tag, at)| self.is_infant(tag, *at) && evictable(tag))
+            .or_else(|| {
+                self.accesses
+                    .iter()
+                    .find(|(tag, at)| self.is_kth(tag, *at) && evictable(tag))
+            })
+            .map(|(tag, _)| *tag)
+    }
+}
+
+impl IReplacementPolicy for LruK {
+    fn admit(&self, tag: &JarTag) {
+        self.0.lock().unwrap().record(tag);
+    }
+
+    fn touch(&self, tag: &JarTag) {
+        let mut inner = self.0.lock().unwrap();
+        if inner.history.contains_key(tag) {
+            inner.record(tag);
+        }
+    }
+
+    fn forget(&self, tag: &JarTag) {
+        self.0.lock().unwrap().history.remove(tag);
+    }
+
+    fn victim(&self, evictable: &dyn Fn(&JarTag) -> bool) -> Option<JarTag> {
+        self.0.lock().unwrap().victim(evictable)
+    }
+}
+
+/// Algorithme 2Q.
+///
+/// Les pages admises entrent dans une file FIFO (A1in). Évincées de cette
+/// file, elles laissent une trace (A1out) ; une page réadmise alors qu'elle
+/// est tracée rejoint la file LRU des pages chaudes (Am).
+pub struct TwoQ(Mutex<TwoQInner>);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TwoQQueue {
+    A1In,
+    Am,
+}
+
+struct TwoQInner {
+    /// Taille cible de la file A1in
+    kin: usize,
+    /// Taille maximale de la trace A1out
+    kout: usize,
+    stamp: u64,
+    /// File et estampille courante de chaque page en mémoire.
+    ///
+    /// Les files sont à suppression paresseuse : une entrée dont
+    /// l'estampille diffère de celle-ci est périmée.
+    entries: HashMap<JarTag, (TwoQQueue, u64)>,
+    a1in: VecDeque<(JarTag, u64)>,
+    /// Nombre de pages vivantes dans A1in
+    a1in_len: usize,
+    am: VecDeque<(JarTag, u64)>,
+    a1out: VecDeque<JarTag>,
+    ghosts: HashMap<JarTag, usize>,
+}
+
+impl Default for TwoQ {
+    fn default() -> Self {
+        Self::new(64, 256)
+    }
+}
+
+impl TwoQ {
+    /// Crée une politique 2Q avec une file A1in de taille `kin`
+    /// et une trace A1out de taille `kout`.
+    pub fn new(kin: usize, kout: usize) -> Self {
+        Self(Mutex::new(TwoQInner {
+            kin,
+            kout,
+            stamp: 0,
+            entries: Default::default(),
+            a1in: Default::default(),
+            a1in_len: 0,
+            am: Default::default(),
+            a1out: Default::default(),
+            ghosts: Default::default(),
+        }))
+    }
+
+    pub fn into_boxed(self) -> ReplacementPolicy {
+        Box::new(self)
+    }
+}
+
+impl TwoQInner {
+    fn push(&mut self, tag: &JarTag, queue: TwoQQueue) {
+        self.stamp += 1;
+        let stamp = self.stamp;
+        self.entries.insert(*tag, (queue, stamp));
+
+        match queue {
+            TwoQQueue::A1In => {
+                self.a1in.push_back((*tag, stamp));
+                self.a1in_len += 1;
+            }
+            TwoQQueue::Am => self.am.push_back((*tag, stamp)),
+        }
+
+        self.compact();
+    }
+
+    /// Purge les entrées périmées quand elles deviennent majoritaires.
+    fn compact(&mut self) {
+        let live = self.entries.len();
+        if self.a1in.len() + self.am.len() <= 2 * live + 16 {
+            return;
+        }
+
+        let entries = &self.entries;
+        self.a1in
+            .retain(|(tag, stamp)| entries.get(tag).is_some_and(|e| e.1 == *stamp));
+        self.am
+            .retain(|(tag, stamp)| entries.get(tag).is_some_and(|e| e.1 == *stamp));
+    }
+
+    fn remember(&mut self, tag: &JarTag) {
+        self.a1out.push_back(*tag);
+        *self.ghosts.entry(*tag).or_default() += 1;
+
+        while self.a1out.len() > self.kout {
+            let old = self.a1out.pop_front().unwrap();
+            if let Some(count) = self.ghosts.get_mut(&old) {
+                *count -= 1;
+                if *count == 0 {
+                    self.ghosts.remove(&old);
+                }
+            }
+        }
+    }
+
+    /// Parcourt une file à la recherche d'une victime.
+    ///
+    /// Les entrées périmées sont retirées, les pages non évinçables
+    /// sont remises en fin de file.
+    fn scan(
+        &mut self,
+        queue: TwoQQueue,
+        evictable: &dyn Fn(&JarTag) -> bool,
+    ) -> Option<JarTag> {
+        let len = match queue {
+            TwoQQueue::A1In => self.a1in.len(),
+            TwoQQueue::Am => self.am.len(),
+        };
+
+        for _ in 0..len {
+            let (tag, stamp) = match queue {
+                TwoQQueue::A1In => self.a1in.pop_front(),
+                TwoQQueue::Am => self.am.pop_front(),
+            }?;
+
+            if self.entries.get(&tag) != Some(&(queue, stamp)) {
+                continue;
+            }
+
+            match queue {
+                TwoQQueue::A1In => self.a1in.push_back((tag, stamp)),
+                TwoQQueue::Am => self.am.push_back((tag, stamp)),
+            }
+
+            if evictable(&tag) {
+                return Some(tag);
+            }
+        }
+
+        None
+    }
+}
+
+impl IReplacementPolicy for TwoQ {
+    fn admit(&self, tag: &JarTag) {
+        let mut inner = self.0.lock().unwrap();
+
+        if inner.entries.contains_key(tag) {
+            return;
+        }
+
+        if inner.ghosts.contains_key(tag) {
+            inner.push(tag, TwoQQueue::Am);
+        } else {
+            inner.push(tag, TwoQQueue::A1In);
+        }
+    }
+
+    fn touch(&self, tag: &JarTag) {
+        let mut inner = self.0.lock().unwrap();
+
+        // Les accès répétés dans A1in sont considérés corrélés.
+        if let Some((TwoQQueue::Am, _)) = inner.entries.get(tag) {
+            inner.push(tag, TwoQQueue::Am);
+        }
+    }
+
+    fn forget(&self, tag: &JarTag) {
+        let mut inner = self.0.lock().unwrap();
+
+        if let Some((TwoQQueue::A1In, _)) = inner.entries.remove(tag) {
+            inner.a1in_len -= 1;
+            inner.remember(tag);
+        }
+    }
+
+    fn victim(&self, evictable: &dyn Fn(&JarTag) -> bool) -> Option<JarTag> {
+        let mut inner = self.0.lock().unwrap();
+
+        let order = if inner.a1in_len > inner.kin || inner.am.is_empty() {
+            [TwoQQueue::A1In, TwoQQueue::Am]
+        } else {
+            [TwoQQueue::Am, TwoQQueue::A1In]
+        };
+
+        order
+            .into_iter()
+            .find_map(|queue| inner.scan(queue, evictable))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tag::JarTag;
+
+    use super::{Clock, IReplacementPolicy, LruK, TwoQ};
+
+    fn tag(page_id: u64) -> JarTag {
+        JarTag::in_jar(0).in_page(page_id)
+    }
+
+    #[test]
+    fn test_clock_second_chance() {
+        let policy = Clock::default();
+        (0..3).map(tag).for_each(|t| policy.admit(&t));
+
+        // Premier tour : tous les bits sont effacés, la page 0 est désignée.
+        assert_eq!(policy.victim(&|_| true), Some(tag(0)));
+
+        policy.touch(&tag(1));
+        assert_eq!(policy.victim(&|_| true), Some(tag(2)));
+
+        // Les pages épinglées ne sont jamais désignées.
+        assert_eq!(policy.victim(&|t| *t == tag(1)), Some(tag(1)));
+        assert_eq!(policy.victim(&|_| false), None);
+    }
+
+    #[test]
+    fn test_clock_keeps_rejected_bits() {
+        let policy = Clock::default();
+        (0..2).map(tag).for_each(|t| policy.admit(&t));
+
+        // La page 0 n'est pas candidate : son bit de référence est conservé.
+        assert_eq!(policy.victim(&|t| *t == tag(1)), Some(tag(1)));
+        assert_eq!(policy.victim(&|_| true), Some(tag(1)));
+    }
+
+    #[test]
+    fn test_lru_k() {
+        let policy = LruK::new(2);
+        (0..3).map(tag).for_each(|t| policy.admit(&t));
+        policy.touch(&tag(0));
+        policy.touch(&tag(1));
+
+        // La page 2 n'a été accédée qu'une fois.
+        assert_eq!(policy.victim(&|_| true), Some(tag(2)));
+        policy.forget(&tag(2));
+
+        assert_eq!(policy.victim(&|_| true), Some(tag(0)));
+        assert_eq!(policy.victim(&|t| *t != tag(0)), Some(tag(1)));
+    }
+
+    #[test]
+    fn test_lru_k_order() {
+        let policy = LruK::new(2);
+        (0..3).map(tag).for_each(|t| policy.admit(&t));
+        policy.touch(&tag(1));
+        policy.touch(&tag(2));
+
+        // Suffisamment d'accès pour déclencher la purge du journal.
+        (0..100).for_each(|_| policy.touch(&tag(0)));
+        assert_eq!(policy.victim(&|_| true), Some(tag(1)));
+
+        // L'avant-dernier accès de la page 1 devient plus récent que celui de la page 2.
+        policy.touch(&tag(1));
+        assert_eq!(policy.victim(&|_| true), Some(tag(2)));
+        policy.touch(&tag(2));
+        assert_eq!(policy.victim(&|_| true), Some(tag(1)));
+        assert_eq!(policy.victim(&|t| *t != tag(1) && *t != tag(2)), Some(tag(0)));
+    }
+
+    #[test]
+    fn test_two_q() {
+        let policy = TwoQ::new(1, 4);
+        (0..3).map(tag).for_each(|t| policy.admit(&t));
+
+        // A1in dépasse sa taille cible, on évince en FIFO.
+        assert_eq!(policy.victim(&|_| true), Some(tag(0)));
+        policy.forget(&tag(0));
+
+        // La page 0 est tracée : réadmise, elle devient chaude.
+        policy.admit(&tag(0));
+        assert_eq!(policy.victim(&|_| true), Some(tag(1)));
+        policy.forget(&tag(1));
+        policy.forget(&tag(2));
+
+        assert_eq!(policy.victim(&|_| true), Some(tag(0)));
+    }
+}
@@ -0,0 +1,131 @@
+//! Métriques du tampon de pages.
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use dashmap::DashMap;
+
+use crate::tag::{JarId, JarTag};
+
+/// Compteurs d'activité du tampon.
+#[derive(Default)]
+pub(super) struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    clean_evictions: AtomicU64,
+    discharges: AtomicU64,
+}
+
+impl Counters {
+    fn snapshot(&self) -> ActivityStats {
+        ActivityStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            clean_evictions: self.clean_evictions.load(Ordering::Relaxed),
+            discharges: self.discharges.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Compteurs d'activité du tampon, globaux et par jar.
+#[derive(Default)]
+pub(super) struct BufferPoolCounters {
+    global: Counters,
+    jars: DashMap<JarId, Counters>,
+}
+
+impl BufferPoolCounters {
+    fn record(&self, tag: &JarTag, counter: impl Fn(&Counters) -> &AtomicU64) {
+        counter(&self.global).fetch_add(1, Ordering::Relaxed);
+        counter(&self.jars.entry(tag.jar_id).or_default()).fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// La page demandée était en mémoire.
+    pub fn hit(&self, tag: &JarTag) {
+        self.record(tag, |c| &c.hits)
+    }
+
+    /// La page demandée n'était pas en mémoire.
+    pub fn miss(&self, tag: &JarTag) {
+        self.record(tag, |c| &c.misses)
+    }
+
+    /// Une page propre a été évincée.
+    pub fn clean_eviction(&self, tag: &JarTag) {
+        self.record(tag, |c| &c.clean_evictions)
+    }
+
+    /// Une page sale a été déchargée (voir [super::stress::IBufferStressStrategy]).
+    pub fn discharge(&self, tag: &JarTag) {
+        self.record(tag, |c| &c.discharges)
+    }
+
+    pub fn global(&self) -> ActivityStats {
+        self.global.snapshot()
+    }
+
+    pub fn jars(&self) -> HashMap<JarId, ActivityStats> {
+        self.jars
+            .iter()
+            .map(|kv| (*kv.key(), kv.value().snapshot()))
+            .collect()
+    }
+}
+
+/// Activité cumulée du tampon depuis sa création.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ActivityStats {
+    /// Nombre d'accès à une page en mémoire
+    pub hits: u64,
+    /// Nombre d'accès à une page absente de la mémoire
+    pub misses: u64,
+    /// Nombre de pages propres évincées
+    pub clean_evictions: u64,
+    /// Nombre de pages sales déchargées
+    pub discharges: u64,
+}
+
+impl ActivityStats {
+    /// Proportion des accès servis depuis la mémoire.
+    pub fn hit_ratio(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / total as f64
+    }
+
+    /// Nombre total d'évictions.
+    pub fn evictions(&self) -> u64 {
+        self.clean_evictions + self.discharges
+    }
+}
+
+/// Occupation du tampon par un jar.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct JarStats {
+    pub activity: ActivityStats,
+    /// Nombre de pages en mémoire
+    pub in_memory: usize,
+    /// Nombre de pages sales en mémoire
+    pub dirty: usize,
+    /// Nombre de pages déchargées
+    pub stressed: usize,
+}
+
+/// Instantané de l'état du tampon.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BufferPoolStats {
+    pub activity: ActivityStats,
+    /// Nombre de pages en mémoire
+    pub length: usize,
+    /// Nombre de pages que la mémoire du tampon peut contenir
+    pub capacity: usize,
+    /// Nombre de pages sales en mémoire
+    pub dirty: usize,
+    /// Nombre de pages déchargées
+    pub stressed: usize,
+    /// Ventilation par jar
+    pub jars: HashMap<JarId, JarStats>,
+}
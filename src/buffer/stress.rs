@@ -4,12 +4,13 @@ use std::{
     sync::Mutex,
 };
 
-use byteorder::{ReadBytesExt, WriteBytesExt};
 use dashmap::DashMap;
 
 use crate::{
+    compression::{self, FrameSpace},
+    crypto::{self, EncryptionKey, NONCE_SIZE, TAG_SIZE},
     fs::{FileOpenOptions, FilePtr, IFileSystem},
-    page::{descriptor::PageDescriptor, PageSize},
+    page::descriptor::PageDescriptor,
     result::Result,
     tag::JarTag,
 };
@@ -26,70 +27,121 @@ pub trait IBufferStressStrategy {
     fn retrieve(&self, dest: &mut PageDescriptor<'_>) -> Result<()>;
     /// Vérifie si la page est déchargée.
     fn contains(&self, tag: &JarTag) -> bool;
+    /// Oublie une page déchargée, sans la récupérer.
+    fn remove(&self, tag: &JarTag) -> Result<()>;
+    /// Liste les pages déchargées.
+    fn discharged(&self) -> Vec<JarTag>;
 }
 
 pub type BufferStressStrategy = Box<dyn IBufferStressStrategy>;
 
 /// Gestion du stress mémoire du système de pagination
 /// par décharge via un système de fichier (cf [IFileSystem]).
+///
+/// Les pages sont compressées (cf [crate::compression]) et stockées dans des
+/// emplacements de taille variable : un octet de drapeaux suivi de la page compressée.
+///
+/// Si une clé est fournie, la page compressée est chiffrée (cf [crate::crypto]),
+/// et précédée de son nonce et de son code d'authentification.
 pub struct FsPagerStress<Fs: IFileSystem> {
     /// Pointeur vers le fichier responsable de stocker les données déchargées
     file: FilePtr<Fs>,
-    /// Taille d'une page
-    page_size: PageSize,
-    /// Pages stockées sous la forme pager's pid vers stress's pid.
-    pages: DashMap<JarTag, usize>,
-    /// Espaces libres
-    freelist: Mutex<Vec<usize>>,
+    /// Pages stockées sous la forme pager's pid vers emplacement dans le fichier.
+    pages: DashMap<JarTag, StressSlot>,
+    /// Espace occupé par les pages déchargées
+    space: Mutex<FrameSpace>,
+    /// Clé de chiffrement des pages déchargées
+    key: Option<EncryptionKey>,
+}
+
+/// Emplacement d'une page déchargée
+#[derive(Clone, Copy)]
+struct StressSlot {
+    offset: u64,
+    len: u64,
 }
 
 impl<Fs: IFileSystem> FsPagerStress<Fs> {
-    pub fn new<Path: Into<Fs::Path>>(fs: Fs, path: Path, page_size: PageSize) -> Self {
+    pub fn new<Path: Into<Fs::Path>>(fs: Fs, path: Path) -> Self {
         let file = FilePtr::new(fs, path);
 
         Self {
             file,
-            page_size,
             pages: Default::default(),
-            freelist: Default::default(),
+            space: Default::default(),
+            key: None,
         }
     }
+
+    /// Chiffre les pages déchargées.
+    pub fn with_encryption(mut self, key: EncryptionKey) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    /// Taille occupée par les pages déchargées dans le fichier.
+    pub fn used_space(&self) -> u64 {
+        self.space.lock().unwrap().used()
+    }
+}
+
+/// Données associées d'un emplacement chiffré : la page et ses drapeaux, stockés en clair.
+fn slot_aad(tag: &JarTag, flags: u8) -> [u8; 17] {
+    let mut aad = [flags; 17];
+    aad[0..16].copy_from_slice(&crypto::tag_aad(tag));
+    aad
 }
 
 impl<Fs: IFileSystem> IBufferStressStrategy for FsPagerStress<Fs> {
     fn discharge(&self, src: &PageDescriptor<'_>) -> Result<()> {
-        let id = self
-            .freelist
-            .lock()
-            .unwrap()
-            .pop()
-            .unwrap_or_else(|| self.pages.len());
+        let mut content = unsafe { compression::compress(src.get_content_ptr().as_ref()) };
+        let mut slot = vec![src.get_flags()];
+
+        if let Some(key) = &self.key {
+            let nonce = crypto::random_nonce();
+            let tag = crypto::seal(key, &nonce, &slot_aad(src.tag(), src.get_flags()), &mut content);
+            slot.extend_from_slice(&nonce);
+            slot.extend_from_slice(&tag);
+        }
+
+        slot.extend(content);
+
+        let len = u64::try_from(slot.len()).unwrap();
+        let offset = self.space.lock().unwrap().reserve(len);
 
         let mut file = self
             .file
             .open(FileOpenOptions::new().create(true).write(true))?;
 
-        let loc = u64::from(self.page_size) * u64::try_from(id).unwrap();
-        file.seek(std::io::SeekFrom::Start(loc))?;
-        file.write_u8(src.get_flags())?;
-        unsafe {
-            file.write_all(src.get_content_ptr().as_ref())?;
-        }
-        self.pages.insert(*src.tag(), id);
+        file.seek(std::io::SeekFrom::Start(offset))?;
+        file.write_all(&slot)?;
+        self.pages.insert(*src.tag(), StressSlot { offset, len });
 
         Ok(())
     }
 
     fn retrieve(&self, dest: &mut PageDescriptor<'_>) -> Result<()> {
-        let id = self.pages.get(dest.tag()).unwrap().to_owned();
+        let slot = *self.pages.get(dest.tag()).unwrap();
         let mut file = self.file.open(FileOpenOptions::new().read(true))?;
 
-        let addr = u64::from(self.page_size) * u64::try_from(id).unwrap();
-        file.seek(std::io::SeekFrom::Start(addr))?;
-        dest.set_flags(file.read_u8()?);
-        file.read_exact(dest.borrow_mut(true).deref_mut())?;
+        let mut buf = vec![0u8; usize::try_from(slot.len).unwrap()];
+        file.seek(std::io::SeekFrom::Start(slot.offset))?;
+        file.read_exact(&mut buf)?;
 
-        self.freelist.lock().unwrap().push(id);
+        let (flags, mut content) = buf.split_first_mut().unwrap();
+
+        if let Some(key) = &self.key {
+            let (header, data) = content.split_at_mut(NONCE_SIZE + TAG_SIZE);
+            let (nonce, tag) = header.split_at(NONCE_SIZE);
+            let (nonce, tag) = (nonce.try_into().unwrap(), tag.try_into().unwrap());
+            crypto::open(key, &nonce, &slot_aad(dest.tag(), *flags), data, &tag)?;
+            content = data;
+        }
+
+        compression::decompress(content, dest.borrow_mut(true).deref_mut())?;
+        dest.set_flags(*flags);
+
+        self.space.lock().unwrap().release(slot.offset, slot.len);
         self.pages.remove(dest.tag());
 
         Ok(())
@@ -98,6 +150,17 @@ impl<Fs: IFileSystem> IBufferStressStrategy for FsPagerStress<Fs> {
     fn contains(&self, tag: &JarTag) -> bool {
         self.pages.contains_key(tag)
     }
+
+    fn remove(&self, tag: &JarTag) -> Result<()> {
+        if let Some((_, slot)) = self.pages.remove(tag) {
+            self.space.lock().unwrap().release(slot.offset, slot.len);
+        }
+        Ok(())
+    }
+
+    fn discharged(&self) -> Vec<JarTag> {
+        self.pages.iter().map(|kv| *kv.key()).collect()
+    }
 }
 
 pub mod stubs {
@@ -133,21 +196,120 @@ pub mod stubs {
         }
 
         fn retrieve(&self, dest: &mut super::PageDescriptor<'_>) -> Result<()> {
-            let tag = dest.tag();
-            let buf = self.0.get(tag).unwrap();
+            let (_, buf) = self.0.remove(dest.tag()).unwrap();
             //println!("récupère {pid} {buf:?}");
             dest.borrow_mut(false).as_mut_bytes().write_all(&buf)?;
-
-            self.0.remove(dest.tag());
             Ok(())
         }
 
         fn contains(&self, tag: &JarTag) -> bool {
             self.0.contains_key(tag)
         }
+
+        fn remove(&self, tag: &JarTag) -> Result<()> {
+            self.0.remove(tag);
+            Ok(())
+        }
+
+        fn discharged(&self) -> Vec<JarTag> {
+            self.0.iter().map(|kv| *kv.key()).collect()
+        }
     }
 }
 
 #[cfg(test)]
-mod test {}
+mod test {
+    use std::{
+        io::{Read, Seek, Write},
+        rc::Rc,
+    };
+
+    use crate::{
+        buffer::{BufferPool, IBufferPool},
+        error::ErrorKind,
+        fs::{in_memory::InMemoryFs, FileOpenOptions, IFileSystem},
+        page::{descriptor::PageDescriptorInner, AsMutPageSlice, AsRefPageSlice},
+        tag::JarTag,
+    };
+
+    use super::FsPagerStress;
+
+    #[test]
+    fn test_compressed_discharge() {
+        let fs = Rc::new(InMemoryFs::default());
+        let stress = FsPagerStress::new(fs.clone(), "stress");
+        let frame_size = 4096 + size_of::<PageDescriptorInner>();
+        let buf_pool = BufferPool::new(2 * frame_size, 4096, Box::new(stress));
+
+        let tags = (1..=6).map(|pid| JarTag::in_jar(1).in_page(pid)).collect::<Vec<_>>();
+        for (i, tag) in tags.iter().enumerate() {
+            let mut page = buf_pool.alloc(tag).unwrap();
+            page.as_mut_bytes()[0..8].copy_from_slice(&(i as u64).to_le_bytes());
+        }
+        assert_eq!(buf_pool.stats().stressed, 4);
+
+        // Quatre pages presque vides tiennent dans bien moins qu'une page.
+        let size = fs
+            .open(&"stress".into(), FileOpenOptions::new().read(true))
+            .unwrap()
+            .seek(std::io::SeekFrom::End(0))
+            .unwrap();
+        assert!(size < 4096);
+
+        for (i, tag) in tags.iter().enumerate() {
+            let page = buf_pool.try_get_ref(tag).unwrap().unwrap();
+            assert!(page.is_dirty());
+            assert_eq!(page.as_bytes()[0..8], (i as u64).to_le_bytes());
+            assert!(page.as_bytes()[8..].iter().all(|b| *b == 0));
+        }
+    }
 
+    #[test]
+    fn test_encrypted_discharge() {
+        let fs = Rc::new(InMemoryFs::default());
+        let stress = FsPagerStress::new(fs.clone(), "stress").with_encryption([7u8; 32].into());
+        let frame_size = 4096 + size_of::<PageDescriptorInner>();
+        let buf_pool = BufferPool::new(frame_size, 4096, Box::new(stress));
+
+        let tags = (1..=2).map(|pid| JarTag::in_jar(1).in_page(pid)).collect::<Vec<_>>();
+        for tag in tags.iter() {
+            buf_pool.alloc(tag).unwrap().as_mut_bytes()[0..12].copy_from_slice(b"confidential");
+        }
+
+        let mut bytes = Vec::default();
+        fs.open(&"stress".into(), FileOpenOptions::new().read(true))
+            .unwrap()
+            .read_to_end(&mut bytes)
+            .unwrap();
+        assert!(!bytes.windows(12).any(|w| w == b"confidential"));
+
+        let page = buf_pool.try_get_ref(&tags[0]).unwrap().unwrap();
+        assert_eq!(&page.as_bytes()[0..12], b"confidential");
+    }
+
+    #[test]
+    fn test_encrypted_flags_tampering() {
+        let fs = Rc::new(InMemoryFs::default());
+        let stress = FsPagerStress::new(fs.clone(), "stress").with_encryption([7u8; 32].into());
+        let frame_size = 4096 + size_of::<PageDescriptorInner>();
+        let buf_pool = BufferPool::new(frame_size, 4096, Box::new(stress));
+
+        let tags = (1..=2).map(|pid| JarTag::in_jar(1).in_page(pid)).collect::<Vec<_>>();
+        for tag in tags.iter() {
+            buf_pool.alloc(tag).unwrap().fill(1);
+        }
+
+        // La page 1, sale, a été déchargée en tête du fichier : on efface ses drapeaux.
+        fs.open(&"stress".into(), FileOpenOptions::new().write(true))
+            .unwrap()
+            .write_all(&[0])
+            .unwrap();
+
+        let err = buf_pool.try_get_ref(&tags[0]).err().unwrap();
+        assert!(matches!(err.kind, ErrorKind::DecryptionFailed));
+
+        // La page ne doit pas être lue comme une page vierge au prochain accès.
+        let err = buf_pool.try_get_ref(&tags[0]).err().unwrap();
+        assert!(matches!(err.kind, ErrorKind::DecryptionFailed));
+    }
+}
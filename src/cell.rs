@@ -307,12 +307,10 @@ where
         self.as_meta().get_head()
     }
 
-    #[allow(dead_code)]
     fn free_head(&self) -> Option<CellId> {
         self.as_meta().get_free_head()
     }
 
-    #[allow(dead_code)]
     pub(crate) fn free_len(&self) -> CellCapacity {
         self.as_meta().free_len()
     }
@@ -321,6 +319,67 @@ where
         self.as_meta().get_tail()
     }
 
+    /// Vérifie la cohérence des listes chaînées des cellules allouées et libérées.
+    ///
+    /// Chaque cellule apparaît au plus une fois, dans une seule des listes, et
+    /// les longueurs des listes sont celles inscrites dans l'en-tête.
+    pub fn is_consistent(&self) -> bool {
+        let meta = self.as_meta();
+        let (base, size) = (usize::from(meta.cell_base), usize::from(meta.cell_size));
+
+        if size < size_of::<CellHeader>() || base + size * usize::from(meta.capacity) > self.0.as_bytes().len() {
+            return false;
+        }
+
+        let mut seen = vec![false; usize::from(self.capacity()) + 1];
+
+        let mut visit = |cid: CellId| {
+            let seen = &mut seen[usize::from(cid)];
+            !std::mem::replace(seen, true)
+        };
+
+        // La liste des cellules allouées est doublement chaînée.
+        let mut prev = None;
+        let mut current = self.head();
+        let mut len: CellCapacity = 0;
+
+        while let Some(cid) = current {
+            let Some(cell) = self.borrow_cell(&cid) else {
+                return false;
+            };
+
+            if !visit(cid) || cell.prev_sibling() != prev {
+                return false;
+            }
+
+            len += 1;
+            prev = Some(cid);
+            current = cell.next_sibling();
+        }
+
+        if prev != self.tail() || len != self.len() {
+            return false;
+        }
+
+        let mut current = self.free_head();
+        let mut free_len: CellCapacity = 0;
+
+        while let Some(cid) = current {
+            let Some(cell) = self.borrow_cell(&cid) else {
+                return false;
+            };
+
+            if !visit(cid) {
+                return false;
+            }
+
+            free_len += 1;
+            current = cell.next_sibling();
+        }
+
+        free_len == self.free_len()
+    }
+
     fn as_meta(&self) -> &CellsMeta {
         CellsMeta::ref_from_bytes(&self.0.as_ref()[CellsMeta::AREA]).unwrap()
     }
@@ -0,0 +1,579 @@
+//! Vérification de l'intégrité d'un jar, hors ligne.
+//!
+//! Toutes les pages sont parcourues afin de vérifier que :
+//! - leur type est connu, et les listes de cellules des noeuds d'arbre B+ sont cohérentes ;
+//! - chaque page est soit accessible depuis une racine, soit libre, et une seule fois ;
+//! - les listes chaînées de pages de débordement se terminent ;
+//! - le nombre de pages décrit correspond à la taille du fichier.
+//!
+//! Les racines sont les pages d'entrée des arbres B+, la liste des pages libres
+//! et la table des générations (voir [crate::pager::lsn]).
+use std::{collections::BTreeSet, fmt::Display, io::Read, path::Path};
+
+use zerocopy::FromBytes;
+
+use crate::{
+    arena::IArena,
+    bpt::{descriptor::BPTreeDescriptor, interior::BPlusTreeInterior, leaf::BPlusTreeLeaf},
+    buffer::{stress::stubs::StressStub, BufferPool},
+    cell::CellPage,
+    free::FreePage,
+    fs::{std::StdFs, FileOpenOptions, FilePtr, IFileSystem},
+    page::{AsRefPageSlice, PageId, PageKind, PageSize},
+    pager::{
+        lsn::LsnPage,
+        storage::{FsPagerStorage, IPagerStorage},
+        IPager, Pager, PagerDescription,
+    },
+    result::Result,
+    var::SpillPage,
+};
+
+/// Anomalie détectée dans un jar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// Le nombre de pages décrit ne correspond pas à la taille du fichier.
+    PageCountMismatch { described: u64, stored: u64 },
+    /// Le type de la page est inconnu.
+    InvalidPageKind { pid: PageId, kind: u8 },
+    /// Les listes de cellules de la page sont incohérentes.
+    InconsistentCells { pid: PageId },
+    /// Une page référence une page hors du jar.
+    DanglingReference { from: PageId, to: PageId },
+    /// Une page référencée n'est pas du type attendu.
+    UnexpectedPageKind { pid: PageId, got: u8 },
+    /// La page est référencée plusieurs fois.
+    MultiplyReferenced { pid: PageId },
+    /// La liste chaînée des pages de débordement boucle.
+    UnterminatedSpillChain { head: PageId },
+    /// La page n'est ni accessible depuis une racine, ni libre.
+    LeakedPage { pid: PageId },
+    /// Le nombre de pages libres décrit ne correspond pas à la liste des pages libres.
+    FreeCountMismatch { described: u64, counted: u64 },
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::PageCountMismatch { described, stored } => {
+                write!(f, "page count is {described}, but {stored} pages are stored")
+            }
+            Problem::InvalidPageKind { pid, kind } => write!(f, "page {pid} has invalid kind {kind}"),
+            Problem::InconsistentCells { pid } => write!(f, "page {pid} has inconsistent cell lists"),
+            Problem::DanglingReference { from, to } => {
+                write!(f, "page {from} references page {to}, which is out of the jar")
+            }
+            Problem::UnexpectedPageKind { pid, got } => {
+                write!(f, "page {pid} has unexpected kind {got}")
+            }
+            Problem::MultiplyReferenced { pid } => write!(f, "page {pid} is referenced more than once"),
+            Problem::UnterminatedSpillChain { head } => {
+                write!(f, "spill chain starting at page {head} does not terminate")
+            }
+            Problem::LeakedPage { pid } => write!(f, "page {pid} is neither reachable nor free"),
+            Problem::FreeCountMismatch { described, counted } => {
+                write!(f, "free count is {described}, but {counted} pages are free")
+            }
+        }
+    }
+}
+
+/// Résultat de la vérification d'un jar.
+#[derive(Debug, Default)]
+pub struct CheckReport {
+    /// Anomalies détectées
+    pub problems: Vec<Problem>,
+    /// Pages perdues remises dans la liste des pages libres (voir [repair_jar_in])
+    pub repaired: Vec<PageId>,
+}
+
+impl CheckReport {
+    /// Aucune anomalie n'a été détectée.
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Vérifie l'intégrité d'un fichier jar non chiffré.
+pub fn check_jar<P: AsRef<Path>>(path: P) -> Result<CheckReport> {
+    check_jar_in(StdFs, path.as_ref())
+}
+
+/// Vérifie l'intégrité d'un jar non chiffré stocké dans le système de fichier *fs*.
+pub fn check_jar_in<Fs, P>(fs: Fs, path: P) -> Result<CheckReport>
+where
+    Fs: IFileSystem + 'static,
+    P: Into<Fs::Path>,
+{
+    run(FilePtr::new(fs, path), false)
+}
+
+/// Vérifie l'intégrité d'un fichier jar non chiffré, et répare ses pages perdues
+/// (voir [repair_jar_in]).
+pub fn repair_jar<P: AsRef<Path>>(path: P) -> Result<CheckReport> {
+    repair_jar_in(StdFs, path.as_ref())
+}
+
+/// Vérifie l'intégrité d'un jar non chiffré stocké dans le système de fichier *fs*,
+/// et remet ses pages perdues dans la liste des pages libres.
+///
+/// Seules les pages dont la perte est certaine sont réparées : les noeuds d'arbre B+
+/// et les pages de la table des générations, qui ne peuvent être référencés que
+/// depuis les racines parcourues. Les autres pages inaccessibles, comme les pages de
+/// débordement des valeurs stockées hors des arbres, sont signalées
+/// (voir [Problem::LeakedPage]) mais laissées intactes.
+///
+/// Les pages perdues ne sont pas réparées si la liste des pages libres est elle-même corrompue.
+pub fn repair_jar_in<Fs, P>(fs: Fs, path: P) -> Result<CheckReport>
+where
+    Fs: IFileSystem + 'static,
+    P: Into<Fs::Path>,
+{
+    run(FilePtr::new(fs, path), true)
+}
+
+/// Nombre de pages tamponnées pendant la vérification
+const CHECK_POOL_PAGES: usize = 256;
+
+fn run<Fs: IFileSystem + 'static>(file: FilePtr<Fs>, repair: bool) -> Result<CheckReport> {
+    let mut page_size = [0u8; size_of::<PageSize>()];
+    file.open(FileOpenOptions::new().read(true))?.read_exact(&mut page_size)?;
+    let page_size = PageSize::read_from_bytes(&page_size).unwrap();
+
+    let storage = FsPagerStorage::new(file.fs, file.path, page_size);
+    let stored = storage.len()?;
+
+    let pool = BufferPool::new(
+        CHECK_POOL_PAGES * usize::from(page_size),
+        page_size,
+        StressStub::default().into_boxed(),
+    );
+    let pager = Pager::open(0, &pool, storage.into_boxed())?;
+
+    let mut checker = Checker::new(&pager, stored)?;
+    checker.check()?;
+
+    let mut report = CheckReport::default();
+
+    if repair && checker.free_list_ok {
+        for problem in checker.problems.iter() {
+            if let Problem::LeakedPage { pid } = problem {
+                if checker.is_surely_leaked(*pid)? {
+                    report.repaired.push(*pid);
+                }
+            }
+        }
+
+        for pid in report.repaired.iter() {
+            pager.delete_element(&pager.tag().in_page(*pid))?;
+        }
+
+        pager.flush()?;
+    }
+
+    report.problems = checker.problems;
+    Ok(report)
+}
+
+/// Parcours des pages d'un jar
+struct Checker<'a, 'buf> {
+    pager: &'a Pager<'buf>,
+    /// Nombre de pages lisibles
+    len: u64,
+    /// Nombre de références vers chaque page
+    references: Vec<u32>,
+    /// Pages dont les cellules sont cohérentes
+    consistent: Vec<bool>,
+    /// La liste des pages libres n'est pas corrompue
+    free_list_ok: bool,
+    problems: Vec<Problem>,
+}
+
+impl<'a, 'buf> Checker<'a, 'buf> {
+    fn new(pager: &'a Pager<'buf>, stored: u64) -> Result<Self> {
+        let described = pager.len();
+        let mut problems = Vec::default();
+
+        if described != stored {
+            problems.push(Problem::PageCountMismatch { described, stored });
+        }
+
+        let len = described.min(stored);
+
+        Ok(Self {
+            pager,
+            len,
+            references: vec![0; usize::try_from(len).unwrap()],
+            consistent: vec![false; usize::try_from(len).unwrap()],
+            free_list_ok: true,
+            problems,
+        })
+    }
+
+    fn check(&mut self) -> Result<()> {
+        let roots = self.scan()?;
+        self.check_free_list()?;
+        self.check_lsn_table()?;
+
+        for root in roots {
+            self.check_tree(root)?;
+        }
+
+        for pid in 1..self.len {
+            if self.references[usize::try_from(pid).unwrap()] == 0 {
+                self.problems.push(Problem::LeakedPage { pid });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Vérifie le type de chaque page, ainsi que la cohérence de leurs cellules.
+    ///
+    /// Retourne les pages d'entrée des arbres B+.
+    fn scan(&mut self) -> Result<Vec<PageId>> {
+        let mut roots = Vec::default();
+
+        for pid in 1..self.len {
+            let page = self.pager.borrow_element(&self.pager.tag().in_page(pid))?;
+            let byte = page.as_bytes()[0];
+
+            match PageKind::try_from(byte) {
+                Err(_) => self.problems.push(Problem::InvalidPageKind { pid, kind: byte }),
+                Ok(PageKind::BPlusTree) => roots.push(pid),
+                Ok(PageKind::BPlusTreeInterior | PageKind::BPlusTreeLeaf) => {
+                    if CellPage::from(page).is_consistent() {
+                        self.consistent[usize::try_from(pid).unwrap()] = true;
+                    } else {
+                        self.problems.push(Problem::InconsistentCells { pid });
+                    }
+                }
+                Ok(_) => {}
+            }
+        }
+
+        Ok(roots)
+    }
+
+    /// Référence une page depuis une autre, et vérifie son type.
+    ///
+    /// Retourne vrai si la page doit être parcourue.
+    fn reach(&mut self, from: PageId, pid: PageId, expected: &[PageKind]) -> Result<bool> {
+        if pid == 0 || pid >= self.len {
+            self.problems.push(Problem::DanglingReference { from, to: pid });
+            return Ok(false);
+        }
+
+        let references = &mut self.references[usize::try_from(pid).unwrap()];
+        *references += 1;
+
+        if *references > 1 {
+            self.problems.push(Problem::MultiplyReferenced { pid });
+            return Ok(false);
+        }
+
+        if expected.is_empty() {
+            return Ok(true);
+        }
+
+        let byte = self.pager.borrow_element(&self.pager.tag().in_page(pid))?.as_bytes()[0];
+
+        match PageKind::try_from(byte) {
+            Ok(kind) if expected.contains(&kind) => Ok(true),
+            // Déjà signalée lors du parcours des pages.
+            Err(_) => Ok(false),
+            Ok(_) => {
+                self.problems.push(Problem::UnexpectedPageKind { pid, got: byte });
+                Ok(false)
+            }
+        }
+    }
+
+    /// Parcourt la liste des pages libres, troncs et feuilles.
+    fn check_free_list(&mut self) -> Result<()> {
+        let problems = self.problems.len();
+        let (mut current, described) = self.with_description(|desc| (desc.get_free_head(), desc.free_count()))?;
+        let mut from = 0;
+        let mut counted = 0;
+
+        while let Some(pid) = current {
+            if !self.reach(from, pid, &[PageKind::Free])? {
+                break;
+            }
+
+            let (leaves, next) = {
+                let trunk = self
+                    .pager
+                    .borrow_element(&self.pager.tag().in_page(pid))
+                    .and_then(FreePage::try_from)?;
+                (trunk.iter().collect::<Vec<_>>(), trunk.get_next())
+            };
+
+            // Le contenu d'une feuille est périmé, son type n'a pas d'importance.
+            for leaf in leaves {
+                if self.reach(pid, leaf, &[])? {
+                    counted += 1;
+                }
+            }
+
+            counted += 1;
+            from = pid;
+            current = next;
+        }
+
+        if counted != described {
+            self.problems.push(Problem::FreeCountMismatch { described, counted });
+        }
+
+        self.free_list_ok = self.problems.len() == problems;
+        Ok(())
+    }
+
+    /// Parcourt la table des générations des pages.
+    fn check_lsn_table(&mut self) -> Result<()> {
+        let mut current = self.with_description(|desc| desc.get_lsn_head())?;
+        let mut from = 0;
+
+        while let Some(pid) = current {
+            if !self.reach(from, pid, &[PageKind::Lsn])? {
+                break;
+            }
+
+            from = pid;
+            current = self
+                .pager
+                .borrow_element(&self.pager.tag().in_page(pid))
+                .and_then(LsnPage::try_from)?
+                .get_next();
+        }
+
+        Ok(())
+    }
+
+    /// Parcourt les noeuds d'un arbre B+, et les pages de débordement de ses valeurs.
+    fn check_tree(&mut self, desc_pid: PageId) -> Result<()> {
+        // Les pages d'entrée ne sont référencées par aucune page.
+        self.references[usize::try_from(desc_pid).unwrap()] += 1;
+
+        let (root, spills) = {
+            let desc = self
+                .pager
+                .borrow_element(&self.pager.tag().in_page(desc_pid))
+                .and_then(BPTreeDescriptor::try_from)?;
+            (desc.root(), desc.is_var_sized())
+        };
+
+        let node_kinds = [PageKind::BPlusTreeInterior, PageKind::BPlusTreeLeaf];
+        let mut stack = Vec::default();
+
+        if let Some(root) = root {
+            if self.reach(desc_pid, root, &node_kinds)? {
+                stack.push(root);
+            }
+        }
+
+        while let Some(pid) = stack.pop() {
+            // Les cellules incohérentes ne peuvent être parcourues sans risque.
+            if !self.consistent[usize::try_from(pid).unwrap()] {
+                continue;
+            }
+
+            let page = self.pager.borrow_element(&self.pager.tag().in_page(pid))?;
+
+            let (children, heads) = if page.as_bytes()[0] == PageKind::BPlusTreeInterior as u8 {
+                let interior = BPlusTreeInterior::try_from(page)?;
+                let children = interior
+                    .iter()
+                    .filter_map(|cell| cell.left())
+                    .chain(interior.tail())
+                    .collect::<Vec<_>>();
+                (children, Vec::default())
+            } else if spills {
+                let leaf = BPlusTreeLeaf::try_from(page)?;
+                let heads = leaf
+                    .iter()
+                    .filter_map(|cell| cell.borrow_var().spill_page())
+                    .collect::<Vec<_>>();
+                (Vec::default(), heads)
+            } else {
+                (Vec::default(), Vec::default())
+            };
+
+            for child in children {
+                if self.reach(pid, child, &node_kinds)? {
+                    stack.push(child);
+                }
+            }
+
+            for head in heads {
+                self.check_spill_chain(pid, head)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Parcourt une liste chaînée de pages de débordement.
+    fn check_spill_chain(&mut self, from: PageId, head: PageId) -> Result<()> {
+        let mut chain = BTreeSet::default();
+        let mut from = from;
+        let mut current = Some(head);
+
+        while let Some(pid) = current {
+            if !chain.insert(pid) {
+                self.problems.push(Problem::UnterminatedSpillChain { head });
+                break;
+            }
+
+            if !self.reach(from, pid, &[PageKind::Spill])? {
+                break;
+            }
+
+            from = pid;
+            current = self
+                .pager
+                .borrow_element(&self.pager.tag().in_page(pid))
+                .and_then(SpillPage::try_from)?
+                .get_next();
+        }
+
+        Ok(())
+    }
+
+    /// La page inaccessible ne peut être référencée que depuis une racine parcourue.
+    fn is_surely_leaked(&self, pid: PageId) -> Result<bool> {
+        let byte = self.pager.borrow_element(&self.pager.tag().in_page(pid))?.as_bytes()[0];
+
+        Ok(matches!(
+            PageKind::try_from(byte),
+            Ok(PageKind::BPlusTreeInterior | PageKind::BPlusTreeLeaf | PageKind::Lsn)
+        ))
+    }
+
+    fn with_description<R>(&self, f: impl FnOnce(&PagerDescription) -> R) -> Result<R> {
+        let page = self.pager.borrow_element(&self.pager.tag().in_page(0))?;
+        let desc = PagerDescription::ref_from_bytes(&page.as_bytes()[..size_of::<PagerDescription>()]).unwrap();
+        Ok(f(desc))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Borrow, rc::Rc};
+
+    use crate::{
+        arena::IArena,
+        bpt::{descriptor::BPTreeDescriptor, BPlusTree, BPlusTreeArgs},
+        buffer::{stress::stubs::StressStub, BufferPool},
+        fs::{in_memory::InMemoryFs, std::StdFs},
+        page::{AsMutPageSlice, PageKind},
+        pager::{storage::FsPagerStorage, IPager, Pager},
+        prelude::IntoKnackBuf,
+        var::SpillPage,
+    };
+
+    use super::{check_jar, check_jar_in, repair_jar_in, Problem};
+
+    /// Crée un jar contenant un arbre B+ dont la valeur déborde sur les pages 5 à 7,
+    /// une page libre, et une page perdue.
+    fn create_jar(fs: &Rc<InMemoryFs>) -> u64 {
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let storage = FsPagerStorage::new(fs.clone(), "jar", 4096).into_boxed();
+        let pager = Pager::open(0, &buf_pool, storage).unwrap();
+
+        let fillers = (0..2)
+            .map(|_| *pager.new_element().unwrap().tag())
+            .collect::<Vec<_>>();
+        let mut tree = BPlusTree::new(&pager, BPlusTreeArgs::new::<u64, str>(None)).unwrap();
+        let value = "jarnac".repeat(2_000);
+        tree.insert(18u64.into_knack_buf().borrow(), &value.as_str().into_knack_buf()).unwrap();
+
+        // Une page de débordement écrite, mais référencée nulle part.
+        let mut page = pager.new_element().unwrap();
+        page.as_mut_bytes()[0] = PageKind::Spill as u8;
+        let leaked = page.tag().page_id;
+        drop(page);
+
+        for tag in fillers.iter() {
+            pager.delete_element(tag).unwrap();
+        }
+
+        // La table des générations prend l'une des pages libres.
+        pager.flush().unwrap();
+        leaked
+    }
+
+    #[test]
+    fn test_check_and_repair_leaked_page() {
+        let fs = Rc::new(InMemoryFs::default());
+        let leaked = create_jar(&fs);
+
+        let report = check_jar_in(fs.clone(), "jar").unwrap();
+        assert_eq!(report.problems, vec![Problem::LeakedPage { pid: leaked }]);
+
+        // Une page de débordement peut être référencée hors des arbres : elle n'est pas réparée.
+        let report = repair_jar_in(fs.clone(), "jar").unwrap();
+        assert!(report.repaired.is_empty());
+
+        // L'arbre perd sa racine : la feuille est réparée, ses pages de débordement sont signalées.
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let storage = FsPagerStorage::new(fs.clone(), "jar", 4096).into_boxed();
+        let pager = Pager::open(0, &buf_pool, storage).unwrap();
+        pager
+            .borrow_mut_element(&pager.tag().in_page(3))
+            .and_then(BPTreeDescriptor::try_from)
+            .unwrap()
+            .set_root(None);
+        pager.flush().unwrap();
+        drop(pager);
+
+        let report = repair_jar_in(fs.clone(), "jar").unwrap();
+        assert_eq!(report.repaired, vec![4]);
+
+        let report = check_jar_in(fs.clone(), "jar").unwrap();
+        let expected = [5, 6, 7, leaked].map(|pid| Problem::LeakedPage { pid });
+        assert_eq!(report.problems, expected);
+    }
+
+    #[test]
+    fn test_check_jar_file() {
+        let path = std::env::temp_dir().join(format!("jarnac-check-{}.jar", std::process::id()));
+        std::fs::write(&path, []).unwrap();
+
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let storage = FsPagerStorage::new(StdFs, path.as_path(), 4096).into_boxed();
+        let pager = Pager::open(0, &buf_pool, storage).unwrap();
+        pager.new_element().unwrap().as_mut_bytes()[1] = 1;
+        pager.flush().unwrap();
+        drop(pager);
+
+        let report = check_jar(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(report.unwrap().problems, vec![Problem::LeakedPage { pid: 1 }]);
+    }
+
+    #[test]
+    fn test_check_corrupted_pages() {
+        let fs = Rc::new(InMemoryFs::default());
+        let leaked = create_jar(&fs);
+
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let storage = FsPagerStorage::new(fs.clone(), "jar", 4096).into_boxed();
+        let pager = Pager::open(0, &buf_pool, storage).unwrap();
+
+        // La dernière page de débordement boucle sur la première.
+        pager
+            .borrow_mut_element(&pager.tag().in_page(7))
+            .and_then(SpillPage::try_from)
+            .unwrap()
+            .set_next(Some(5));
+        pager.borrow_mut_element(&pager.tag().in_page(leaked)).unwrap().as_mut_bytes()[0] = 42;
+        pager.flush().unwrap();
+        drop(pager);
+
+        let report = check_jar_in(fs.clone(), "jar").unwrap();
+        assert!(report.problems.contains(&Problem::UnterminatedSpillChain { head: 5 }));
+        assert!(report.problems.contains(&Problem::InvalidPageKind { pid: leaked, kind: 42 }));
+        assert!(report.problems.contains(&Problem::LeakedPage { pid: leaked }));
+    }
+}
@@ -0,0 +1,233 @@
+//! Compression des pages.
+//!
+//! Implémente un codage par plages (RLE) à la manière de *PackBits* :
+//! - un octet de contrôle *c* < 128 est suivi de *c + 1* octets littéraux ;
+//! - un octet de contrôle *c* ≥ 128 est suivi d'un octet répété *c - 128 + 3* fois.
+//!
+//! Les pages étant en grande partie remplies de zéros, le gain est important
+//! pour un coût négligeable.
+use std::collections::BTreeMap;
+
+use itertools::Itertools;
+
+use crate::{
+    error::{Error, ErrorKind},
+    result::Result,
+};
+
+/// Longueur minimale d'une plage répétée
+const MIN_RUN: usize = 3;
+/// Longueur maximale d'une plage répétée
+const MAX_RUN: usize = 127 + MIN_RUN;
+/// Longueur maximale d'une suite de littéraux
+const MAX_LITERALS: usize = 128;
+
+/// Compresse les données.
+pub fn compress(src: &[u8]) -> Vec<u8> {
+    let mut dest = Vec::with_capacity(src.len() / 4);
+    let mut literals = 0..0;
+    let mut i = 0;
+
+    while i < src.len() {
+        let run = src[i..]
+            .iter()
+            .take(MAX_RUN)
+            .take_while(|b| **b == src[i])
+            .count();
+
+        if run >= MIN_RUN {
+            flush_literals(&mut dest, &src[literals.clone()]);
+            dest.push(u8::try_from(run - MIN_RUN + 128).unwrap());
+            dest.push(src[i]);
+            i += run;
+            literals = i..i;
+        } else {
+            i += 1;
+            literals.end = i;
+
+            if literals.len() == MAX_LITERALS {
+                flush_literals(&mut dest, &src[literals.clone()]);
+                literals = i..i;
+            }
+        }
+    }
+
+    flush_literals(&mut dest, &src[literals]);
+    dest
+}
+
+fn flush_literals(dest: &mut Vec<u8>, literals: &[u8]) {
+    if !literals.is_empty() {
+        dest.push(u8::try_from(literals.len() - 1).unwrap());
+        dest.extend_from_slice(literals);
+    }
+}
+
+/// Décompresse les données dans *dest*.
+///
+/// Échoue si les données sont corrompues, ou si leur taille décompressée
+/// diffère de celle de *dest*.
+pub fn decompress(src: &[u8], dest: &mut [u8]) -> Result<()> {
+    let mut src = src.iter().copied();
+    let mut cursor = 0;
+
+    while let Some(ctrl) = src.next() {
+        let ctrl = usize::from(ctrl);
+
+        if ctrl < 128 {
+            let len = ctrl + 1;
+            let dest = dest
+                .get_mut(cursor..cursor + len)
+                .ok_or_else(|| Error::new(ErrorKind::InvalidFormat))?;
+
+            for b in dest.iter_mut() {
+                *b = src.next().ok_or_else(|| Error::new(ErrorKind::InvalidFormat))?;
+            }
+
+            cursor += len;
+        } else {
+            let len = ctrl - 128 + MIN_RUN;
+            let value = src.next().ok_or_else(|| Error::new(ErrorKind::InvalidFormat))?;
+
+            dest.get_mut(cursor..cursor + len)
+                .ok_or_else(|| Error::new(ErrorKind::InvalidFormat))?
+                .fill(value);
+
+            cursor += len;
+        }
+    }
+
+    if cursor != dest.len() {
+        return Err(Error::new(ErrorKind::InvalidFormat));
+    }
+
+    Ok(())
+}
+
+/// Espace occupé par des trames compressées de taille variable dans un fichier.
+///
+/// Les étendues libérées sont fusionnées avec leurs voisines, et réutilisées
+/// selon la politique du premier ajustement.
+#[derive(Default)]
+pub(crate) struct FrameSpace {
+    /// Fin de l'espace utilisé
+    end: u64,
+    /// Étendues libres, indexées par leur début
+    free: BTreeMap<u64, u64>,
+}
+
+impl FrameSpace {
+    /// Espace débutant à *start*, dont les trames *frames* (début, taille) sont occupées.
+    pub fn new(start: u64, frames: impl Iterator<Item = (u64, u64)>) -> Self {
+        let mut space = Self { end: start, free: BTreeMap::default() };
+
+        for (offset, len) in frames.sorted() {
+            if offset > space.end {
+                space.free.insert(space.end, offset - space.end);
+            }
+            space.end = space.end.max(offset + len);
+        }
+
+        space
+    }
+
+    /// Fin de l'espace utilisé.
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    /// Taille occupée dans le fichier, hors étendues libres.
+    pub fn used(&self) -> u64 {
+        self.end - self.free.values().sum::<u64>()
+    }
+
+    /// Réserve une étendue de la taille demandée.
+    pub fn reserve(&mut self, len: u64) -> u64 {
+        let found = self
+            .free
+            .iter()
+            .find(|(_, free_len)| **free_len >= len)
+            .map(|(offset, free_len)| (*offset, *free_len));
+
+        if let Some((offset, free_len)) = found {
+            self.free.remove(&offset);
+            if free_len > len {
+                self.free.insert(offset + len, free_len - len);
+            }
+            return offset;
+        }
+
+        let offset = self.end;
+        self.end += len;
+        offset
+    }
+
+    /// Libère une étendue.
+    pub fn release(&mut self, mut offset: u64, mut len: u64) {
+        if let Some((&prev, &prev_len)) = self.free.range(..offset).next_back() {
+            if prev + prev_len == offset {
+                self.free.remove(&prev);
+                offset = prev;
+                len += prev_len;
+            }
+        }
+
+        if let Some(next_len) = self.free.remove(&(offset + len)) {
+            len += next_len;
+        }
+
+        if offset + len == self.end {
+            self.end = offset;
+        } else {
+            self.free.insert(offset, len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compress, decompress, FrameSpace};
+
+    #[test]
+    fn test_roundtrip() {
+        let mut page = vec![0u8; 4096];
+        page[10..20].copy_from_slice(b"0123456789");
+        page[100..400].fill(7);
+        page[1000..1300]
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, b)| *b = (i % 251) as u8);
+
+        let compressed = compress(&page);
+        assert!(compressed.len() < 600);
+
+        let mut decompressed = vec![0u8; 4096];
+        decompress(&compressed, &mut decompressed).unwrap();
+        assert_eq!(page, decompressed);
+    }
+
+    #[test]
+    fn test_corrupted() {
+        let compressed = compress(&[1, 1, 1, 1, 2, 3]);
+        let mut dest = [0u8; 5];
+        assert!(decompress(&compressed, &mut dest).is_err());
+        assert!(decompress(&compressed[..compressed.len() - 1], &mut [0u8; 6]).is_err());
+    }
+
+    #[test]
+    fn test_frame_space() {
+        let mut space = FrameSpace::new(10, [(20, 5), (30, 10)].into_iter());
+        assert_eq!(space.end(), 40);
+        assert_eq!(space.used(), 25);
+
+        assert_eq!(space.reserve(8), 10);
+        assert_eq!(space.reserve(8), 40);
+
+        space.release(30, 10);
+        space.release(20, 5);
+        assert_eq!(space.reserve(12), 18);
+
+        space.release(40, 8);
+        assert_eq!(space.end(), 30);
+    }
+}
@@ -0,0 +1,97 @@
+//! Chiffrement authentifié des pages.
+//!
+//! Repose sur l'implémentation ChaCha20-Poly1305 (RFC 8439) de la crate chacha20poly1305.
+//! Chaque chiffrement utilise un nonce aléatoire stocké aux côtés des données, et des
+//! données associées liant le chiffré à son emplacement (voir [tag_aad]).
+use chacha20poly1305::{aead::AeadInPlace, ChaCha20Poly1305, KeyInit};
+
+use crate::{
+    error::{Error, ErrorKind},
+    result::Result,
+    tag::JarTag,
+};
+
+/// Taille d'un nonce
+pub const NONCE_SIZE: usize = 12;
+/// Taille d'un code d'authentification
+pub const TAG_SIZE: usize = 16;
+
+pub type Nonce = [u8; NONCE_SIZE];
+pub type AuthTag = [u8; TAG_SIZE];
+
+/// Clé de chiffrement de 256 bits.
+#[derive(Clone)]
+pub struct EncryptionKey([u8; 32]);
+
+impl From<[u8; 32]> for EncryptionKey {
+    fn from(value: [u8; 32]) -> Self {
+        Self(value)
+    }
+}
+
+/// Génère un nonce aléatoire.
+pub fn random_nonce() -> Nonce {
+    rand::random()
+}
+
+/// Données associées liant un chiffré à une page.
+pub fn tag_aad(tag: &JarTag) -> [u8; 16] {
+    let mut aad = [0u8; 16];
+    aad[0..8].copy_from_slice(&tag.jar_id.to_le_bytes());
+    aad[8..16].copy_from_slice(&tag.page_id.to_le_bytes());
+    aad
+}
+
+/// Chiffre *buf* en place, et retourne son code d'authentification.
+pub fn seal(key: &EncryptionKey, nonce: &Nonce, aad: &[u8], buf: &mut [u8]) -> AuthTag {
+    ChaCha20Poly1305::new(&key.0.into())
+        .encrypt_in_place_detached(nonce.into(), aad, buf)
+        .expect("page must fit in a ChaCha20-Poly1305 message")
+        .into()
+}
+
+/// Authentifie et déchiffre *buf* en place.
+///
+/// Échoue si la clé est erronée ou si les données ont été altérées.
+pub fn open(key: &EncryptionKey, nonce: &Nonce, aad: &[u8], buf: &mut [u8], tag: &AuthTag) -> Result<()> {
+    ChaCha20Poly1305::new(&key.0.into())
+        .decrypt_in_place_detached(nonce.into(), aad, buf, tag.into())
+        .map_err(|_| Error::new(ErrorKind::DecryptionFailed))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ErrorKind;
+
+    use super::{open, seal, EncryptionKey};
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_aead() {
+        // RFC 8439, 2.8.2
+        let key = EncryptionKey::from(<[u8; 32]>::try_from(hex(
+            "808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f",
+        )).unwrap());
+        let nonce = hex("070000004041424344454647").try_into().unwrap();
+        let aad = hex("50515253c0c1c2c3c4c5c6c7");
+        let plaintext = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+
+        let mut buf = plaintext.to_vec();
+        let tag = seal(&key, &nonce, &aad, &mut buf);
+        assert_eq!(buf[0..16], hex("d31a8d34648e60db7b86afbc53ef7ec2"));
+        assert_eq!(tag.to_vec(), hex("1ae10b594f09e26a7e902ecbd0600691"));
+
+        open(&key, &nonce, &aad, &mut buf, &tag).unwrap();
+        assert_eq!(buf, plaintext);
+
+        let wrong = EncryptionKey::from([0u8; 32]);
+        let err = open(&wrong, &nonce, &aad, &mut buf, &tag).err().unwrap();
+        assert!(matches!(err.kind, ErrorKind::DecryptionFailed));
+    }
+}
@@ -10,6 +10,8 @@ use zerocopy::TryFromBytes;
 use crate::tag::JarTag;
 use crate::page::PageKind;
 use crate::knack::error::KnackError as KnackError;
+use crate::knack::path::KnackPath;
+use crate::jar::SchemaViolation;
 
 pub struct Error {
     pub backtrace: Backtrace,
@@ -61,6 +63,12 @@ where Dest: TryFromBytes + ?Sized
     }
 }
 
+impl From<KnackError> for Error {
+    fn from(value: KnackError) -> Self {
+        Self::new(ErrorKind::KnackError(value))
+    }
+}
+
 impl From<io::Error> for Error {
     fn from(value: io::Error) -> Self {
         Self::new(ErrorKind::IoError(value))
@@ -75,6 +83,10 @@ pub enum ErrorKind {
     PageAlreadyCached(JarTag),
     PageNotCached(JarTag),
     PageCurrentlyBorrowed,
+    LockTimeout,
+    ReadOnly,
+    WrongKey,
+    DecryptionFailed,
     PageLoadingFailed {
         tag: JarTag, 
         source: Box<Error>
@@ -87,6 +99,10 @@ pub enum ErrorKind {
     CellPageFull,
     KnackError(KnackError),
     InvalidBPlusTreeDefinition,
+    /// Le schéma d'un pot est mal formé
+    InvalidSchema { path: KnackPath, message: &'static str },
+    /// Le document ne respecte pas le schéma du pot
+    SchemaViolations(Vec<SchemaViolation>),
     IoError(io::Error),
 }
 
@@ -97,6 +113,10 @@ impl Display for ErrorKind {
             ErrorKind::UnexistingPage(id) => write!(f, "page {id} does not exist"),
             ErrorKind::PageAlreadyCached(id) => write!(f, "page {id} is already cached"),
             ErrorKind::PageCurrentlyBorrowed => write!(f, "page is already borrowed"),
+            ErrorKind::LockTimeout => write!(f, "timed out while waiting for the page lock"),
+            ErrorKind::ReadOnly => write!(f, "the pager is read-only"),
+            ErrorKind::WrongKey => write!(f, "wrong encryption key"),
+            ErrorKind::DecryptionFailed => write!(f, "failed to authenticate encrypted data"),
             ErrorKind::InvalidPageKind(invalid_kind) => write!(f, "unknown page kind, got {0}", invalid_kind),
             ErrorKind::InvalidFormat => write!(f, "invalid pager format"),
             ErrorKind::WrongPageKind { expected, got } => {
@@ -109,7 +129,17 @@ impl Display for ErrorKind {
             ErrorKind::CellPageOverflow => write!(f, "cell space overflows allocated page space"),
             ErrorKind::PageLoadingFailed { tag: id, source } => write!(f, "failed to load page {id}, reason: {source}"),
             ErrorKind::InvalidBPlusTreeDefinition => write!(f, "the b+ tree definition is invalid"),
-            ErrorKind::KnackError(_error) => todo!(),
+            ErrorKind::KnackError(error) => write!(f, "{error}"),
+            ErrorKind::InvalidSchema { path, message } => write!(f, "invalid schema rule at {path}: {message}"),
+            ErrorKind::SchemaViolations(violations) => {
+                write!(f, "document does not match the jar schema")?;
+
+                for violation in violations {
+                    write!(f, "; {violation}")?;
+                }
+
+                Ok(())
+            }
         }
     }
 }
@@ -1,11 +1,15 @@
-use zerocopy::TryFromBytes;
+use zerocopy::{FromBytes, IntoBytes, TryFromBytes};
 use zerocopy_derive::*;
 
 use crate::{
     page::{AsMutPageSlice, AsRefPageSlice, InPage, OptionalPageId, PageId, PageKind}, pager::{IPager, PagerDescription}, result::Result, tag::{DataArea, JarTag}
 };
 
-
+/// Une page tronc de la liste des pages libres.
+///
+/// Les troncs sont chaînés entre eux, et répertorient chacun plusieurs centaines
+/// de pages libres (les feuilles). Libérer ou allouer une feuille ne modifie
+/// ainsi que le tronc de tête, et jamais la feuille elle-même.
 pub struct FreePage<Page>(Page)
 where
     Page: AsRefPageSlice;
@@ -14,7 +18,7 @@ impl<Page> FreePage<Page>
 where
     Page: AsRefPageSlice,
 {
-    /// Initialise une page libre.
+    /// Initialise un tronc vide.
     pub fn new(mut page: Page) -> Result<Self>
     where
         Page: AsMutPageSlice,
@@ -25,16 +29,47 @@ where
         Ok(Self(page))
     }
 
-    /// Embarque la page en tant que page libre.
+    /// Embarque la page en tant que tronc.
     pub fn try_from(page: Page) -> Result<Self> {
         let kind: PageKind = page.as_ref().as_bytes()[0].try_into()?;
         PageKind::Free.assert(kind).map(|_| Self(page))
     }
 
+    /// Tronc suivant
     pub fn get_next(&self) -> Option<PageId> {
         self.as_meta().next.into()
     }
 
+    /// Nombre de feuilles répertoriées par le tronc.
+    pub fn len(&self) -> usize {
+        usize::from(self.as_meta().len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Nombre maximal de feuilles répertoriées par le tronc.
+    pub fn capacity(&self) -> usize {
+        (self.0.as_bytes().len() - FreePageMeta::AREA.end) / size_of::<PageId>()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() >= self.capacity()
+    }
+
+    /// Itère sur les feuilles répertoriées par le tronc.
+    pub fn iter(&self) -> impl Iterator<Item = PageId> + '_ {
+        (0..self.len()).map(|idx| {
+            PageId::read_from_bytes(&self.0.as_bytes()[Self::leaf_range(idx)]).unwrap()
+        })
+    }
+
+    fn leaf_range(idx: usize) -> std::ops::Range<usize> {
+        let start = FreePageMeta::AREA.end + idx * size_of::<PageId>();
+        start..(start + size_of::<PageId>())
+    }
+
     fn as_meta(&self) -> &FreePageMeta {
         FreePageMeta::try_ref_from_bytes(&self.0.as_ref()[FreePageMeta::AREA]).unwrap()
     }
@@ -48,51 +83,93 @@ where
         self.as_mut_meta().next = next.into()
     }
 
+    /// Ajoute une feuille au tronc, qui ne doit pas être plein.
+    pub fn push(&mut self, pid: PageId) {
+        assert!(!self.is_full(), "free trunk page is full");
+        let idx = self.len();
+        self.0.as_mut_bytes()[Self::leaf_range(idx)].copy_from_slice(pid.as_bytes());
+        self.as_mut_meta().len += 1;
+    }
+
+    /// Retire la dernière feuille ajoutée au tronc.
+    pub fn pop(&mut self) -> Option<PageId> {
+        let idx = self.len().checked_sub(1)?;
+        let pid = PageId::read_from_bytes(&self.0.as_bytes()[Self::leaf_range(idx)]).unwrap();
+        self.as_mut_meta().len -= 1;
+        Some(pid)
+    }
+
     fn as_mut_meta(&mut self) -> &mut FreePageMeta {
         FreePageMeta::try_mut_from_bytes(&mut self.0.as_mut()[FreePageMeta::AREA]).unwrap()
     }
 }
 
 #[derive(FromBytes, IntoBytes, KnownLayout, Immutable)]
-#[repr(C)]
-/// Représente les données stockée dans une page libre.
+#[repr(C, packed)]
+/// Représente les données stockées dans un tronc.
 pub struct FreePageMeta {
+    /// Tronc suivant
     next: OptionalPageId,
+    /// Nombre de feuilles répertoriées
+    len: u16,
 }
 
 impl DataArea for FreePageMeta {
     const AREA: std::ops::Range<usize> = InPage::<Self>::AREA;
 }
 
-/// Empile une nouvelle page libre dans la liste chaînée
+/// Ajoute une page libre à la liste.
+///
+/// La page est répertoriée dans le tronc de tête, ou devient le nouveau tronc
+/// de tête si celui-ci est plein.
 pub(super) fn push_free_page<'buf, Pager: IPager<'buf>>(
     pager: &Pager,
     desc: &mut PagerDescription,
     tag: &JarTag,
 ) -> Result<()> {
-    let mut page = pager.borrow_mut_element(tag)?;
-    page.fill(0);
-
-    FreePage::new(&mut page)?;
+    let head = desc
+        .get_free_head()
+        .map(|pid| pager.borrow_mut_element(&pager.tag().in_page(pid)).and_then(FreePage::try_from))
+        .transpose()?;
+
+    match head {
+        Some(mut trunk) if !trunk.is_full() => trunk.push(tag.page_id),
+        _ => {
+            let page = pager.borrow_mut_element(tag)?;
+            FreePage::new(page)?.set_next(desc.get_free_head());
+            desc.set_free_head(Some(tag.page_id));
+        }
+    }
 
-    desc.set_free_head(Some(tag.page_id));
+    desc.free_count += 1;
 
     Ok(())
 }
 
-/// Dépile une page libre dans la liste chaînée
+/// Retire une page libre de la liste.
+///
+/// Une feuille du tronc de tête est retirée en priorité, le tronc lui-même
+/// n'est retiré qu'une fois vide.
 pub(super) fn pop_free_page<'pager, Pager: IPager<'pager>>(
     pager: &Pager,
     desc: &mut PagerDescription
 ) -> Result<Option<JarTag>> {
-    
-    if let Some(next) = desc.get_free_head() {
-        let next = pager.tag().in_page(next);
-        let page = pager.borrow_element(&next).and_then(FreePage::try_from)?;
-        let new_head = page.get_next();
-        desc.set_free_head(new_head);
-        return Ok(Some(next));
-    }
+    let Some(head) = desc.get_free_head() else {
+        return Ok(None);
+    };
+
+    let head = pager.tag().in_page(head);
+    let mut trunk = pager.borrow_mut_element(&head).and_then(FreePage::try_from)?;
+
+    let tag = match trunk.pop() {
+        Some(pid) => pager.tag().in_page(pid),
+        None => {
+            desc.set_free_head(trunk.get_next());
+            head
+        }
+    };
+
+    desc.free_count -= 1;
 
-    Ok(None)
+    Ok(Some(tag))
 }
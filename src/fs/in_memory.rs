@@ -138,6 +138,10 @@ impl IFileSystem for Rc<InMemoryFs> {
     fn exists(&self, path: &Self::Path) -> bool {
         self.deref().exists(path)
     }
+
+    fn set_len(&self, path: &Self::Path, size: u64) -> io::Result<()> {
+        self.deref().set_len(path, size)
+    }
 }
 
 impl IFileSystem for InMemoryFs {
@@ -175,6 +179,19 @@ impl IFileSystem for InMemoryFs {
     fn exists(&self, path: &Self::Path) -> bool {
         self.0.borrow().contains_key(path.as_ref())
     }
+
+    fn set_len(&self, path: &Self::Path, size: u64) -> std::io::Result<()> {
+        let map = self.0.borrow();
+        let data = map.get(path.as_ref()).ok_or_else(|| {
+            io::Error::new(ErrorKind::NotFound, format!("file {path} does not exist"))
+        })?;
+
+        unsafe {
+            data.get_mut_ptr().as_mut().resize(usize::try_from(size).unwrap(), 0);
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -1,6 +1,7 @@
-use std::io::{self, Read, Result, Seek, Write};
+use ::std::io::{self, Read, Result, Seek, Write};
 
 pub mod in_memory;
+pub mod std;
 
 pub trait IPath: Clone + PartialEq + ToString {
     /// Retourne le répertoire à partir du chemin.
@@ -55,7 +56,7 @@ pub struct FileOpenOptions(u8);
 
 impl FileOpenOptions {
     const CREATE_FLAG: u8 = 0b1;
-    const READ_FLAG: u8 = 0b01;
+    const READ_FLAG: u8 = 0b10;
     const WRITE_FLAG: u8 = 0b100;
 
     pub fn new() -> Self {
@@ -71,7 +72,7 @@ impl FileOpenOptions {
     }
 
     pub fn is_write(&self) -> bool {
-        self.0 & Self::READ_FLAG == Self::READ_FLAG
+        self.0 & Self::WRITE_FLAG == Self::WRITE_FLAG
     }
 
     pub fn create(self, value: bool) -> Self {
@@ -112,7 +113,10 @@ pub trait IFileSystem {
     fn exists(&self, path: &Self::Path) -> bool;
 
     /// Supprime le fichier/répertoire
-    fn rm(&self, path: &Self::Path) -> std::io::Result<()>;
+    fn rm(&self, path: &Self::Path) -> io::Result<()>;
+
+    /// Tronque ou étend le fichier à la taille donnée.
+    fn set_len(&self, path: &Self::Path, size: u64) -> io::Result<()>;
 }
 
 /// Un pointeur vers un fichier dans un système de fichier.
@@ -144,5 +148,9 @@ where
     pub fn exists(&self) -> bool {
         self.fs.exists(&self.path)
     }
+
+    pub fn set_len(&self, size: u64) -> io::Result<()> {
+        self.fs.set_len(&self.path, size)
+    }
 }
 
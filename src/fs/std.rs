@@ -1 +1,97 @@
-pub struct StdPath(PathBuf);
\ No newline at end of file
+use std::{
+    fmt::Display,
+    fs::{File, OpenOptions},
+    io,
+    path::{Path, PathBuf},
+};
+
+use super::{FileOpenOptions, IFileSystem, IPath};
+
+/// Chemin dans le système de fichier du système d'exploitation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StdPath(PathBuf);
+
+impl From<&str> for StdPath {
+    fn from(value: &str) -> Self {
+        Self(PathBuf::from(value))
+    }
+}
+
+impl From<&Path> for StdPath {
+    fn from(value: &Path) -> Self {
+        Self(value.to_path_buf())
+    }
+}
+
+impl From<PathBuf> for StdPath {
+    fn from(value: PathBuf) -> Self {
+        Self(value)
+    }
+}
+
+impl AsRef<Path> for StdPath {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Display for StdPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.display().fmt(f)
+    }
+}
+
+impl IPath for StdPath {
+    fn parent(&self) -> Self {
+        Self(self.0.parent().map(Path::to_path_buf).unwrap_or_default())
+    }
+
+    fn join(&self, rhs: Self) -> Self {
+        Self(self.0.join(rhs.0))
+    }
+
+    fn append(&self, path: &str) -> Self {
+        Self(self.0.join(path))
+    }
+
+    fn tail(&self) -> String {
+        self.0
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+/// Système de fichier du système d'exploitation.
+#[derive(Default, Clone, Copy)]
+pub struct StdFs;
+
+impl IFileSystem for StdFs {
+    type File<'fs> = File;
+    type Path = StdPath;
+
+    fn open<'fs>(&'fs self, path: &Self::Path, options: FileOpenOptions) -> io::Result<Self::File<'fs>> {
+        OpenOptions::new()
+            .read(options.is_read())
+            .write(options.is_write())
+            .create(options.is_create())
+            .truncate(false)
+            .open(path)
+    }
+
+    fn exists(&self, path: &Self::Path) -> bool {
+        path.0.exists()
+    }
+
+    fn rm(&self, path: &Self::Path) -> io::Result<()> {
+        if path.0.is_dir() {
+            std::fs::remove_dir_all(path)
+        } else {
+            std::fs::remove_file(path)
+        }
+    }
+
+    fn set_len(&self, path: &Self::Path, size: u64) -> io::Result<()> {
+        OpenOptions::new().write(true).open(path)?.set_len(size)
+    }
+}
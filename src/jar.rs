@@ -1,8 +1,116 @@
+use std::{borrow::Cow, fmt::Display};
+
 use zerocopy::LE;
 
-use crate::{knack::document::{DocBuilder, DocCow}, page::PageId, pager::Pager, tag::JarId};
+use crate::{
+    bpt::{BPlusTree, BPlusTreeArgs},
+    error::{Error, ErrorKind},
+    knack::{
+        array::Array,
+        buf::{IntoKnackBuf, KnackBuf},
+        builder::KnackBuilder,
+        de::from_knack,
+        document::{DocBuilder, DocCow, Document},
+        path::KnackPath,
+        update::Update,
+        FixedStrRef, Knack,
+    },
+    page::PageId,
+    pager::Pager,
+    result::Result,
+    tag::{JarId, JarTag},
+};
+
+pub struct Jar<'buf> {
+    pager: Pager<'buf>,
+    description: JarDescription<'buf>,
+    /// Schéma du pot, construit une seule fois.
+    schema: Option<JarSchema<'static>>,
+    /// Descripteur de l'arbre B+ des documents, indexés par identifiant.
+    documents: JarTag,
+}
+
+impl<'buf> Jar<'buf> {
+    /// Créé un nouveau pot, ainsi que l'arbre de ses documents.
+    pub fn new(pager: Pager<'buf>, description: JarDescription<'buf>) -> Result<Self> {
+        let args = BPlusTreeArgs::new::<u64, DocBuilder>(None);
+        let documents = *BPlusTree::new(&pager, args)?.tag();
+        let schema = description.schema().map(JarSchema::into_owned);
+
+        Ok(Self { pager, description, schema, documents })
+    }
+
+    pub fn pager(&self) -> &Pager<'buf> {
+        &self.pager
+    }
+
+    pub fn description(&self) -> &JarDescription<'buf> {
+        &self.description
+    }
+
+    /// Nombre de documents du pot.
+    pub fn len(&self) -> Result<u64> {
+        self.documents().map(|tree| tree.len())
+    }
+
+    pub fn is_empty(&self) -> Result<bool> {
+        self.len().map(|len| len == 0)
+    }
+
+    /// Récupère un document du pot.
+    pub fn get(&self, id: u64) -> Result<Option<KnackBuf>> {
+        self.documents()?
+            .search(&id.into_knack_buf())?
+            .map(|doc| doc.assert_loaded(&self.pager).map(|doc| Knack::to_owned(&doc)))
+            .transpose()
+    }
+
+    /// Insère un document dans le pot, et retourne son identifiant.
+    ///
+    /// Le document est validé selon le schéma du pot, s'il en possède un,
+    /// rien n'est écrit s'il ne le respecte pas.
+    pub fn insert(&mut self, doc: DocBuilder) -> Result<u64> {
+        let buf = doc.into_knack_buf();
+        self.validate(buf.cast::<DocBuilder>())?;
 
-pub struct Jar<'buf>(Pager<'buf>);
+        // Les documents ne sont jamais supprimés, l'identifiant suivant est donc le nombre de documents.
+        let mut documents = self.documents()?;
+        let id = documents.len();
+        documents.insert(&id.into_knack_buf(), &buf)?;
+
+        Ok(id)
+    }
+
+    /// Met à jour un document du pot (cf [Update::apply]).
+    ///
+    /// Le document mis à jour est validé selon le schéma du pot, s'il en possède un,
+    /// rien n'est écrit s'il ne le respecte pas.
+    ///
+    /// Retourne faux si le document n'existe pas, ou si la mise à jour est sans effet.
+    pub fn update(&mut self, id: u64, update: &Update) -> Result<bool> {
+        let Some(doc) = self.get(id)? else {
+            return Ok(false);
+        };
+
+        let Some(updated) = update.apply(doc.cast::<DocBuilder>())? else {
+            return Ok(false);
+        };
+
+        self.validate(updated.cast::<DocBuilder>())?;
+        self.documents()?.replace(&id.into_knack_buf(), &updated)
+    }
+
+    fn validate(&self, doc: &Document) -> Result<()> {
+        match &self.schema {
+            Some(schema) => schema.validate(doc),
+            None => Ok(()),
+        }
+    }
+
+    fn documents(&self) -> Result<BPlusTree<'_, Pager<'_>>> {
+        BPlusTree::open(&self.pager, self.documents)
+    }
+}
 
 #[repr(C, packed)]
 /// Metadonnées d'un pot
@@ -33,6 +141,31 @@ impl JarDescription<'_> {
 
         Self(doc.into())
     }
+
+    /// Schéma du pot, s'il en possède un.
+    pub fn schema(&self) -> Option<JarSchema<'_>> {
+        match &self.0 {
+            DocCow::Borrow(doc) => doc
+                .get_field("schema")
+                .and_then(|schema| <&Document>::try_from(schema).ok())
+                .map(JarSchema::from),
+            DocCow::Owned(doc) => match doc.get_field("schema")? {
+                KnackBuilder::Document(schema) => Some(JarSchema::from(schema.clone())),
+                KnackBuilder::Other(schema) => <&Document>::try_from(&**schema).ok().map(JarSchema::from),
+                _ => None,
+            },
+        }
+    }
+
+    pub fn set_schema(&mut self, schema: DocBuilder) {
+        let mut doc = match std::mem::replace(&mut self.0, DocCow::Owned(DocBuilder::default())) {
+            DocCow::Owned(doc) => doc,
+            DocCow::Borrow(doc) => doc.to_owned(),
+        };
+
+        doc.insert("schema", schema);
+        self.0 = doc.into();
+    }
 }
 
 //// Description d'un index 
@@ -42,7 +175,397 @@ impl JarDescription<'_> {
 /// - kind: Type d'index (BPlusTree, ...) ;
 /// - fields: Liste de champs [KnackPath] indexés ;
 /// - unique: Oblige chaque entrée de l'index a ne posséder qu'une seule valeur.
-pub struct JarIndex<'a>(DocCow<'a>);
+pub struct JarIndex<'a>(#[allow(dead_code)] DocCow<'a>);
 
 /// Description d'un schéma
-pub struct JarSchema<'a>(DocCow<'a>);
\ No newline at end of file
+///
+/// # Structure
+/// - fields: Dictionnaire des règles de chaque champ du document.
+///
+/// Une règle de champ :
+/// - kind (optional) : Type attendu ("u32", "str", "fixed-str(3)", "document", "array<u8>"...),
+///   "array" accepte toute liste ;
+/// - required (optional) : Le champ doit être présent ;
+/// - min, max (optional) : Bornes d'une valeur numérique (ou d'un horodatage, en microsecondes) ;
+/// - min_length, max_length (optional) : Bornes de la longueur d'une chaîne, en caractères,
+///   ou du nombre d'éléments d'une liste ;
+/// - fields (optional) : Règles des champs d'un document imbriqué ;
+/// - items (optional) : Règle de chaque élément d'une liste.
+pub struct JarSchema<'a>(Cow<'a, Knack>);
+
+impl From<DocBuilder> for JarSchema<'_> {
+    fn from(value: DocBuilder) -> Self {
+        Self(Cow::Owned(value.into_knack_buf()))
+    }
+}
+
+impl<'a> From<&'a Document> for JarSchema<'a> {
+    fn from(value: &'a Document) -> Self {
+        Self(Cow::Borrowed(Knack::from_ref(value)))
+    }
+}
+
+impl JarSchema<'_> {
+    /// Détache le schéma du document dont il est issu.
+    pub fn into_owned(self) -> JarSchema<'static> {
+        JarSchema(Cow::Owned(self.0.into_owned()))
+    }
+
+    /// Valide un document.
+    ///
+    /// Toutes les violations du schéma sont retournées, chacune avec le chemin de la valeur fautive.
+    pub fn validate(&self, doc: &Document) -> Result<()> {
+        let mut violations = Vec::default();
+        validate_schema(self.0.cast::<DocBuilder>(), doc, &mut violations)?;
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::new(ErrorKind::SchemaViolations(violations)))
+        }
+    }
+}
+
+/// Valeur ne respectant pas le schéma d'un pot.
+#[derive(Debug)]
+pub struct SchemaViolation {
+    pub path: KnackPath,
+    pub message: String,
+}
+
+impl Display for SchemaViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{0}: {1}", self.path, self.message)
+    }
+}
+
+/// Parcours d'un document et des règles qui s'y appliquent.
+struct SchemaValidator<'v> {
+    violations: &'v mut Vec<SchemaViolation>,
+}
+
+fn validate_schema(schema: &Document, doc: &Document, violations: &mut Vec<SchemaViolation>) -> Result<()> {
+    let Some(fields) = schema.get_field("fields") else {
+        return Ok(());
+    };
+
+    let rules_at = KnackPath::default().field("fields");
+    let mut validator = SchemaValidator { violations };
+    validator.validate_fields(rule_document(fields, &rules_at)?, doc, &KnackPath::default(), &rules_at)
+}
+
+impl SchemaValidator<'_> {
+    fn violation(&mut self, path: &KnackPath, message: String) {
+        self.violations.push(SchemaViolation { path: path.clone(), message });
+    }
+
+    fn validate_fields(&mut self, rules: &Document, doc: &Document, at: &KnackPath, rules_at: &KnackPath) -> Result<()> {
+        for kv in rules.iter() {
+            let name: &str = kv.key().cast::<str>();
+            let rule_at = rules_at.field(name);
+            let rule = rule_document(kv.value(), &rule_at)?;
+
+            match doc.get_field(name) {
+                Some(value) => self.validate_value(rule, value, &at.field(name), &rule_at)?,
+                None => {
+                    if rule_flag(rule, "required", &rule_at)? {
+                        self.violation(&at.field(name), "required field is missing".to_owned());
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_value(&mut self, rule: &Document, value: &Knack, at: &KnackPath, rule_at: &KnackPath) -> Result<()> {
+        if let Some(kind) = rule.get_field("kind") {
+            let expected = rule_str(kind, &rule_at.field("kind"))?;
+            let got = value.kind().to_string();
+
+            let matches = match expected {
+                "array" => <&Array>::try_from(value).is_ok(),
+                _ => expected == got,
+            };
+
+            // Les autres règles n'ont pas de sens pour une valeur d'un autre type.
+            if !matches {
+                self.violation(at, format!("expecting {expected}, got {got}"));
+                return Ok(());
+            }
+        }
+
+        let min = rule.get_field("min").map(|min| rule_number(min, &rule_at.field("min"))).transpose()?;
+        let max = rule.get_field("max").map(|max| rule_number(max, &rule_at.field("max"))).transpose()?;
+
+        if min.is_some() || max.is_some() {
+            match from_knack::<f64>(value) {
+                Ok(number) if min.is_some_and(|min| number < min) => {
+                    self.violation(at, format!("{number} is lower than {0}", min.unwrap()))
+                }
+                Ok(number) if max.is_some_and(|max| number > max) => {
+                    self.violation(at, format!("{number} is greater than {0}", max.unwrap()))
+                }
+                Ok(_) => {}
+                Err(_) => self.violation(at, "expecting a number".to_owned()),
+            }
+        }
+
+        let min_length = rule
+            .get_field("min_length")
+            .map(|min| rule_length(min, &rule_at.field("min_length")))
+            .transpose()?;
+        let max_length = rule
+            .get_field("max_length")
+            .map(|max| rule_length(max, &rule_at.field("max_length")))
+            .transpose()?;
+
+        if min_length.is_some() || max_length.is_some() {
+            match length(value) {
+                Some(len) if min_length.is_some_and(|min| len < min) => {
+                    self.violation(at, format!("length {len} is lower than {0}", min_length.unwrap()))
+                }
+                Some(len) if max_length.is_some_and(|max| len > max) => {
+                    self.violation(at, format!("length {len} is greater than {0}", max_length.unwrap()))
+                }
+                Some(_) => {}
+                None => self.violation(at, "expecting a string or an array".to_owned()),
+            }
+        }
+
+        if let Some(fields) = rule.get_field("fields") {
+            let fields_at = rule_at.field("fields");
+            let fields = rule_document(fields, &fields_at)?;
+
+            match <&Document>::try_from(value) {
+                Ok(doc) => self.validate_fields(fields, doc, at, &fields_at)?,
+                Err(_) => self.violation(at, "expecting a document".to_owned()),
+            }
+        }
+
+        if let Some(items) = rule.get_field("items") {
+            let items_at = rule_at.field("items");
+            let items = rule_document(items, &items_at)?;
+
+            match <&Array>::try_from(value) {
+                Ok(array) => {
+                    for (index, element) in array.iter().enumerate() {
+                        self.validate_value(items, element, &at.index(index), &items_at)?;
+                    }
+                }
+                Err(_) => self.violation(at, "expecting an array".to_owned()),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Longueur d'une chaîne, en caractères, ou d'une liste.
+fn length(value: &Knack) -> Option<u64> {
+    let len = if value.is::<str>() {
+        value.cast::<str>().chars().count()
+    } else if let Ok(fixed) = <&FixedStrRef>::try_from(value) {
+        fixed.as_str().chars().count()
+    } else {
+        <&Array>::try_from(value).ok()?.len()
+    };
+
+    u64::try_from(len).ok()
+}
+
+fn invalid_schema(path: &KnackPath, message: &'static str) -> Error {
+    Error::new(ErrorKind::InvalidSchema { path: path.clone(), message })
+}
+
+fn rule_document<'a>(rule: &'a Knack, at: &KnackPath) -> Result<&'a Document> {
+    <&Document>::try_from(rule).map_err(|_| invalid_schema(at, "expecting a document"))
+}
+
+fn rule_flag(rule: &Document, name: &str, rule_at: &KnackPath) -> Result<bool> {
+    rule.get_field(name)
+        .map(|flag| from_knack::<bool>(flag).map_err(|_| invalid_schema(&rule_at.field(name), "expecting a boolean")))
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+fn rule_str<'a>(rule: &'a Knack, at: &KnackPath) -> Result<&'a str> {
+    if !rule.is::<str>() {
+        return Err(invalid_schema(at, "expecting a string"));
+    }
+
+    Ok(rule.cast::<str>())
+}
+
+fn rule_number(rule: &Knack, at: &KnackPath) -> Result<f64> {
+    from_knack::<f64>(rule).map_err(|_| invalid_schema(at, "expecting a number"))
+}
+
+fn rule_length(rule: &Knack, at: &KnackPath) -> Result<u64> {
+    from_knack::<u64>(rule).map_err(|_| invalid_schema(at, "expecting a length"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        buffer::{stress::stubs::StressStub, BufferPool},
+        error::{Error, ErrorKind},
+        knack::{
+            buf::{IntoKnackBuf, KnackBuf},
+            builder::KnackBuilder,
+            document::DocBuilder,
+            path::KnackPath,
+            update::Update,
+            Knack,
+        },
+        pager::Pager,
+    };
+
+    use super::{Jar, JarDescription, JarSchema};
+
+    fn knack(json: &str) -> KnackBuf {
+        KnackBuilder::from_json(json).unwrap().into_knack_buf()
+    }
+
+    fn doc(json: &str) -> DocBuilder {
+        knack(json).cast::<DocBuilder>().to_owned()
+    }
+
+    fn json(knack: &Knack) -> String {
+        let mut out = Vec::default();
+        knack.to_json(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn violated_paths(error: Error) -> Vec<KnackPath> {
+        match error.kind {
+            ErrorKind::SchemaViolations(violations) => violations.into_iter().map(|violation| violation.path).collect(),
+            kind => panic!("expecting schema violations, got {kind}"),
+        }
+    }
+
+    fn path(path: &str) -> KnackPath {
+        KnackPath::parse(path).unwrap()
+    }
+
+    fn violations(schema: &JarSchema<'_>, json: &str) -> Vec<(KnackPath, String)> {
+        match schema.validate(knack(json).cast::<DocBuilder>()) {
+            Ok(()) => Vec::default(),
+            Err(error) => match error.kind {
+                ErrorKind::SchemaViolations(violations) => {
+                    violations.into_iter().map(|violation| (violation.path, violation.message)).collect()
+                }
+                kind => panic!("expecting schema violations, got {kind}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let schema = knack(
+            r#"{"fields": {
+                "name": {"kind": "str", "required": true, "min_length": 1, "max_length": 8},
+                "age": {"min": 0, "max": 150},
+                "address": {"kind": "document", "fields": {"city": {"kind": "str", "required": true}}},
+                "tags": {"kind": "array", "max_length": 2, "items": {"kind": "str", "max_length": 3}}
+            }}"#,
+        );
+        let schema = JarSchema::from(schema.cast::<DocBuilder>());
+
+        assert!(violations(&schema, r#"{"name": "Jean", "age": 42, "tags": ["a", "bc"], "extra": 1}"#).is_empty());
+
+        assert_eq!(
+            violations(&schema, r#"{"age": 200, "address": {"zip": 1}, "tags": ["abcd", 2, "x"]}"#),
+            vec![
+                (path("address.city"), "required field is missing".to_owned()),
+                (path("age"), "200 is greater than 150".to_owned()),
+                (path("name"), "required field is missing".to_owned()),
+                (path("tags"), "length 3 is greater than 2".to_owned()),
+                (path("tags[0]"), "length 4 is greater than 3".to_owned()),
+                (path("tags[1]"), "expecting str, got i64".to_owned()),
+            ]
+        );
+
+        assert_eq!(
+            violations(&schema, r#"{"name": 3, "age": "old", "address": [1]}"#),
+            vec![
+                (path("address"), "expecting document, got array<i64>".to_owned()),
+                (path("age"), "expecting a number".to_owned()),
+                (path("name"), "expecting str, got i64".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_schema() {
+        let schema = knack(r#"{"fields": {"name": {"required": "yes"}}}"#);
+        let schema = JarSchema::from(schema.cast::<DocBuilder>());
+
+        match schema.validate(knack("{}").cast::<DocBuilder>()).unwrap_err().kind {
+            ErrorKind::InvalidSchema { path: at, .. } => assert_eq!(at, path("fields.name.required")),
+            kind => panic!("expecting an invalid schema, got {kind}"),
+        }
+    }
+
+    #[test]
+    fn test_description_schema() {
+        let mut description = JarDescription::new("people");
+        assert!(description.schema().is_none());
+
+        let mut fields = DocBuilder::default();
+        let mut rule = DocBuilder::default();
+        rule.insert("kind", "str");
+        rule.insert("required", true);
+        fields.insert("name", rule);
+
+        let mut schema = DocBuilder::default();
+        schema.insert("fields", fields);
+        description.set_schema(schema);
+
+        let schema = description.schema().unwrap();
+        assert!(schema.validate(knack(r#"{"name": "Jean"}"#).cast::<DocBuilder>()).is_ok());
+        assert!(schema.validate(knack("{}").cast::<DocBuilder>()).is_err());
+    }
+
+    #[test]
+    fn test_jar_insert_and_update() {
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let mut description = JarDescription::new("people");
+        description.set_schema(doc(r#"{"fields": {"name": {"kind": "str", "required": true}, "age": {"max": 150}}}"#));
+        let mut jar = Jar::new(Pager::new(0, &buf_pool).unwrap(), description).unwrap();
+
+        let id = jar.insert(doc(r#"{"name": "Jean", "age": 42}"#)).unwrap();
+        assert_eq!(json(&jar.get(id).unwrap().unwrap()), r#"{"age":42,"name":"Jean"}"#);
+
+        // Un document invalide est rejeté, et rien n'est écrit.
+        let error = jar.insert(doc(r#"{"age": 200}"#)).unwrap_err();
+        assert_eq!(violated_paths(error), vec![path("age"), path("name")]);
+        assert_eq!(jar.len().unwrap(), 1);
+        assert!(jar.get(id + 1).unwrap().is_none());
+
+        assert!(jar.update(id, &Update::default().inc(path("age"), 1i64)).unwrap());
+        assert_eq!(json(&jar.get(id).unwrap().unwrap()), r#"{"age":43,"name":"Jean"}"#);
+
+        // Une mise à jour invalide est rejetée, et le document reste inchangé.
+        let error = jar.update(id, &Update::default().set(path("age"), 200i64).unset(path("name"))).unwrap_err();
+        assert_eq!(violated_paths(error), vec![path("age"), path("name")]);
+        assert_eq!(json(&jar.get(id).unwrap().unwrap()), r#"{"age":43,"name":"Jean"}"#);
+
+        assert!(!jar.update(id + 1, &Update::default().inc(path("age"), 1i64)).unwrap());
+    }
+
+    #[test]
+    fn test_jar_update_spilled_document() {
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let mut jar = Jar::new(Pager::new(0, &buf_pool).unwrap(), JarDescription::new("people")).unwrap();
+
+        let bio = "a".repeat(10_000);
+        let id = jar.insert(doc(&format!(r#"{{"bio": "{bio}"}}"#))).unwrap();
+        assert_eq!(json(&jar.get(id).unwrap().unwrap()), format!(r#"{{"bio":"{bio}"}}"#));
+
+        // Les pages de débordement de l'ancienne version sont libérées.
+        assert!(jar.update(id, &Update::default().set(path("bio"), "b")).unwrap());
+        assert_eq!(json(&jar.get(id).unwrap().unwrap()), r#"{"bio":"b"}"#);
+        assert!(jar.pager().free_count() > 0);
+    }
+}
@@ -1,13 +1,14 @@
-use std::ops::Deref;
+use std::{io::Write, ops::Deref};
 
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use itertools::Itertools;
 
-use crate::utils::Shift;
-
 use super::{
     buf::{IntoKnackBuf, KnackBuf},
+    error::{KnackError, KnackErrorKind},
     kind::{GetKnackKind, KnackKind, KnackKindDescriptor, ANY_TYPE_ID, ARRAY_FLAG, KNACK_KIND_DESCRIPTORS},
-    marker::{sized::{Sized, VarSized}, Comparable, Element, FixedSized},
+    marker::{sized::{Sized, VarSized}, Comparable, FixedSized},
+    result::KnackResult,
     FromKnack, Knack, KnackBuilder, KnackTypeId,
 };
 
@@ -55,7 +56,7 @@ impl super::marker::Element<KnackKind> {
     fn is_sized(&self) -> bool {
         KNACK_KIND_DESCRIPTORS
             .get(&self.type_id())
-            .map(|desc| desc.flags & KnackKindDescriptor::FLAG_SIZED > 0)
+            .map(KnackKindDescriptor::is_sized)
             .unwrap_or_default()
     }
 
@@ -75,17 +76,53 @@ impl super::marker::Array<KnackKind> {
     }
 }
 
+/// Liste de knacks.
+///
+/// # Structure
+/// Une liste d'éléments tous du même type de taille fixe est compacte :
+/// - kind: type des éléments | [ARRAY_FLAG] ;
+/// - elements: les knacks, placés bout à bout.
+///
+/// Toute autre liste (types hétérogènes, documents, chaînes, listes imbriquées…) :
+/// - kind: [ANY_TYPE_ID] | [ARRAY_FLAG] ;
+/// - len: u32, nombre d'éléments ;
+/// - offsets: u32 * len, position de chaque élément depuis le début de la zone des éléments ;
+/// - elements: les knacks, placés bout à bout.
 pub struct Array([u8]);
 
+impl TryFrom<&Knack> for &Array {
+    type Error = KnackError;
+
+    fn try_from(value: &Knack) -> Result<Self, Self::Error> {
+        if value.kind().try_as_array().is_none() {
+            return Err(KnackError::new(KnackErrorKind::WrongKind {
+                expected: <Array as GetKnackKind>::kind().to_owned(),
+                got: value.kind().to_owned(),
+            }));
+        }
+
+        unsafe { Ok(std::mem::transmute::<&Knack, &Array>(value)) }
+    }
+}
+
+impl TryFrom<&mut Knack> for &mut Array {
+    type Error = KnackError;
+
+    fn try_from(value: &mut Knack) -> Result<Self, Self::Error> {
+        <&Array>::try_from(&*value)?;
+        unsafe { Ok(std::mem::transmute::<&mut Knack, &mut Array>(value)) }
+    }
+}
+
 impl FromKnack for Array {
     type Output = Self;
 
-    fn try_ref_from_knack(value: &Knack) -> super::result::KnackResult<&Self::Output> {
-        todo!()
+    fn try_ref_from_knack(value: &Knack) -> KnackResult<&Self::Output> {
+        value.try_into()
     }
 
-    fn try_mut_from_knack(value: &mut Knack) -> super::result::KnackResult<&mut Self::Output> {
-        todo!()
+    fn try_mut_from_knack(value: &mut Knack) -> KnackResult<&mut Self::Output> {
+        value.try_into()
     }
 }
 
@@ -100,18 +137,84 @@ impl GetKnackKind for Array {
     }
 }
 
-
 impl Array {
+    const OFFSET_SIZE: usize = size_of::<u32>();
+
+    pub fn len(&self) -> usize {
+        match self.element_size() {
+            Some(0) => 0,
+            Some(size) => self.elements().len() / size,
+            None => usize::try_from((&self.0[1..]).read_u32::<LittleEndian>().unwrap()).unwrap(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     pub fn get<'array>(&'array self, index: &usize) -> Option<&'array Knack> {
-        if let Sized::Fixed(desc) = self.element_kind().as_sized() {
-            let offset = index * desc.outer_size();
-            let base = 1usize;
+        let index = *index;
 
-            let range = (0..desc.outer_size()).shift(base + offset);
-            Some(<&Knack>::from(&self.0[range]))
-        } else {
-            todo!("implement variable array")
+        if index >= self.len() {
+            return None;
+        }
+
+        let elements = self.elements();
+
+        match self.element_size() {
+            Some(size) => Some(Knack::from_ref(&elements[index * size..(index + 1) * size])),
+            None => {
+                let start = self.read_offset(index);
+                let end = if index + 1 < self.len() {
+                    self.read_offset(index + 1)
+                } else {
+                    elements.len()
+                };
+                Some(Knack::from_ref(&elements[start..end]))
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Knack> {
+        (0..self.len()).map(|index| self.get(&index).unwrap())
+    }
+
+    pub fn to_owned(&self) -> Vec<KnackBuilder> {
+        self.iter().map(KnackBuilder::from).collect()
+    }
+
+    /// Taille d'un élément, si la liste est compacte.
+    fn element_size(&self) -> Option<usize> {
+        if self.element_kind().type_id() == ANY_TYPE_ID {
+            return None;
+        }
+
+        let elements = self.elements();
+
+        if elements.is_empty() {
+            return Some(0);
+        }
+
+        Knack::from_ref(elements)
+            .kind()
+            .try_as_fixed_sized()
+            .map(FixedSized::outer_size)
+    }
+
+    /// Zone des éléments
+    fn elements(&self) -> &[u8] {
+        let mut base = self.kind().0.len();
+
+        if self.element_kind().type_id() == ANY_TYPE_ID {
+            base += Self::OFFSET_SIZE * (1 + self.len());
         }
+
+        &self.0[base..]
+    }
+
+    fn read_offset(&self, index: usize) -> usize {
+        let base = 1 + Self::OFFSET_SIZE * (1 + index);
+        usize::try_from((&self.0[base..]).read_u32::<LittleEndian>().unwrap()).unwrap()
     }
 
     fn element_kind(&self) -> &super::marker::Element<KnackKind> {
@@ -132,23 +235,48 @@ impl ArrayBuilder {
     }
 }
 
+impl From<Vec<KnackBuilder>> for ArrayBuilder {
+    fn from(value: Vec<KnackBuilder>) -> Self {
+        Self(value)
+    }
+}
+
 impl IntoKnackBuf for ArrayBuilder {
     type Buf = KnackBuf;
 
     fn into_knack_buf(self) -> KnackBuf {
         let kinds = self.kinds();
+        let mut buf: Vec<u8> = vec![];
 
         // Il s'agit d'une liste d'un seul type
         // de taille fixe,
         // on peut avoir une structure simplifiée
         if kinds.len() == 1 && kinds[0].try_as_fixed_sized().is_some() {
-            let element_kind = kinds[0].try_as_fixed_sized().unwrap();
-            
+            let mut kind = kinds[0].as_bytes().to_vec();
+            kind[0] |= ARRAY_FLAG;
+            buf.write_all(&kind).unwrap();
+
+            for element in self.0.into_iter().map(IntoKnackBuf::into_knack_buf) {
+                buf.write_all(element.as_bytes()).unwrap();
+            }
         } else {
+            let elements: Vec<KnackBuf> = self.0.into_iter().map(IntoKnackBuf::into_knack_buf).collect();
+
+            buf.write_all(<Array as GetKnackKind>::kind().as_bytes()).unwrap();
+            buf.write_u32::<LittleEndian>(u32::try_from(elements.len()).unwrap()).unwrap();
+
+            let mut offset = 0usize;
+            for element in elements.iter() {
+                buf.write_u32::<LittleEndian>(u32::try_from(offset).unwrap()).unwrap();
+                offset += element.as_bytes().len();
+            }
 
+            for element in elements.iter() {
+                buf.write_all(element.as_bytes()).unwrap();
+            }
         }
 
-        todo!("implement into_knack_buf for ArrayBuilder");
+        KnackBuf::from_bytes(buf)
     }
 }
 
@@ -156,13 +284,13 @@ impl FromKnack for ArrayBuilder {
     type Output = Array;
 
     fn try_ref_from_knack(value: &super::Knack) -> super::result::KnackResult<&Self::Output> {
-        todo!()
+        value.try_into()
     }
 
     fn try_mut_from_knack(
         value: &mut super::Knack,
     ) -> super::result::KnackResult<&mut Self::Output> {
-        todo!()
+        value.try_into()
     }
 }
 
@@ -174,3 +302,53 @@ impl Deref for ArrayBuilder {
         &self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Deref;
+
+    use crate::knack::{
+        buf::IntoKnackBuf, builder::{IntoKnackBuilder, KnackBuilder}, document::DocBuilder, Knack,
+    };
+
+    use super::Array;
+
+    #[test]
+    fn test_fixed_sized_array() {
+        let buf = vec![1u32, 2, 3].into_knack_builder().into_knack_buf();
+        let array = buf.cast::<Array>();
+
+        assert_eq!(buf.kind().as_bytes(), &[3 | 128]);
+        assert_eq!(array.len(), 3);
+        assert_eq!(array.get(&1).unwrap().cast::<u32>().to_owned(), 2);
+        assert!(array.get(&3).is_none());
+    }
+
+    #[test]
+    fn test_heterogeneous_array() {
+        let mut order = DocBuilder::default();
+        order.insert("sku", "JRN-18");
+        order.insert("qty", 2u8);
+
+        let values: Vec<KnackBuilder> = vec![
+            18u64.into_knack_builder(),
+            "hello".into_knack_builder(),
+            order.into_knack_builder(),
+            Vec::<KnackBuilder>::new().into_knack_builder(),
+        ];
+
+        let buf = values.into_knack_builder().into_knack_buf();
+        let array = buf.cast::<Array>();
+
+        assert_eq!(array.len(), 4);
+        assert!(array.get(&0).unwrap().cast::<u64>() == &18);
+        assert_eq!(array.get(&1).unwrap().cast::<str>().deref(), "hello");
+        assert_eq!(buf.get("2.sku").unwrap().cast::<str>().deref(), "JRN-18");
+        assert!(array.get(&3).unwrap().cast::<Array>().is_empty());
+        assert!(array.get(&4).is_none());
+
+        // Aller-retour par le constructeur.
+        let owned = KnackBuilder::from(Knack::from_ref(buf.as_bytes()));
+        assert_eq!(owned.get("2.qty").unwrap().cast::<u8>(), &2);
+    }
+}
@@ -5,7 +5,7 @@ use zerocopy::IntoBytes;
 
 use super::marker::{kernel::{AsKernelMut, AsKernelRef, IntoKernel}, ComparableAndFixedSized, FixedSized};
 
-use super::{builder::KnackBuilder, document::KeyValue, kind::GetKnackKind, Knack};
+use super::{builder::KnackBuilder, document::KeyValue, fixed_str::FixedStr, kind::GetKnackKind, timestamp::Timestamp, uuid::Uuid, Knack};
 
 pub trait IntoKnackBuf {
     type Buf: Borrow<Knack>;
@@ -76,7 +76,7 @@ impl FixedSized<KnackBuf> {
     }
 }
 
-#[derive(Hash)]
+#[derive(Hash, Clone)]
 /// Valeur entièrement détenue par l'objet (Owned value)
 pub struct KnackBuf(Vec<u8>);
 impl std::fmt::Display for KnackBuf {
@@ -131,10 +131,12 @@ impl From<(String, KnackBuilder)> for KnackBuf {
     fn from(kv: (String, KnackBuilder)) -> Self {
         let mut buf: Vec<u8> = vec![];
         buf.write_all(KeyValue::kind().as_kernel_ref().as_bytes()).unwrap();
+        let k = KnackBuf::from(kv.0);
         let v = kv.1.into_knack_buf();
-        let k = kv.0;
-        let size: u32 = u32::try_from(k.len() + v.as_bytes().len()).unwrap();
-        buf.write_all(&size.to_le_bytes()).unwrap();
+        let key_len = u32::try_from(k.as_bytes().len()).unwrap();
+        let value_len = u32::try_from(v.as_bytes().len()).unwrap();
+        buf.write_all(&key_len.to_le_bytes()).unwrap();
+        buf.write_all(&value_len.to_le_bytes()).unwrap();
         buf.write_all(k.as_bytes()).unwrap();
         buf.write_all(v.as_bytes()).unwrap();
         Self(buf)
@@ -357,6 +359,105 @@ impl From<f64> for KnackBuf {
         Self(buf)
     }
 }
+impl IntoKnackBuf for bool {
+    type Buf = ComparableAndFixedSized<KnackBuf>;
+
+    fn into_knack_buf(self) -> Self::Buf {
+        let buf = KnackBuf::from(self);
+        unsafe {
+            std::mem::transmute(buf)
+        }
+    }
+}
+impl From<bool> for KnackBuf {
+    fn from(value: bool) -> Self {
+        let mut buf: Vec<u8> = vec![];
+        buf.write_all(bool::kind().as_kernel_ref().as_bytes()).unwrap();
+        buf.write_u8(u8::from(value)).unwrap();
+        Self(buf)
+    }
+}
+impl IntoKnackBuf for () {
+    type Buf = KnackBuf;
+
+    fn into_knack_buf(self) -> Self::Buf {
+        KnackBuf::from(self)
+    }
+}
+impl From<()> for KnackBuf {
+    fn from(_value: ()) -> Self {
+        Self(<()>::kind().as_kernel_ref().as_bytes().to_vec())
+    }
+}
+impl IntoKnackBuf for &[u8] {
+    type Buf = KnackBuf;
+
+    fn into_knack_buf(self) -> Self::Buf {
+        KnackBuf::from(self)
+    }
+}
+impl From<&[u8]> for KnackBuf {
+    fn from(value: &[u8]) -> Self {
+        let mut buf: Vec<u8> = vec![];
+        buf.write_all(<[u8]>::kind().as_kernel_ref().as_bytes()).unwrap();
+        buf.write_all(value).unwrap();
+        Self(buf)
+    }
+}
+impl IntoKnackBuf for Timestamp {
+    type Buf = ComparableAndFixedSized<KnackBuf>;
+
+    fn into_knack_buf(self) -> Self::Buf {
+        let buf = KnackBuf::from(self);
+        unsafe {
+            std::mem::transmute(buf)
+        }
+    }
+}
+impl From<Timestamp> for KnackBuf {
+    fn from(value: Timestamp) -> Self {
+        let mut buf: Vec<u8> = vec![];
+        buf.write_all(Timestamp::kind().as_kernel_ref().as_bytes()).unwrap();
+        buf.write_all(&value.as_micros().to_le_bytes()).unwrap();
+        Self(buf)
+    }
+}
+impl IntoKnackBuf for Uuid {
+    type Buf = ComparableAndFixedSized<KnackBuf>;
+
+    fn into_knack_buf(self) -> Self::Buf {
+        let buf = KnackBuf::from(self);
+        unsafe {
+            std::mem::transmute(buf)
+        }
+    }
+}
+impl From<Uuid> for KnackBuf {
+    fn from(value: Uuid) -> Self {
+        let mut buf: Vec<u8> = vec![];
+        buf.write_all(Uuid::kind().as_kernel_ref().as_bytes()).unwrap();
+        buf.write_all(value.as_bytes()).unwrap();
+        Self(buf)
+    }
+}
+impl<const N: usize> IntoKnackBuf for FixedStr<N> {
+    type Buf = ComparableAndFixedSized<KnackBuf>;
+
+    fn into_knack_buf(self) -> Self::Buf {
+        let buf = KnackBuf::from(self);
+        unsafe {
+            std::mem::transmute(buf)
+        }
+    }
+}
+impl<const N: usize> From<FixedStr<N>> for KnackBuf {
+    fn from(value: FixedStr<N>) -> Self {
+        let mut buf: Vec<u8> = vec![];
+        buf.write_all(FixedStr::<N>::kind().as_kernel_ref().as_bytes()).unwrap();
+        buf.write_all(value.as_padded_bytes()).unwrap();
+        Self(buf)
+    }
+}
 impl IntoKnackBuf for String {
     type Buf = KnackBuf;
 
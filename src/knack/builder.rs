@@ -1,10 +1,16 @@
 use super::marker::kernel::{AsKernelRef, IntoKernel};
 
 use super::{
+    array::{Array, ArrayBuilder},
     buf::{IntoKnackBuf, KnackBuf},
     document::DocBuilder,
-    path::IntoKnackPath,
-    GetKnackKind, Knack, KnackKind, Str, F32, F64, I128, I16, I32, I64, I8, U128, U16, U32, U64,
+    fixed_str::FixedStr,
+    path::{IntoKnackPath, PathSegment},
+    result::KnackResult,
+    timestamp::Timestamp,
+    uuid::Uuid,
+    Bool, Bytes, FixedStrRef, GetKnackKind, Knack, KnackKind, Null, Str, TimestampRef, UuidRef, F32, F64, I128, I16,
+    I32, I64, I8, U128, U16, U32, U64,
 };
 
 pub trait IntoKnackBuilder {
@@ -19,6 +25,7 @@ pub trait FromKnackBuilder {
 }
 
 /// Type utilisé pour construire des valeurs stockables en base.
+#[derive(Clone)]
 pub enum KnackBuilder {
     Document(DocBuilder),
     Array(Vec<KnackBuilder>),
@@ -35,18 +42,55 @@ impl KnackBuilder {
     /// }
     /// val.get("foo.0.bar") retourne "hello world !"
     pub fn get<P: IntoKnackPath>(&self, path: P) -> Option<&KnackBuilder> {
-        let mut pth = path.into_value_path();
+        self.get_checked(path).ok().flatten()
+    }
+
+    /// Retourne toutes les valeurs désignées par le chemin (cf [Knack::get_all]).
+    pub fn get_all<P: IntoKnackPath>(&self, path: P) -> Vec<&KnackBuilder> {
+        self.get_all_checked(path).unwrap_or_default()
+    }
+
+    /// Recherche une valeur, retourne une erreur si le chemin est invalide (cf [Knack::get_checked]).
+    pub fn get_checked<P: IntoKnackPath>(&self, path: P) -> KnackResult<Option<&KnackBuilder>> {
+        let mut matches = Vec::with_capacity(1);
+        self.collect_matches(path.try_into_value_path()?.segments(), &mut matches, true);
+        Ok(matches.pop())
+    }
+
+    /// Retourne toutes les valeurs désignées par le chemin, ou une erreur s'il est invalide (cf [Knack::get_all_checked]).
+    pub fn get_all_checked<P: IntoKnackPath>(&self, path: P) -> KnackResult<Vec<&KnackBuilder>> {
+        let mut matches = Vec::default();
+        self.collect_matches(path.try_into_value_path()?.segments(), &mut matches, false);
+        Ok(matches)
+    }
+
+    pub(super) fn collect_matches<'a>(
+        &'a self,
+        segments: &[PathSegment],
+        matches: &mut Vec<&'a KnackBuilder>,
+        first_only: bool,
+    ) {
+        let Some((segment, tail)) = segments.split_first() else {
+            matches.push(self);
+            return;
+        };
 
-        match (pth.pop(), self) {
-            (Some(attr_name), KnackBuilder::Document(doc)) => {
-                doc.get_field(&attr_name).and_then(|attr| attr.get(pth))
+        let children: Box<dyn Iterator<Item = &KnackBuilder>> = match (segment, self) {
+            (PathSegment::Field(name), KnackBuilder::Document(doc)) => Box::new(doc.get_field(name).into_iter()),
+            (PathSegment::Wildcard, KnackBuilder::Document(doc)) => Box::new(doc.iter().map(|(_, value)| value)),
+            (PathSegment::AnyIndex | PathSegment::Wildcard, KnackBuilder::Array(array)) => Box::new(array.iter()),
+            (segment, KnackBuilder::Array(array)) => {
+                Box::new(segment.as_index().and_then(|index| array.get(index)).into_iter())
             }
-            (Some(str_index), KnackBuilder::Array(array)) => {
-                let index = str_index.parse::<usize>().ok()?;
-                array.get(index).and_then(|element| element.get(pth))
+            _ => return,
+        };
+
+        for child in children {
+            child.collect_matches(tail, matches, first_only);
+
+            if first_only && !matches.is_empty() {
+                return;
             }
-            (None, val) => Some(val),
-            _ => None,
         }
     }
 
@@ -67,16 +111,22 @@ impl KnackBuilder {
     pub fn kind(&self) -> &KnackKind {
         match self {
             KnackBuilder::Document(_) => DocBuilder::kind().as_kernel_ref(),
-            KnackBuilder::Array(_) => todo!(),
+            KnackBuilder::Array(_) => <Array as GetKnackKind>::kind(),
             KnackBuilder::Str(_) => str::kind().as_kernel_ref(),
             KnackBuilder::Other(value_buf) => value_buf.kind(),
         }
     }
 }
 
-impl IntoKnackBuilder for Vec<KnackBuilder> {
+impl IntoKnackBuilder for KnackBuilder {
+    fn into_knack_builder(self) -> KnackBuilder {
+        self
+    }
+}
+
+impl<T: IntoKnackBuilder> IntoKnackBuilder for Vec<T> {
     fn into_knack_builder(self) -> KnackBuilder {
-        KnackBuilder::from(self)
+        KnackBuilder::Array(self.into_iter().map(IntoKnackBuilder::into_knack_builder).collect())
     }
 }
 
@@ -100,6 +150,9 @@ impl From<&Knack> for KnackBuilder {
         if value.is::<str>() {
             return Self::Str(value.cast::<str>().to_owned());
         }
+        if let Ok(array) = <&Array>::try_from(value) {
+            return Self::Array(array.to_owned());
+        }
         Self::Other(value.to_owned())
     }
 }
@@ -110,7 +163,7 @@ impl IntoKnackBuf for KnackBuilder {
     fn into_knack_buf(self) -> KnackBuf {
         match self {
             KnackBuilder::Document(document) => document.into_knack_buf(),
-            KnackBuilder::Array(_array) => todo!("implement into knack buf for vec of knack builders"),
+            KnackBuilder::Array(array) => ArrayBuilder::from(array).into_knack_buf(),
             KnackBuilder::Str(string) => string.into_knack_buf(),
             KnackBuilder::Other(value) => value,
         }
@@ -412,6 +465,144 @@ impl FromKnackBuilder for str {
     }
 }
 
+impl FromKnackBuilder for bool {
+    type Output = Bool;
+
+    fn borrow_value(value: &KnackBuilder) -> &Self::Output {
+        if let KnackBuilder::Other(val) = value {
+            return val.cast::<Self>();
+        }
+        panic!("not a bool")
+    }
+
+    fn borrow_mut_value(value: &mut KnackBuilder) -> &mut Self::Output {
+        if let KnackBuilder::Other(buf) = value {
+            return buf.cast_mut::<Self>();
+        }
+        panic!("not a bool")
+    }
+}
+impl IntoKnackBuilder for bool {
+    fn into_knack_builder(self) -> KnackBuilder {
+        KnackBuilder::Other(self.into_knack_buf().into_kernel())
+    }
+}
+
+impl FromKnackBuilder for () {
+    type Output = Null;
+
+    fn borrow_value(value: &KnackBuilder) -> &Self::Output {
+        if let KnackBuilder::Other(val) = value {
+            return val.cast::<Self>();
+        }
+        panic!("not a ()")
+    }
+
+    fn borrow_mut_value(value: &mut KnackBuilder) -> &mut Self::Output {
+        if let KnackBuilder::Other(buf) = value {
+            return buf.cast_mut::<Self>();
+        }
+        panic!("not a ()")
+    }
+}
+impl IntoKnackBuilder for () {
+    fn into_knack_builder(self) -> KnackBuilder {
+        KnackBuilder::Other(self.into_knack_buf())
+    }
+}
+
+impl FromKnackBuilder for [u8] {
+    type Output = Bytes;
+
+    fn borrow_value(value: &KnackBuilder) -> &Self::Output {
+        if let KnackBuilder::Other(val) = value {
+            return val.cast::<Self>();
+        }
+        panic!("not a [u8]")
+    }
+
+    fn borrow_mut_value(value: &mut KnackBuilder) -> &mut Self::Output {
+        if let KnackBuilder::Other(buf) = value {
+            return buf.cast_mut::<Self>();
+        }
+        panic!("not a [u8]")
+    }
+}
+impl IntoKnackBuilder for &[u8] {
+    fn into_knack_builder(self) -> KnackBuilder {
+        KnackBuilder::Other(self.into_knack_buf())
+    }
+}
+
+impl FromKnackBuilder for Timestamp {
+    type Output = TimestampRef;
+
+    fn borrow_value(value: &KnackBuilder) -> &Self::Output {
+        if let KnackBuilder::Other(val) = value {
+            return val.cast::<Self>();
+        }
+        panic!("not a Timestamp")
+    }
+
+    fn borrow_mut_value(value: &mut KnackBuilder) -> &mut Self::Output {
+        if let KnackBuilder::Other(buf) = value {
+            return buf.cast_mut::<Self>();
+        }
+        panic!("not a Timestamp")
+    }
+}
+impl IntoKnackBuilder for Timestamp {
+    fn into_knack_builder(self) -> KnackBuilder {
+        KnackBuilder::Other(self.into_knack_buf().into_kernel())
+    }
+}
+
+impl<const N: usize> FromKnackBuilder for FixedStr<N> {
+    type Output = FixedStrRef;
+
+    fn borrow_value(value: &KnackBuilder) -> &Self::Output {
+        if let KnackBuilder::Other(val) = value {
+            return val.cast::<Self>();
+        }
+        panic!("not a FixedStr")
+    }
+
+    fn borrow_mut_value(value: &mut KnackBuilder) -> &mut Self::Output {
+        if let KnackBuilder::Other(buf) = value {
+            return buf.cast_mut::<Self>();
+        }
+        panic!("not a FixedStr")
+    }
+}
+impl<const N: usize> IntoKnackBuilder for FixedStr<N> {
+    fn into_knack_builder(self) -> KnackBuilder {
+        KnackBuilder::Other(self.into_knack_buf().into_kernel())
+    }
+}
+
+impl FromKnackBuilder for Uuid {
+    type Output = UuidRef;
+
+    fn borrow_value(value: &KnackBuilder) -> &Self::Output {
+        if let KnackBuilder::Other(val) = value {
+            return val.cast::<Self>();
+        }
+        panic!("not a Uuid")
+    }
+
+    fn borrow_mut_value(value: &mut KnackBuilder) -> &mut Self::Output {
+        if let KnackBuilder::Other(buf) = value {
+            return buf.cast_mut::<Self>();
+        }
+        panic!("not a Uuid")
+    }
+}
+impl IntoKnackBuilder for Uuid {
+    fn into_knack_builder(self) -> KnackBuilder {
+        KnackBuilder::Other(self.into_knack_buf().into_kernel())
+    }
+}
+
 impl IntoKnackBuilder for &str {
     fn into_knack_builder(self) -> KnackBuilder {
         KnackBuilder::Other(self.into_knack_buf())
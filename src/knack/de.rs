@@ -0,0 +1,344 @@
+//! Désérialisation de knacks en valeurs Rust (cf [serde]).
+//!
+//! Suit la correspondance de [super::ser], les chaînes et les octets sont empruntés au knack.
+//! Les horodatages sont lus comme un nombre de microsecondes, les UUID comme 16 octets.
+use std::ops::Deref;
+
+use serde::{
+    de::{self, value::BorrowedStrDeserializer, IntoDeserializer},
+    forward_to_deserialize_any, Deserialize,
+};
+
+use super::{
+    array::Array,
+    document::{DocAttributesIter, DocBuilder, Document},
+    error::KnackError,
+    kind::{
+        BOOL_TYPE_ID, BYTES_TYPE_ID, DOCUMENT_TYPE_ID, F32_TYPE_ID, FIXED_STR_TYPE_ID, F64_TYPE_ID, I128_TYPE_ID,
+        I16_TYPE_ID, I32_TYPE_ID, I64_TYPE_ID, I8_TYPE_ID, NULL_TYPE_ID, STR_TYPE_ID,
+        TIMESTAMP_TYPE_ID, U128_TYPE_ID, U16_TYPE_ID, U32_TYPE_ID, U64_TYPE_ID, U8_TYPE_ID,
+        UUID_TYPE_ID,
+    },
+    result::KnackResult,
+    timestamp::Timestamp,
+    uuid::Uuid,
+    FixedStrRef, Knack,
+};
+
+/// Désérialise une valeur depuis un knack.
+pub fn from_knack<'de, T: Deserialize<'de>>(knack: &'de Knack) -> KnackResult<T> {
+    T::deserialize(KnackDeserializer(knack))
+}
+
+impl Knack {
+    /// Désérialise la valeur (cf [from_knack]).
+    pub fn deserialize<'de, T: Deserialize<'de>>(&'de self) -> KnackResult<T> {
+        from_knack(self)
+    }
+}
+
+impl Document {
+    /// Désérialise le document (cf [from_knack]).
+    pub fn deserialize<'de, T: Deserialize<'de>>(&'de self) -> KnackResult<T> {
+        from_knack(Knack::from_ref(self.deref()))
+    }
+}
+
+/// Désérialiseur de knacks.
+pub struct KnackDeserializer<'de>(&'de Knack);
+
+impl<'de> From<&'de Knack> for KnackDeserializer<'de> {
+    fn from(value: &'de Knack) -> Self {
+        Self(value)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for KnackDeserializer<'de> {
+    type Error = KnackError;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> KnackResult<V::Value> {
+        let knack = self.0;
+
+        match *knack.kind().type_id() {
+            U8_TYPE_ID => visitor.visit_u8(**knack.cast::<u8>()),
+            U16_TYPE_ID => visitor.visit_u16(knack.cast::<u16>().get()),
+            U32_TYPE_ID => visitor.visit_u32(knack.cast::<u32>().to_owned()),
+            U64_TYPE_ID => visitor.visit_u64(knack.cast::<u64>().to_owned()),
+            U128_TYPE_ID => visitor.visit_u128(knack.cast::<u128>().to_owned()),
+            I8_TYPE_ID => visitor.visit_i8(knack.cast::<i8>().to_owned()),
+            I16_TYPE_ID => visitor.visit_i16(knack.cast::<i16>().to_owned()),
+            I32_TYPE_ID => visitor.visit_i32(knack.cast::<i32>().to_owned()),
+            I64_TYPE_ID => visitor.visit_i64(knack.cast::<i64>().to_owned()),
+            I128_TYPE_ID => visitor.visit_i128(knack.cast::<i128>().to_owned()),
+            F32_TYPE_ID => visitor.visit_f32(knack.cast::<f32>().to_owned()),
+            F64_TYPE_ID => visitor.visit_f64(knack.cast::<f64>().to_owned()),
+            BOOL_TYPE_ID => visitor.visit_bool(knack.cast::<bool>().to_owned()),
+            NULL_TYPE_ID => visitor.visit_unit(),
+            STR_TYPE_ID => visitor.visit_borrowed_str(knack.cast::<str>().deref()),
+            FIXED_STR_TYPE_ID => visitor.visit_borrowed_str(<&FixedStrRef>::try_from(knack)?.as_str()),
+            BYTES_TYPE_ID => visitor.visit_borrowed_bytes(knack.cast::<[u8]>().deref()),
+            TIMESTAMP_TYPE_ID => visitor.visit_i64(knack.cast::<Timestamp>().to_owned().as_micros()),
+            UUID_TYPE_ID => visitor.visit_borrowed_bytes(knack.cast::<Uuid>().as_bytes()),
+            DOCUMENT_TYPE_ID => visitor.visit_map(DocAccess {
+                iter: knack.cast::<DocBuilder>().iter(),
+                value: None,
+            }),
+            _ => match <&Array>::try_from(knack) {
+                Ok(array) => visitor.visit_seq(SeqAccess { array, index: 0 }),
+                Err(_) => Err(de::Error::custom(format!(
+                    "cannot deserialize a knack of kind {0}",
+                    knack.kind()
+                ))),
+            },
+        }
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> KnackResult<V::Value> {
+        if self.0.is::<()>() {
+            return visitor.visit_none();
+        }
+
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> KnackResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> KnackResult<V::Value> {
+        if self.0.is::<str>() {
+            return visitor.visit_enum(self.0.cast::<str>().deref().into_deserializer());
+        }
+
+        if self.0.is::<DocBuilder>() {
+            let mut fields = self.0.cast::<DocBuilder>().iter();
+
+            if let (Some(kv), None) = (fields.next(), fields.next()) {
+                return visitor.visit_enum(VariantAccess {
+                    variant: kv.key().cast::<str>().deref(),
+                    value: kv.value(),
+                });
+            }
+        }
+
+        Err(de::Error::custom(
+            "an enum must be either a string, or a document with a single field",
+        ))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/// Parcourt les champs d'un document.
+struct DocAccess<'de> {
+    iter: DocAttributesIter<'de>,
+    value: Option<&'de Knack>,
+}
+
+impl<'de> de::MapAccess<'de> for DocAccess<'de> {
+    type Error = KnackError;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> KnackResult<Option<K::Value>> {
+        match self.iter.next() {
+            None => Ok(None),
+            Some(kv) => {
+                self.value = Some(kv.value());
+                let key = BorrowedStrDeserializer::new(kv.key().cast::<str>().deref());
+                seed.deserialize(key).map(Some)
+            }
+        }
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> KnackResult<V::Value> {
+        let value = self.value.take().expect("value deserialized before its key");
+        seed.deserialize(KnackDeserializer(value))
+    }
+}
+
+/// Parcourt les éléments d'une liste.
+struct SeqAccess<'de> {
+    array: &'de Array,
+    index: usize,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqAccess<'de> {
+    type Error = KnackError;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(&mut self, seed: T) -> KnackResult<Option<T::Value>> {
+        match self.array.get(&self.index) {
+            None => Ok(None),
+            Some(element) => {
+                self.index += 1;
+                seed.deserialize(KnackDeserializer(element)).map(Some)
+            }
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.array.len() - self.index)
+    }
+}
+
+/// Variante d'une énumération stockée dans un document à un seul champ.
+struct VariantAccess<'de> {
+    variant: &'de str,
+    value: &'de Knack,
+}
+
+impl<'de> de::EnumAccess<'de> for VariantAccess<'de> {
+    type Error = KnackError;
+    type Variant = Self;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(self, seed: V) -> KnackResult<(V::Value, Self)> {
+        let variant = seed.deserialize(BorrowedStrDeserializer::<KnackError>::new(self.variant))?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for VariantAccess<'de> {
+    type Error = KnackError;
+
+    fn unit_variant(self) -> KnackResult<()> {
+        Err(de::Error::custom("expecting a unit variant, got a document"))
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> KnackResult<T::Value> {
+        seed.deserialize(KnackDeserializer(self.value))
+    }
+
+    fn tuple_variant<V: de::Visitor<'de>>(self, _len: usize, visitor: V) -> KnackResult<V::Value> {
+        de::Deserializer::deserialize_any(KnackDeserializer(self.value), visitor)
+    }
+
+    fn struct_variant<V: de::Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> KnackResult<V::Value> {
+        de::Deserializer::deserialize_any(KnackDeserializer(self.value), visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde::{Deserialize, Serialize};
+
+    use crate::knack::{buf::IntoKnackBuf, de::from_knack, document::DocBuilder, ser::to_knack_buf};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Status {
+        Active,
+        Suspended { reason: String },
+        Moved(String, u32),
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Order {
+        sku: String,
+        qty: u16,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Address {
+        city: String,
+        zip: u32,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Customer {
+        id: u64,
+        name: String,
+        balance: f64,
+        vip: bool,
+        nickname: Option<String>,
+        address: Address,
+        status: Status,
+        tags: HashMap<String, i16>,
+        orders: Vec<Order>,
+        scores: Vec<u8>,
+        location: (f64, f64),
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let customer = Customer {
+            id: 18,
+            name: "Jean Nicot".to_owned(),
+            balance: -12.5,
+            vip: true,
+            nickname: None,
+            address: Address {
+                city: "Jarnac".to_owned(),
+                zip: 16200,
+            },
+            status: Status::Suspended {
+                reason: "unpaid".to_owned(),
+            },
+            tags: HashMap::from_iter([("tobacco".to_owned(), -3)]),
+            orders: vec![
+                Order { sku: "JRN-18".to_owned(), qty: 2 },
+                Order { sku: "JRN-42".to_owned(), qty: 1 },
+            ],
+            scores: vec![12, 18],
+            location: (45.68, -0.17),
+        };
+
+        let buf = to_knack_buf(&customer).unwrap();
+        assert!(buf.is::<DocBuilder>());
+        assert!(buf.get("nickname").unwrap().is::<()>());
+        assert!(buf.get("vip").unwrap().cast::<bool>() == &true);
+        assert_eq!(buf.get("address.city").unwrap().cast::<str>(), "Jarnac");
+        assert_eq!(buf.get("orders.1.sku").unwrap().cast::<str>(), "JRN-42");
+        assert!(buf.get("scores.1").unwrap().cast::<u8>() == &18);
+
+        let decoded: Customer = from_knack(&buf).unwrap();
+        assert_eq!(decoded, customer);
+
+        let status: Status = buf.cast::<DocBuilder>().get_field("status").unwrap().deserialize().unwrap();
+        assert_eq!(status, customer.status);
+        assert_eq!(from_knack::<Status>(&to_knack_buf(&Status::Active).unwrap()).unwrap(), Status::Active);
+    }
+
+    #[test]
+    fn test_borrowed() {
+        #[derive(Deserialize)]
+        struct Borrowed<'a> {
+            name: &'a str,
+            raw: &'a [u8],
+        }
+
+        let mut doc = DocBuilder::default();
+        doc.insert("name", "Jarnac");
+        doc.insert("raw", &[0u8, 1, 2][..]);
+        let buf = doc.into_knack_buf();
+
+        let borrowed = buf.cast::<DocBuilder>().deserialize::<Borrowed>().unwrap();
+        assert_eq!(borrowed.name, "Jarnac");
+        assert_eq!(borrowed.raw, &[0, 1, 2]);
+    }
+
+    #[test]
+    fn test_sequences() {
+        assert_eq!(from_knack::<Vec<u8>>(&to_knack_buf(&vec![1u8, 2, 3]).unwrap()).unwrap(), vec![1, 2, 3]);
+        assert_eq!(from_knack::<(u8, String)>(&to_knack_buf(&(1u8, "a")).unwrap()).unwrap(), (1, "a".to_owned()));
+        assert_eq!(from_knack::<Vec<()>>(&to_knack_buf(&Vec::<()>::new()).unwrap()).unwrap(), vec![]);
+
+        let moved = Status::Moved("Cognac".to_owned(), 16100);
+        assert_eq!(from_knack::<Status>(&to_knack_buf(&moved).unwrap()).unwrap(), moved);
+    }
+}
@@ -4,15 +4,16 @@ use std::{
     ops::{Deref, Index},
 };
 
-use byteorder::{LittleEndian, ReadBytesExt};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use itertools::Itertools;
 
 use super::{error::KnackError, marker::kernel::AsKernelRef, result::KnackResult};
 
 use super::{
     buf::{IntoKnackBuf, KnackBuf},
     builder::IntoKnackBuilder,
-    path::IntoKnackPath,
-    FromKnack, GetKnackKind, Knack, KnackBuilder,
+    path::{IntoKnackPath, PathSegment},
+    FromKnack, GetKnackKind, Knack, KnackBuilder, KnackKind,
 };
 
 pub enum DocCow<'a> {
@@ -43,21 +44,27 @@ impl Deref for KeyValue {
 }
 
 impl KeyValue {
+    const KV_BASE: usize = 1 + 2 * size_of::<u32>();
+
     /// Lit une paire clé/valeur depuis la base de la tranche.
-    pub fn read_from_slice(_slice: &[u8]) -> &Self {
-        todo!();
-        /*
-        let kind = KnackKind::from(slice[0]);
-        KeyValue::KIND.assert_eq(&kind).expect("not a kv pair");
+    ///
+    /// # Structure
+    /// - kind: [KV_PAIR_TYPE_ID](super::kind) ;
+    /// - key_len: u32, taille de la clé ;
+    /// - value_len: u32, taille de la valeur ;
+    /// - key: Knack de type str ;
+    /// - value: Knack.
+    pub fn read_from_slice(slice: &[u8]) -> &Self {
+        KeyValue::kind()
+            .as_kernel_ref()
+            .assert_same(<&KnackKind>::try_from(slice).unwrap())
+            .expect("not a kv pair");
 
         let key_len = usize::try_from(Self::read_key_len(slice)).unwrap();
         let val_len = usize::try_from(Self::read_value_len(slice)).unwrap();
-        let kv_slice = &slice[..1usize + 2 * 4usize + key_len + val_len];
+        let kv_slice = &slice[..Self::KV_BASE + key_len + val_len];
 
-        unsafe {
-            std::mem::transmute(kv_slice)
-        }
-         */
+        unsafe { std::mem::transmute(kv_slice) }
     }
 
     fn read_key_len(slice: &[u8]) -> u32 {
@@ -85,12 +92,12 @@ impl KeyValue {
     }
 
     fn kv_space(&self) -> &[u8] {
-        let base = 1usize + 4usize + 4usize;
-        return &self.0[base..];
+        &self.0[Self::KV_BASE..]
     }
 
     fn key_slice(&self) -> &[u8] {
-        &self.kv_space()[..]
+        let key_len = usize::try_from(self.key_len()).unwrap();
+        &self.kv_space()[..key_len]
     }
 
     fn value_slice(&self) -> &[u8] {
@@ -100,27 +107,74 @@ impl KeyValue {
     }
 }
 
+/// Document stocké.
+///
+/// # Structure
+/// - kind: [DOCUMENT_TYPE_ID](super::kind) ;
+/// - len: u32, nombre de champs ;
+/// - offsets: u32 * len, position de chaque paire depuis le début de la zone des paires ;
+/// - pairs: les paires clé/valeur ([KeyValue]), triées par clé.
+///
+/// Le tri des paires permet de rechercher un champ par dichotomie, sans parcourir tout le document.
 pub struct Document([u8]);
 
 impl Document {
-    const KV_BASE: usize = 1;
+    const LEN_BASE: usize = 1;
+    const OFFSET_SIZE: usize = size_of::<u32>();
 
+    pub fn len(&self) -> usize {
+        usize::try_from((&self.0[Self::LEN_BASE..]).read_u32::<LittleEndian>().unwrap()).unwrap()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Parcourt les champs, dans l'ordre des clés.
     pub fn iter(&self) -> DocAttributesIter<'_> {
         DocAttributesIter {
             doc: self,
-            base: Self::KV_BASE,
+            base: self.pairs_base(),
         }
     }
 
+    /// Recherche un champ par son nom, en O(log n).
     pub fn get_field(&self, name: &str) -> Option<&Knack> {
-        self.iter()
-            .filter(|kv| kv.key().cast::<str>() == name)
-            .map(|kv| kv.value())
-            .next()
+        let (mut low, mut high) = (0, self.len());
+
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let kv = self.kv_at(mid);
+
+            match kv.key().cast::<str>().deref().cmp(name) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => return Some(kv.value()),
+            }
+        }
+
+        None
     }
 
-    pub fn get<Path: IntoKnackPath>(&self, _key: Path) -> &Knack {
-        todo!()
+    /// Recherche une valeur de manière récursive (cf [Knack::get]).
+    pub fn get<Path: IntoKnackPath>(&self, path: Path) -> Option<&Knack> {
+        Knack::from_ref(&self.0).get(path)
+    }
+
+    /// Recherche une valeur, retourne une erreur si le chemin est invalide (cf [Knack::get_checked]).
+    pub fn get_checked<Path: IntoKnackPath>(&self, path: Path) -> KnackResult<Option<&Knack>> {
+        Knack::from_ref(&self.0).get_checked(path)
+    }
+
+    fn kv_at(&self, index: usize) -> &KeyValue {
+        let offset_base = Self::LEN_BASE + Self::OFFSET_SIZE * (1 + index);
+        let offset = usize::try_from((&self.0[offset_base..]).read_u32::<LittleEndian>().unwrap()).unwrap();
+        KeyValue::read_from_slice(&self.0[self.pairs_base() + offset..])
+    }
+
+    /// Début de la zone des paires
+    fn pairs_base(&self) -> usize {
+        Self::LEN_BASE + Self::OFFSET_SIZE * (1 + self.len())
     }
 
     pub fn to_owned(&self) -> DocBuilder {
@@ -173,7 +227,7 @@ impl<'a> Iterator for DocAttributesIter<'a> {
     type Item = &'a KeyValue;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.base >= self.doc.len() {
+        if self.base >= self.doc.0.len() {
             return None;
         }
 
@@ -184,7 +238,7 @@ impl<'a> Iterator for DocAttributesIter<'a> {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct DocBuilder(HashMap<String, KnackBuilder>);
 
 impl FromKnack for DocBuilder {
@@ -213,6 +267,8 @@ impl FromIterator<(String, KnackBuilder)> for DocBuilder {
     }
 }
 
+/// # Panics
+/// Si le chemin ne désigne aucune valeur, ou s'il est invalide (cf [DocBuilder::try_get]).
 impl<Q> Index<Q> for DocBuilder
 where
     Q: IntoKnackPath,
@@ -231,18 +287,69 @@ impl DocBuilder {
         self.0.insert(key.to_string(), value);
     }
 
+    /// Recherche une valeur de manière récursive (cf [Knack::get]).
     pub fn try_get<P: IntoKnackPath>(&self, k: P) -> Option<&KnackBuilder> {
-        let mut pth = k.into_value_path();
+        self.get_checked(k).ok().flatten()
+    }
 
-        match pth.pop() {
-            None => None,
-            Some(attr_name) => self.get_field(&attr_name).and_then(|val| val.get(pth)),
+    /// Retourne toutes les valeurs désignées par le chemin (cf [Knack::get_all]).
+    pub fn get_all<P: IntoKnackPath>(&self, k: P) -> Vec<&KnackBuilder> {
+        self.get_all_checked(k).unwrap_or_default()
+    }
+
+    /// Recherche une valeur, retourne une erreur si le chemin est invalide (cf [Knack::get_checked]).
+    pub fn get_checked<P: IntoKnackPath>(&self, k: P) -> KnackResult<Option<&KnackBuilder>> {
+        let path = k.try_into_value_path()?;
+
+        if path.is_root() {
+            return Ok(None);
+        }
+
+        let mut matches = Vec::with_capacity(1);
+        self.collect_matches(path.segments(), &mut matches, true);
+        Ok(matches.pop())
+    }
+
+    /// Retourne toutes les valeurs désignées par le chemin, ou une erreur s'il est invalide (cf [Knack::get_all_checked]).
+    pub fn get_all_checked<P: IntoKnackPath>(&self, k: P) -> KnackResult<Vec<&KnackBuilder>> {
+        let mut matches = Vec::default();
+        self.collect_matches(k.try_into_value_path()?.segments(), &mut matches, false);
+        Ok(matches)
+    }
+
+    fn collect_matches<'a>(&'a self, segments: &[PathSegment], matches: &mut Vec<&'a KnackBuilder>, first_only: bool) {
+        let children: Box<dyn Iterator<Item = &KnackBuilder>> = match segments.first() {
+            Some(PathSegment::Field(name)) => Box::new(self.get_field(name).into_iter()),
+            Some(PathSegment::Wildcard) => Box::new(self.0.values()),
+            _ => return,
+        };
+
+        for child in children {
+            child.collect_matches(&segments[1..], matches, first_only);
+
+            if first_only && !matches.is_empty() {
+                return;
+            }
         }
     }
 
     pub fn get_field(&self, k: &str) -> Option<&KnackBuilder> {
         self.0.get(k)
     }
+
+    pub fn get_field_mut(&mut self, k: &str) -> Option<&mut KnackBuilder> {
+        self.0.get_mut(k)
+    }
+
+    /// Retire un champ du document.
+    pub fn remove(&mut self, k: &str) -> Option<KnackBuilder> {
+        self.0.remove(k)
+    }
+
+    /// Parcourt les champs, dans un ordre arbitraire.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &KnackBuilder)> {
+        self.0.iter().map(|(key, value)| (key.as_str(), value))
+    }
 }
 
 impl IntoKnackBuf for DocBuilder {
@@ -254,7 +361,21 @@ impl IntoKnackBuf for DocBuilder {
         buf.write_all(&DocBuilder::kind().as_kernel_ref().as_bytes())
             .unwrap();
 
-        for kv in self.0.into_iter().map(IntoKnackBuf::into_knack_buf) {
+        let pairs: Vec<KnackBuf> = self.0
+            .into_iter()
+            .sorted_by(|(a, _), (b, _)| a.cmp(b))
+            .map(IntoKnackBuf::into_knack_buf)
+            .collect();
+
+        buf.write_u32::<LittleEndian>(u32::try_from(pairs.len()).unwrap()).unwrap();
+
+        let mut offset = 0usize;
+        for kv in pairs.iter() {
+            buf.write_u32::<LittleEndian>(u32::try_from(offset).unwrap()).unwrap();
+            offset += kv.as_bytes().len();
+        }
+
+        for kv in pairs.iter() {
             buf.write_all(kv.as_bytes()).unwrap();
         }
 
@@ -264,7 +385,9 @@ impl IntoKnackBuf for DocBuilder {
 
 #[cfg(test)]
 mod tests {
-    use crate::knack::document::DocBuilder;
+    use std::ops::Deref;
+
+    use crate::knack::{buf::IntoKnackBuf, document::DocBuilder};
 
     #[test]
     pub fn test_insert() {
@@ -279,6 +402,42 @@ mod tests {
         assert!(doc["foo"].is::<DocBuilder>());
         assert!(doc["foo.bar"].cast::<str>() == "hello world !");
         assert!(doc["foo.barbar"].cast::<u8>() == &128u8);
+
+        // Un chemin invalide ne désigne aucune valeur.
+        assert!(doc.try_get("foo..bar").is_none());
+        assert!(doc.get_checked("foo..bar").is_err());
     }
-}
 
+    #[test]
+    pub fn test_stored_document() {
+        let mut doc = DocBuilder::default();
+
+        for i in 0..1000u32 {
+            doc.insert(&format!("field-{i}"), i);
+        }
+
+        let mut address = DocBuilder::default();
+        address.insert("city", "Jarnac");
+        doc.insert("address", address);
+        doc.insert("", true);
+
+        let buf = doc.into_knack_buf();
+        let doc = buf.cast::<DocBuilder>();
+
+        assert_eq!(doc.len(), 1002);
+        assert_eq!(doc.get_field("field-517").unwrap().cast::<u32>().to_owned(), 517);
+        assert!(doc.get_field("").unwrap().cast::<bool>() == &true);
+        assert!(doc.get_field("field-1000").is_none());
+        assert_eq!(doc.get("address.city").unwrap().cast::<str>().deref(), "Jarnac");
+        assert!(doc.get("address.zip").is_none());
+
+        // Les champs sont parcourus dans l'ordre des clés.
+        let keys: Vec<_> = doc.iter().map(|kv| kv.key().cast::<str>().to_string()).collect();
+        assert!(keys.is_sorted());
+        assert_eq!(keys.len(), 1002);
+
+        let empty = DocBuilder::default().into_knack_buf();
+        assert!(empty.cast::<DocBuilder>().is_empty());
+        assert!(empty.cast::<DocBuilder>().get_field("foo").is_none());
+    }
+}
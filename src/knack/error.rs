@@ -1,6 +1,8 @@
-use super::kind::EmcompassingKnackKind;
+use std::fmt::Display;
 
-#[derive(Debug)]
+use super::{kind::EmcompassingKnackKind, path::KnackPath};
+
+#[derive(Debug, Clone)]
 pub struct KnackError {
     kind: KnackErrorKind,
 }
@@ -17,8 +19,59 @@ impl KnackError {
     }
 }
 
-#[derive(Debug)]
+impl Display for KnackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            KnackErrorKind::WrongKind { got, expected } => {
+                write!(f, "wrong knack kind, expecting {expected}, got {got}")
+            }
+            KnackErrorKind::Custom(message) => write!(f, "{message}"),
+            KnackErrorKind::Json { position, message } => {
+                write!(f, "invalid JSON at byte {position}: {message}")
+            }
+            KnackErrorKind::Path { position, message } => {
+                write!(f, "invalid path at byte {position}: {message}")
+            }
+            KnackErrorKind::Update { path, message } => {
+                write!(f, "cannot update {path}: {message}")
+            }
+            KnackErrorKind::Patch { path, message } => {
+                write!(f, "cannot patch {path}: {message}")
+            }
+            KnackErrorKind::FixedStr { capacity, message } => {
+                write!(f, "invalid fixed-str({capacity}): {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for KnackError {}
+
+impl serde::ser::Error for KnackError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self::new(KnackErrorKind::Custom(msg.to_string()))
+    }
+}
+
+impl serde::de::Error for KnackError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self::new(KnackErrorKind::Custom(msg.to_string()))
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum KnackErrorKind {
     WrongKind { got: EmcompassingKnackKind, expected: EmcompassingKnackKind },
+    /// Erreur levée lors de la (dé)sérialisation d'une valeur (cf [super::ser], [super::de])
+    Custom(String),
+    /// Erreur levée lors de l'analyse d'un texte JSON (cf [super::json])
+    Json { position: usize, message: &'static str },
+    /// Erreur levée lors de l'analyse d'un chemin (cf [super::path])
+    Path { position: usize, message: &'static str },
+    /// Opérateur de mise à jour inapplicable à la valeur désignée (cf [super::update])
+    Update { path: KnackPath, message: &'static str },
+    /// Opération d'un correctif inapplicable à la valeur (cf [super::patch])
+    Patch { path: KnackPath, message: &'static str },
+    /// Chaîne ne pouvant être stockée dans une chaîne de taille fixe (cf [super::fixed_str])
+    FixedStr { capacity: usize, message: &'static str },
 }
-
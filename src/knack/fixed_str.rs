@@ -0,0 +1,138 @@
+//! Chaîne de taille fixe, pour indexer des codes courts (codes pays ISO, références produit…).
+//!
+//! # Format
+//! Le type du knack encode la capacité N sur son second octet : [14, N].
+//! La valeur est stockée sur exactement N octets, complétée par des octets nuls.
+//!
+//! # Règles
+//! - une chaîne de plus de N octets est refusée ;
+//! - une chaîne contenant un octet nul est refusée, les octets nuls finaux étant retirés à la lecture ;
+//! - la comparaison est faite octet par octet sur la valeur complétée, "ab" < "abc" < "b".
+use std::{fmt::Display, ops::Deref, str::FromStr};
+
+use super::{
+    error::{KnackError, KnackErrorKind},
+    result::KnackResult,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FixedStr<const N: usize>([u8; N]);
+
+impl<const N: usize> FixedStr<N> {
+    /// Capacité de la chaîne, telle qu'encodée dans son type de knack.
+    pub const CAPACITY: u8 = {
+        assert!(N > 0 && N <= u8::MAX as usize, "the capacity of a fixed-str must be within 1..=255");
+        N as u8
+    };
+
+    pub fn new(value: &str) -> KnackResult<Self> {
+        let _ = Self::CAPACITY;
+
+        if value.len() > N {
+            return Err(KnackError::fixed_str(N, "string is too long"));
+        }
+
+        if value.as_bytes().contains(&0) {
+            return Err(KnackError::fixed_str(N, "string contains a null byte"));
+        }
+
+        let mut bytes = [0; N];
+        bytes[..value.len()].copy_from_slice(value.as_bytes());
+        Ok(Self(bytes))
+    }
+
+    pub fn as_str(&self) -> &str {
+        // La chaîne a été validée à sa création.
+        unsafe { unpad_unchecked(&self.0) }
+    }
+
+    /// Octets de la chaîne, complétés par des octets nuls.
+    pub fn as_padded_bytes(&self) -> &[u8; N] {
+        &self.0
+    }
+}
+
+impl KnackError {
+    fn fixed_str(capacity: usize, message: &'static str) -> Self {
+        Self::new(KnackErrorKind::FixedStr { capacity, message })
+    }
+}
+
+/// Retire le remplissage d'une chaîne de taille fixe.
+///
+/// Échoue si la chaîne n'est pas valide en UTF-8, ce qui ne peut provenir que d'une page corrompue.
+pub(super) fn unpad(bytes: &[u8]) -> KnackResult<&str> {
+    std::str::from_utf8(trim(bytes)).map_err(|_| KnackError::fixed_str(bytes.len(), "string is not valid UTF-8"))
+}
+
+/// Retire le remplissage d'une chaîne de taille fixe déjà validée (cf [unpad]).
+///
+/// # Safety
+/// La chaîne doit être valide en UTF-8.
+pub(super) unsafe fn unpad_unchecked(bytes: &[u8]) -> &str {
+    std::str::from_utf8_unchecked(trim(bytes))
+}
+
+fn trim(bytes: &[u8]) -> &[u8] {
+    let len = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    &bytes[..len]
+}
+
+impl<const N: usize> Deref for FixedStr<N> {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> Display for FixedStr<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<const N: usize> FromStr for FixedStr<N> {
+    type Err = KnackError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+impl<const N: usize> TryFrom<&str> for FixedStr<N> {
+    type Error = KnackError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FixedStr;
+    use crate::knack::{buf::KnackBuf, FromKnack};
+
+    #[test]
+    fn test_padding() {
+        let fr = FixedStr::<3>::new("FR").unwrap();
+        assert_eq!(fr.as_padded_bytes(), b"FR\0");
+        assert_eq!(fr.as_str(), "FR");
+        assert_eq!(fr.to_string(), "FR");
+
+        assert!(FixedStr::<3>::new("FRA").is_ok());
+        assert!(FixedStr::<3>::new("FRAN").is_err());
+        assert!(FixedStr::<3>::new("F\0R").is_err());
+
+        assert!(FixedStr::<3>::new("ab").unwrap() < FixedStr::<3>::new("abc").unwrap());
+        assert!(FixedStr::<3>::new("abc").unwrap() < FixedStr::<3>::new("b").unwrap());
+    }
+
+    #[test]
+    fn test_corrupted_bytes() {
+        // Octets invalides en UTF-8, comme sur une page corrompue.
+        let corrupted = KnackBuf::from_bytes(vec![14, 3, 0xFF, b'R', 0]);
+        assert!(FixedStr::<3>::try_ref_from_knack(&corrupted).is_err());
+        assert!(corrupted.to_json(&mut Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,535 @@
+//! Import et export JSON des knacks.
+//!
+//! # Correspondance
+//! - nombres entiers : i64, ou u64 s'ils dépassent i64, ou f64 s'ils dépassent u64 ;
+//! - nombres à virgule ou à exposant : f64 ;
+//! - booléens : bool ;
+//! - null : null ;
+//! - chaînes : str ;
+//! - objets : document ;
+//! - tableaux : liste.
+//!
+//! A l'export, les chaînes de taille fixe, les horodatages et les UUID sont écrits en chaînes
+//! (cf [super::fixed_str], [Timestamp], [Uuid]), les octets bruts n'ont pas de représentation.
+//!
+//! L'analyse et l'écriture se font en une seule passe, sans représentation intermédiaire.
+use std::{
+    io::{self, Write},
+    ops::Deref,
+};
+
+use super::{
+    builder::{IntoKnackBuilder, KnackBuilder},
+    document::DocBuilder,
+    error::{KnackError, KnackErrorKind},
+    kind::{
+        BOOL_TYPE_ID, DOCUMENT_TYPE_ID, F32_TYPE_ID, FIXED_STR_TYPE_ID, F64_TYPE_ID, I128_TYPE_ID, I16_TYPE_ID,
+        I32_TYPE_ID, I64_TYPE_ID, I8_TYPE_ID, NULL_TYPE_ID, STR_TYPE_ID, TIMESTAMP_TYPE_ID,
+        U128_TYPE_ID, U16_TYPE_ID, U32_TYPE_ID, U64_TYPE_ID, U8_TYPE_ID, UUID_TYPE_ID,
+    },
+    result::KnackResult,
+    timestamp::Timestamp,
+    uuid::Uuid,
+    Array, FixedStrRef, Knack,
+};
+
+impl Knack {
+    /// Ecrit la valeur au format JSON.
+    pub fn to_json<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        match *self.kind().type_id() {
+            U8_TYPE_ID => write!(out, "{0}", **self.cast::<u8>()),
+            U16_TYPE_ID => write!(out, "{0}", self.cast::<u16>().get()),
+            U32_TYPE_ID => write!(out, "{0}", self.cast::<u32>().to_owned()),
+            U64_TYPE_ID => write!(out, "{0}", self.cast::<u64>().to_owned()),
+            U128_TYPE_ID => write!(out, "{0}", self.cast::<u128>().to_owned()),
+            I8_TYPE_ID => write!(out, "{0}", self.cast::<i8>().to_owned()),
+            I16_TYPE_ID => write!(out, "{0}", self.cast::<i16>().to_owned()),
+            I32_TYPE_ID => write!(out, "{0}", self.cast::<i32>().to_owned()),
+            I64_TYPE_ID => write!(out, "{0}", self.cast::<i64>().to_owned()),
+            I128_TYPE_ID => write!(out, "{0}", self.cast::<i128>().to_owned()),
+            F32_TYPE_ID => write_float(out, self.cast::<f32>().to_owned()),
+            F64_TYPE_ID => write_float(out, self.cast::<f64>().to_owned()),
+            BOOL_TYPE_ID => write!(out, "{0}", self.cast::<bool>().to_owned()),
+            NULL_TYPE_ID => out.write_all(b"null"),
+            STR_TYPE_ID => write_str(out, self.cast::<str>().deref()),
+            FIXED_STR_TYPE_ID => match <&FixedStrRef>::try_from(self) {
+                Ok(fixed) => write_str(out, fixed.as_str()),
+                Err(error) => Err(io::Error::new(io::ErrorKind::InvalidData, error)),
+            },
+            TIMESTAMP_TYPE_ID => write!(out, "\"{0}\"", self.cast::<Timestamp>().to_owned()),
+            UUID_TYPE_ID => write!(out, "\"{0}\"", self.cast::<Uuid>().to_owned()),
+            DOCUMENT_TYPE_ID => {
+                out.write_all(b"{")?;
+
+                for (i, kv) in self.cast::<DocBuilder>().iter().enumerate() {
+                    if i > 0 {
+                        out.write_all(b",")?;
+                    }
+
+                    write_str(out, kv.key().cast::<str>().deref())?;
+                    out.write_all(b":")?;
+                    kv.value().to_json(out)?;
+                }
+
+                out.write_all(b"}")
+            }
+            _ => match <&Array>::try_from(self) {
+                Ok(array) => {
+                    out.write_all(b"[")?;
+
+                    for (i, element) in array.iter().enumerate() {
+                        if i > 0 {
+                            out.write_all(b",")?;
+                        }
+
+                        element.to_json(out)?;
+                    }
+
+                    out.write_all(b"]")
+                }
+                Err(_) => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("cannot export a knack of kind {0} to JSON", self.kind()),
+                )),
+            },
+        }
+    }
+}
+
+impl KnackBuilder {
+    /// Construit une valeur depuis un texte JSON.
+    ///
+    /// Les erreurs indiquent la position, en octets, à laquelle l'analyse a échoué.
+    pub fn from_json(json: &str) -> KnackResult<Self> {
+        let mut parser = JsonParser::new(json);
+        parser.skip_whitespaces();
+        let value = parser.parse_value(0)?;
+        parser.skip_whitespaces();
+
+        if parser.pos < parser.bytes.len() {
+            return Err(parser.error("trailing characters"));
+        }
+
+        Ok(value)
+    }
+}
+
+impl KnackError {
+    fn json(position: usize, message: &'static str) -> Self {
+        Self::new(KnackErrorKind::Json { position, message })
+    }
+}
+
+/// Un flottant sans partie décimale est écrit avec, afin d'être relu comme un flottant.
+fn write_float<W: Write, F: Into<f64> + Copy + std::fmt::Debug>(out: &mut W, value: F) -> io::Result<()> {
+    let float: f64 = value.into();
+
+    if !float.is_finite() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("cannot export {float} to JSON"),
+        ));
+    }
+
+    write!(out, "{0:?}", value)
+}
+
+pub(super) fn write_str<W: Write>(out: &mut W, value: &str) -> io::Result<()> {
+    out.write_all(b"\"")?;
+
+    let mut start = 0;
+
+    for (i, byte) in value.bytes().enumerate() {
+        let escaped: &[u8] = match byte {
+            b'"' => b"\\\"",
+            b'\\' => b"\\\\",
+            b'\n' => b"\\n",
+            b'\r' => b"\\r",
+            b'\t' => b"\\t",
+            0x08 => b"\\b",
+            0x0C => b"\\f",
+            0x00..0x20 => b"",
+            _ => continue,
+        };
+
+        out.write_all(&value.as_bytes()[start..i])?;
+        start = i + 1;
+
+        if escaped.is_empty() {
+            write!(out, "\\u{0:04x}", byte)?;
+        } else {
+            out.write_all(escaped)?;
+        }
+    }
+
+    out.write_all(&value.as_bytes()[start..])?;
+    out.write_all(b"\"")
+}
+
+/// Analyseur JSON par descente récursive.
+struct JsonParser<'a> {
+    src: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    /// Profondeur maximale des objets et des tableaux imbriqués
+    const MAX_DEPTH: usize = 128;
+
+    fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            bytes: src.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    fn error(&self, message: &'static str) -> KnackError {
+        KnackError::json(self.pos, message)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn skip_whitespaces(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8, message: &'static str) -> KnackResult<()> {
+        if self.peek() != Some(byte) {
+            return Err(self.error(message));
+        }
+
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn expect_literal(&mut self, literal: &str) -> KnackResult<()> {
+        if !self.src[self.pos..].starts_with(literal) {
+            return Err(self.error("invalid literal"));
+        }
+
+        self.pos += literal.len();
+        Ok(())
+    }
+
+    fn parse_value(&mut self, depth: usize) -> KnackResult<KnackBuilder> {
+        if depth > Self::MAX_DEPTH {
+            return Err(self.error("nesting is too deep"));
+        }
+
+        match self.peek() {
+            None => Err(self.error("unexpected end of input")),
+            Some(b'{') => self.parse_object(depth),
+            Some(b'[') => self.parse_array(depth),
+            Some(b'"') => self.parse_str().map(|s| s.as_str().into_knack_builder()),
+            Some(b't') => self.expect_literal("true").map(|_| true.into_knack_builder()),
+            Some(b'f') => self.expect_literal("false").map(|_| false.into_knack_builder()),
+            Some(b'n') => self.expect_literal("null").map(|_| ().into_knack_builder()),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            Some(_) => Err(self.error("unexpected character")),
+        }
+    }
+
+    fn parse_object(&mut self, depth: usize) -> KnackResult<KnackBuilder> {
+        self.pos += 1;
+        let mut doc = DocBuilder::default();
+        self.skip_whitespaces();
+
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(doc.into_knack_builder());
+        }
+
+        loop {
+            self.skip_whitespaces();
+
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expecting an object key"));
+            }
+
+            let key = self.parse_str()?;
+            self.skip_whitespaces();
+            self.expect(b':', "expecting ':'")?;
+            self.skip_whitespaces();
+
+            let value = self.parse_value(depth + 1)?;
+            doc.insert(&key, value);
+
+            self.skip_whitespaces();
+
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(doc.into_knack_builder());
+                }
+                _ => return Err(self.error("expecting ',' or '}'")),
+            }
+        }
+    }
+
+    fn parse_array(&mut self, depth: usize) -> KnackResult<KnackBuilder> {
+        self.pos += 1;
+        let mut array = Vec::default();
+        self.skip_whitespaces();
+
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(array.into_knack_builder());
+        }
+
+        loop {
+            self.skip_whitespaces();
+            array.push(self.parse_value(depth + 1)?);
+
+            self.skip_whitespaces();
+
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(array.into_knack_builder());
+                }
+                _ => return Err(self.error("expecting ',' or ']'")),
+            }
+        }
+    }
+
+    fn parse_str(&mut self) -> KnackResult<String> {
+        self.pos += 1;
+        let mut value = String::default();
+
+        loop {
+            // Les caractères non échappés sont copiés d'un bloc.
+            let start = self.pos;
+
+            while let Some(byte) = self.peek().filter(|b| !matches!(*b, b'"' | b'\\' | 0x00..0x20)) {
+                self.pos += 1;
+
+                if byte >= 0x80 {
+                    // Le texte source est de l'UTF-8 valide.
+                    while self.peek().is_some_and(|b| b & 0xC0 == 0x80) {
+                        self.pos += 1;
+                    }
+                }
+            }
+
+            value.push_str(&self.src[start..self.pos]);
+
+            match self.peek() {
+                None => return Err(self.error("unterminated string")),
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(value);
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let escaped = self.parse_escape()?;
+                    value.push(escaped);
+                }
+                Some(_) => return Err(self.error("control characters must be escaped")),
+            }
+        }
+    }
+
+    fn parse_escape(&mut self) -> KnackResult<char> {
+        let escaped = match self.peek() {
+            Some(b'"') => '"',
+            Some(b'\\') => '\\',
+            Some(b'/') => '/',
+            Some(b'b') => '\u{08}',
+            Some(b'f') => '\u{0C}',
+            Some(b'n') => '\n',
+            Some(b'r') => '\r',
+            Some(b't') => '\t',
+            Some(b'u') => {
+                self.pos += 1;
+                return self.parse_unicode_escape();
+            }
+            _ => return Err(self.error("invalid escape sequence")),
+        };
+
+        self.pos += 1;
+        Ok(escaped)
+    }
+
+    /// Analyse un caractère \uXXXX, éventuellement suivi de la seconde moitié d'une paire de substitution.
+    fn parse_unicode_escape(&mut self) -> KnackResult<char> {
+        let start = self.pos;
+        let high = self.parse_hex4()?;
+
+        let code = if (0xD800..0xDC00).contains(&high) {
+            if !self.src[self.pos..].starts_with("\\u") {
+                return Err(KnackError::json(start, "unpaired surrogate"));
+            }
+
+            self.pos += 2;
+            let low = self.parse_hex4()?;
+
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(KnackError::json(start, "unpaired surrogate"));
+            }
+
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+
+        char::from_u32(code).ok_or_else(|| KnackError::json(start, "invalid unicode character"))
+    }
+
+    fn parse_hex4(&mut self) -> KnackResult<u32> {
+        let digits = self
+            .src
+            .get(self.pos..self.pos + 4)
+            .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("expecting 4 hexadecimal digits"))?;
+
+        self.pos += 4;
+        Ok(u32::from_str_radix(digits, 16).unwrap())
+    }
+
+    fn skip_digits(&mut self) -> KnackResult<()> {
+        if !self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            return Err(self.error("expecting a digit"));
+        }
+
+        while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+
+        Ok(())
+    }
+
+    fn parse_number(&mut self) -> KnackResult<KnackBuilder> {
+        let start = self.pos;
+        let mut integer = true;
+
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+
+        if self.peek() == Some(b'0') {
+            self.pos += 1;
+        } else {
+            self.skip_digits()?;
+        }
+
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            self.skip_digits()?;
+            integer = false;
+        }
+
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            self.pos += 1;
+
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+
+            self.skip_digits()?;
+            integer = false;
+        }
+
+        let literal = &self.src[start..self.pos];
+
+        if integer {
+            if let Ok(value) = literal.parse::<i64>() {
+                return Ok(value.into_knack_builder());
+            }
+
+            if let Ok(value) = literal.parse::<u64>() {
+                return Ok(value.into_knack_builder());
+            }
+        }
+
+        literal
+            .parse::<f64>()
+            .map(IntoKnackBuilder::into_knack_builder)
+            .map_err(|_| KnackError::json(start, "invalid number"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::knack::{
+        buf::IntoKnackBuf,
+        builder::KnackBuilder,
+        document::DocBuilder,
+        error::KnackErrorKind,
+        timestamp::Timestamp,
+    };
+
+    fn round_trip(json: &str) -> String {
+        let mut out = Vec::default();
+        KnackBuilder::from_json(json)
+            .unwrap()
+            .into_knack_buf()
+            .to_json(&mut out)
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        assert_eq!(round_trip("-18"), "-18");
+        assert_eq!(round_trip("18446744073709551615"), "18446744073709551615");
+        assert_eq!(round_trip("1e3"), "1000.0");
+        assert_eq!(round_trip("true"), "true");
+        assert_eq!(round_trip("null"), "null");
+        assert_eq!(round_trip(r#""tab\t \"quoted\" é😀""#), "\"tab\\t \\\"quoted\\\" é😀\"");
+        assert_eq!(
+            round_trip(r#" { "city" : { "mayor": null } } "#),
+            r#"{"city":{"mayor":null}}"#
+        );
+        assert_eq!(round_trip("[1, 2, 3]"), "[1,2,3]");
+        assert_eq!(
+            round_trip(r#"{"orders": [{"sku": "JRN-18"}, [], "a", null]}"#),
+            r#"{"orders":[{"sku":"JRN-18"},[],"a",null]}"#
+        );
+    }
+
+    #[test]
+    fn test_json_export() {
+        let mut doc = DocBuilder::default();
+        doc.insert("at", Timestamp::from_micros(1_709_296_200_000_000));
+        let mut out = Vec::default();
+        doc.into_knack_buf().to_json(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), r#"{"at":"2024-03-01T12:30:00.000000Z"}"#);
+
+        assert!(b"raw".as_slice().into_knack_buf().to_json(&mut Vec::default()).is_err());
+    }
+
+    #[test]
+    fn test_json_import() {
+        let value = KnackBuilder::from_json(r#"{"price": 2.5, "tags": ["a", 2]}"#).unwrap();
+        assert!(value.is::<DocBuilder>());
+        assert!(value.get("price").unwrap().cast::<f64>() == &2.5);
+
+        match value.get("tags") {
+            Some(KnackBuilder::Array(tags)) => assert_eq!(tags.len(), 2),
+            _ => panic!("expecting an array"),
+        }
+    }
+
+    #[test]
+    fn test_json_errors() {
+        let position = |json: &str| match KnackBuilder::from_json(json).err().unwrap().kind() {
+            KnackErrorKind::Json { position, .. } => *position,
+            _ => panic!("expecting a json error"),
+        };
+
+        assert_eq!(position(r#"{"a": 1,}"#), 8);
+        assert_eq!(position(r#"{"a" 1}"#), 5);
+        assert_eq!(position("[1, nul]"), 4);
+        assert_eq!(position("01"), 1);
+        assert_eq!(position(r#""\ud83d""#), 3);
+        assert_eq!(position("[1] 2"), 4);
+    }
+}
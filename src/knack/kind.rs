@@ -6,6 +6,9 @@ use std::{any::Any, borrow::Borrow, convert::Infallible, fmt::{Debug, Display},
 
 use super::{
     document::{DocBuilder, KeyValue},
+    fixed_str::FixedStr,
+    timestamp::Timestamp,
+    uuid::Uuid,
     error::{KnackError, KnackErrorKind},
     marker::{
         kernel::AsKernelRef, sized::{Sized, VarSized}, Comparable, ComparableAndFixedSized, FixedSized
@@ -28,10 +31,14 @@ pub(super) const I128_TYPE_ID: KnackTypeId = 10;
 pub(super) const F32_TYPE_ID: KnackTypeId = 11;
 pub(super) const F64_TYPE_ID: KnackTypeId = 12;
 pub(super) const STR_TYPE_ID: KnackTypeId = 13;
-#[allow(dead_code)]
 pub(super) const FIXED_STR_TYPE_ID: KnackTypeId = 14;
 pub(super) const DOCUMENT_TYPE_ID: KnackTypeId = 15;
 pub(super) const KV_PAIR_TYPE_ID: KnackTypeId = 16;
+pub(super) const BOOL_TYPE_ID: KnackTypeId = 17;
+pub(super) const NULL_TYPE_ID: KnackTypeId = 18;
+pub(super) const BYTES_TYPE_ID: KnackTypeId = 19;
+pub(super) const TIMESTAMP_TYPE_ID: KnackTypeId = 20;
+pub(super) const UUID_TYPE_ID: KnackTypeId = 21;
 pub(super) const ARRAY_FLAG: KnackTypeId = 128;
 
 pub trait GetKnackKind {
@@ -82,6 +89,11 @@ impl KnackKindDescriptor {
         self.flags & Self::FLAG_DYN_SIZED > 0
     }
 
+    /// Le type est de taille fixe, qu'elle soit connue du descripteur ou encodée dans le type.
+    pub fn is_sized(&self) -> bool {
+        self.flags & (Self::FLAG_SIZED | Self::FLAG_DYN_SIZED) > 0
+    }
+
 }
 
 pub(super) static KNACK_KIND_DESCRIPTORS: phf::Map<KnackTypeId, KnackKindDescriptor> = phf_map! {
@@ -99,11 +111,18 @@ pub(super) static KNACK_KIND_DESCRIPTORS: phf::Map<KnackTypeId, KnackKindDescrip
     11u8 => KnackKindDescriptor::new("f32").comparable().fixed_sized(4),
     12u8 => KnackKindDescriptor::new("f64").comparable().fixed_sized(8),
     13u8 => KnackKindDescriptor::new("str"),
-    14u8 => KnackKindDescriptor::new("fixed-str").comparable().dyn_fixed_sized()
+    14u8 => KnackKindDescriptor::new("fixed-str").comparable().dyn_fixed_sized(),
+    15u8 => KnackKindDescriptor::new("document"),
+    16u8 => KnackKindDescriptor::new("kv-pair"),
+    17u8 => KnackKindDescriptor::new("bool").comparable().fixed_sized(1),
+    18u8 => KnackKindDescriptor::new("null").fixed_sized(0),
+    19u8 => KnackKindDescriptor::new("bytes"),
+    20u8 => KnackKindDescriptor::new("timestamp").comparable().fixed_sized(8),
+    21u8 => KnackKindDescriptor::new("uuid").comparable().fixed_sized(16)
 };
 
 
-#[derive(FromBytes, IntoBytes, KnownLayout, Immutable)]
+#[derive(FromBytes, IntoBytes, KnownLayout, Immutable, Clone)]
 /// Type de knack englobant (taille maximale que peut avoir un type de knack)
 pub struct EmcompassingKnackKind([u8;5]);
 
@@ -211,11 +230,21 @@ impl Deref for ComparableAndFixedSized<KnackKind> {
 
 impl Display for KnackKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let type_name = KNACK_KIND_DESCRIPTORS
-            .get(&self.0[0])
-            .map(|desc| desc.type_name)
-            .unwrap_or("unknown");
-        write!(f, "{0}", type_name)
+        if self.is_array() {
+            write!(f, "array<")?;
+        }
+
+        match KNACK_KIND_DESCRIPTORS.get(&(self.0[0] & !ARRAY_FLAG)) {
+            Some(desc) if desc.is_dyn_fixed_sized() => write!(f, "{0}({1})", desc.type_name, self.0[1])?,
+            Some(desc) => write!(f, "{0}", desc.type_name)?,
+            None => write!(f, "unknown")?,
+        }
+
+        if self.is_array() {
+            write!(f, ">")?;
+        }
+
+        Ok(())
     }
 }
 
@@ -223,7 +252,8 @@ impl TryFrom<&[u8]> for &KnackKind {
     type Error = Infallible;
 
     fn try_from(value: &[u8]) -> std::result::Result<Self, Self::Error> {
-        let type_id = value[0];
+        // Une liste porte le type de ses éléments.
+        let type_id = value[0] & !ARRAY_FLAG;
         let desc = KNACK_KIND_DESCRIPTORS.get(&type_id).unwrap();
 
         if desc.is_dyn_fixed_sized() {
@@ -317,7 +347,7 @@ impl KnackKind {
     fn is_sized(&self) -> bool {
         KNACK_KIND_DESCRIPTORS
             .get(self.type_id())
-            .map(|desc| desc.flags & KnackKindDescriptor::FLAG_SIZED > 0)
+            .map(KnackKindDescriptor::is_sized)
             .unwrap_or_default()
     }
 
@@ -358,13 +388,14 @@ impl FixedSized<KnackKind> {
     }
 
     pub fn inner_size(&self) -> usize {
-        return usize::from(
-            KNACK_KIND_DESCRIPTORS
-                .get(self.0.type_id())
-                .unwrap()
-                .size
-                .unwrap(),
-        );
+        let desc = KNACK_KIND_DESCRIPTORS.get(self.0.type_id()).unwrap();
+
+        // La taille d'un type dynamique est encodée dans le second octet du type.
+        if desc.is_dyn_fixed_sized() {
+            return usize::from(self.0.as_bytes()[1]);
+        }
+
+        usize::from(desc.size.unwrap())
     }
 
     pub fn range(&self) -> Range<usize> {
@@ -510,6 +541,72 @@ impl GetKnackKind for f64 {
     }
 }
 
+impl GetKnackKind for bool {
+    type Kind = Comparable<FixedSized<KnackKind>>;
+
+    fn kind() -> &'static Self::Kind {
+        unsafe {
+            let raw: &'static [u8] = &[BOOL_TYPE_ID];
+            std::mem::transmute(raw)
+        }
+    }
+}
+
+impl GetKnackKind for () {
+    type Kind = FixedSized<KnackKind>;
+
+    fn kind() -> &'static Self::Kind {
+        unsafe {
+            let raw: &'static [u8] = &[NULL_TYPE_ID];
+            std::mem::transmute(raw)
+        }
+    }
+}
+
+impl GetKnackKind for [u8] {
+    type Kind = VarSized<KnackKind>;
+
+    fn kind() -> &'static Self::Kind {
+        unsafe {
+            let raw: &'static [u8] = &[BYTES_TYPE_ID];
+            std::mem::transmute(raw)
+        }
+    }
+}
+
+impl GetKnackKind for Timestamp {
+    type Kind = Comparable<FixedSized<KnackKind>>;
+
+    fn kind() -> &'static Self::Kind {
+        unsafe {
+            let raw: &'static [u8] = &[TIMESTAMP_TYPE_ID];
+            std::mem::transmute(raw)
+        }
+    }
+}
+
+impl GetKnackKind for Uuid {
+    type Kind = Comparable<FixedSized<KnackKind>>;
+
+    fn kind() -> &'static Self::Kind {
+        unsafe {
+            let raw: &'static [u8] = &[UUID_TYPE_ID];
+            std::mem::transmute(raw)
+        }
+    }
+}
+
+impl<const N: usize> GetKnackKind for FixedStr<N> {
+    type Kind = Comparable<FixedSized<KnackKind>>;
+
+    fn kind() -> &'static Self::Kind {
+        unsafe {
+            let raw: &'static [u8] = const { &[FIXED_STR_TYPE_ID, FixedStr::<N>::CAPACITY] };
+            std::mem::transmute(raw)
+        }
+    }
+}
+
 impl GetKnackKind for str {
     type Kind = VarSized<KnackKind>;
 
@@ -8,26 +8,38 @@
 pub mod array;
 pub mod buf;
 pub mod builder;
+pub mod de;
 pub mod document;
 pub mod error;
+pub mod fixed_str;
+pub mod json;
 pub mod kind;
 pub mod marker;
 pub mod ord;
+pub mod patch;
 pub mod path;
 pub mod prelude;
 pub mod result;
+pub mod ser;
+pub mod timestamp;
+pub mod update;
+pub mod uuid;
 
 use std::{convert::Infallible, ops::{Deref, DerefMut, Range}};
 
-use array::{Array, ArrayBuilder};
+use array::Array;
 use buf::KnackBuf;
 use builder::KnackBuilder;
-use document::DocBuilder;
+use document::{Document, KeyValue};
 use error::KnackError;
+use fixed_str::FixedStr;
 use kind::{GetKnackKind, KnackKind};
+pub use patch::{diff, merge_patch};
 use marker::{kernel::AsKernelRef, Comparable, ComparableAndFixedSized, FixedSized};
-use path::IntoKnackPath;
+use path::{IntoKnackPath, PathSegment};
 use result::KnackResult;
+use timestamp::Timestamp;
+use uuid::Uuid;
 use zerocopy::{FromBytes, LittleEndian};
 
 use crate::page::{AsRefPageSlice, PageSlice};
@@ -175,22 +187,61 @@ impl Knack {
         <&KnackKind>::try_from(&self.0).unwrap()
     }
 
+    /// Recherche une valeur de manière récursive (cf [path::KnackPath]).
+    ///
+    /// Si le chemin contient des jokers, retourne la première valeur trouvée.
+    /// Un chemin invalide ne désigne aucune valeur (cf [Self::get_checked]).
     pub fn get<Path: IntoKnackPath>(&self, path: Path) -> Option<&Knack> {
-        let mut pth = path.into_value_path();
-
-        match pth.pop() {
-            None => Some(self),
-            Some(attr_name) => {
-                if self.is::<DocBuilder>() {
-                    self.cast::<DocBuilder>()
-                        .get_field(&attr_name)
-                        .and_then(|v| v.get(pth))
-                } else if self.is::<Array>() {
-                    let index = attr_name.parse::<usize>().ok()?;
-                    self.cast::<Array>().get(&index).and_then(|v| v.get(pth))
-                } else {
-                    None
-                }
+        self.get_checked(path).ok().flatten()
+    }
+
+    /// Retourne toutes les valeurs désignées par le chemin, dans l'ordre de parcours.
+    ///
+    /// Un chemin invalide ne désigne aucune valeur (cf [Self::get_all_checked]).
+    pub fn get_all<Path: IntoKnackPath>(&self, path: Path) -> Vec<&Knack> {
+        self.get_all_checked(path).unwrap_or_default()
+    }
+
+    /// Recherche une valeur, retourne une erreur si le chemin est invalide (cf [Self::get]).
+    pub fn get_checked<Path: IntoKnackPath>(&self, path: Path) -> KnackResult<Option<&Knack>> {
+        let mut matches = Vec::with_capacity(1);
+        self.collect_matches(path.try_into_value_path()?.segments(), &mut matches, true);
+        Ok(matches.pop())
+    }
+
+    /// Retourne toutes les valeurs désignées par le chemin, ou une erreur s'il est invalide (cf [Self::get_all]).
+    pub fn get_all_checked<Path: IntoKnackPath>(&self, path: Path) -> KnackResult<Vec<&Knack>> {
+        let mut matches = Vec::default();
+        self.collect_matches(path.try_into_value_path()?.segments(), &mut matches, false);
+        Ok(matches)
+    }
+
+    fn collect_matches<'a>(&'a self, segments: &[PathSegment], matches: &mut Vec<&'a Knack>, first_only: bool) {
+        let Some((segment, tail)) = segments.split_first() else {
+            matches.push(self);
+            return;
+        };
+
+        let children: Box<dyn Iterator<Item = &Knack>> = if let Ok(doc) = <&Document>::try_from(self) {
+            match segment {
+                PathSegment::Field(name) => Box::new(doc.get_field(name).into_iter()),
+                PathSegment::Wildcard => Box::new(doc.iter().map(KeyValue::value)),
+                _ => return,
+            }
+        } else if let Ok(array) = <&Array>::try_from(self) {
+            match segment {
+                PathSegment::AnyIndex | PathSegment::Wildcard => Box::new(array.iter()),
+                segment => Box::new(segment.as_index().and_then(|index| array.get(&index)).into_iter()),
+            }
+        } else {
+            return;
+        };
+
+        for child in children {
+            child.collect_matches(tail, matches, first_only);
+
+            if first_only && !matches.is_empty() {
+                return;
             }
         }
     }
@@ -1012,6 +1063,310 @@ impl TryFrom<&mut Knack> for &mut Str {
     }
 }
 
+pub struct Bool([u8]);
+impl Bool {
+    pub fn to_owned(&self) -> bool {
+        self.0[1] != 0
+    }
+
+    pub fn set(&mut self, value: bool) {
+        self.0[1] = u8::from(value)
+    }
+}
+impl PartialEq<Self> for Bool {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_owned() == other.to_owned()
+    }
+}
+impl PartialOrd<Self> for Bool {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.to_owned().partial_cmp(&other.to_owned())
+    }
+}
+impl PartialEq<bool> for Bool {
+    fn eq(&self, other: &bool) -> bool {
+        self.to_owned() == *other
+    }
+}
+impl TryFrom<&Knack> for &Bool {
+    type Error = KnackError;
+
+    fn try_from(value: &Knack) -> std::result::Result<Self, Self::Error> {
+        bool::kind()
+            .as_kernel_ref()
+            .assert_same(value.kind().as_kernel_ref())?;
+
+        unsafe { Ok(std::mem::transmute::<&Knack, &Bool>(value)) }
+    }
+}
+impl TryFrom<&mut Knack> for &mut Bool {
+    type Error = KnackError;
+
+    fn try_from(value: &mut Knack) -> std::result::Result<Self, Self::Error> {
+        bool::kind()
+            .as_kernel_ref()
+            .assert_same(value.kind().as_kernel_ref())?;
+
+        unsafe { Ok(std::mem::transmute::<&mut Knack, &mut Bool>(value)) }
+    }
+}
+
+#[allow(dead_code)]
+pub struct Null([u8]);
+impl TryFrom<&Knack> for &Null {
+    type Error = KnackError;
+
+    fn try_from(value: &Knack) -> std::result::Result<Self, Self::Error> {
+        <()>::kind()
+            .as_kernel_ref()
+            .assert_same(value.kind().as_kernel_ref())?;
+
+        unsafe { Ok(std::mem::transmute::<&Knack, &Null>(value)) }
+    }
+}
+impl TryFrom<&mut Knack> for &mut Null {
+    type Error = KnackError;
+
+    fn try_from(value: &mut Knack) -> std::result::Result<Self, Self::Error> {
+        <()>::kind()
+            .as_kernel_ref()
+            .assert_same(value.kind().as_kernel_ref())?;
+
+        unsafe { Ok(std::mem::transmute::<&mut Knack, &mut Null>(value)) }
+    }
+}
+
+pub struct Bytes([u8]);
+impl PartialEq<[u8]> for Bytes {
+    fn eq(&self, other: &[u8]) -> bool {
+        self.deref().eq(other)
+    }
+}
+impl PartialEq<Self> for Bytes {
+    fn eq(&self, other: &Self) -> bool {
+        self.deref().eq(other.deref())
+    }
+}
+impl Deref for Bytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0[1..]
+    }
+}
+impl DerefMut for Bytes {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0[1..]
+    }
+}
+impl TryFrom<&Knack> for &Bytes {
+    type Error = KnackError;
+
+    fn try_from(value: &Knack) -> std::result::Result<Self, Self::Error> {
+        <[u8]>::kind().as_kernel_ref().assert_same(value.kind().as_kernel_ref())?;
+        unsafe { Ok(std::mem::transmute::<&Knack, &Bytes>(value)) }
+    }
+}
+impl TryFrom<&mut Knack> for &mut Bytes {
+    type Error = KnackError;
+
+    fn try_from(value: &mut Knack) -> std::result::Result<Self, Self::Error> {
+        <[u8]>::kind().as_kernel_ref().assert_same(value.kind().as_kernel_ref())?;
+        unsafe { Ok(std::mem::transmute::<&mut Knack, &mut Bytes>(value)) }
+    }
+}
+
+pub struct TimestampRef([u8]);
+impl TimestampRef {
+    pub fn to_owned(&self) -> Timestamp {
+        Timestamp::from_micros(self.deref().get())
+    }
+
+    pub fn set(&mut self, value: Timestamp) {
+        self.deref_mut().set(value.as_micros())
+    }
+}
+impl PartialEq<Self> for TimestampRef {
+    fn eq(&self, other: &Self) -> bool {
+        self.deref() == other.deref()
+    }
+}
+impl PartialOrd<Self> for TimestampRef {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.deref().partial_cmp(other.deref())
+    }
+}
+impl PartialEq<Timestamp> for TimestampRef {
+    fn eq(&self, other: &Timestamp) -> bool {
+        self.to_owned().eq(other)
+    }
+}
+impl Deref for TimestampRef {
+    type Target = zerocopy::I64<LittleEndian>;
+
+    fn deref(&self) -> &Self::Target {
+        zerocopy::I64::ref_from_bytes(&self.0[1..]).unwrap()
+    }
+}
+impl DerefMut for TimestampRef {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        zerocopy::I64::mut_from_bytes(&mut self.0[1..]).unwrap()
+    }
+}
+impl TryFrom<&Knack> for &TimestampRef {
+    type Error = KnackError;
+
+    fn try_from(value: &Knack) -> std::result::Result<Self, Self::Error> {
+        Timestamp::kind()
+            .as_kernel_ref()
+            .assert_same(value.kind().as_kernel_ref())?;
+
+        unsafe { Ok(std::mem::transmute::<&Knack, &TimestampRef>(value)) }
+    }
+}
+impl TryFrom<&mut Knack> for &mut TimestampRef {
+    type Error = KnackError;
+
+    fn try_from(value: &mut Knack) -> std::result::Result<Self, Self::Error> {
+        Timestamp::kind()
+            .as_kernel_ref()
+            .assert_same(value.kind().as_kernel_ref())?;
+
+        unsafe { Ok(std::mem::transmute::<&mut Knack, &mut TimestampRef>(value)) }
+    }
+}
+
+pub struct UuidRef([u8]);
+impl UuidRef {
+    pub fn to_owned(&self) -> Uuid {
+        Uuid::from_bytes(self.0[1..].try_into().unwrap())
+    }
+
+    pub fn set(&mut self, value: Uuid) {
+        self.0[1..].copy_from_slice(value.as_bytes())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0[1..]
+    }
+}
+impl PartialEq<Self> for UuidRef {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+impl PartialOrd<Self> for UuidRef {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.as_bytes().partial_cmp(other.as_bytes())
+    }
+}
+impl PartialEq<Uuid> for UuidRef {
+    fn eq(&self, other: &Uuid) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+impl TryFrom<&Knack> for &UuidRef {
+    type Error = KnackError;
+
+    fn try_from(value: &Knack) -> std::result::Result<Self, Self::Error> {
+        Uuid::kind()
+            .as_kernel_ref()
+            .assert_same(value.kind().as_kernel_ref())?;
+
+        unsafe { Ok(std::mem::transmute::<&Knack, &UuidRef>(value)) }
+    }
+}
+impl TryFrom<&mut Knack> for &mut UuidRef {
+    type Error = KnackError;
+
+    fn try_from(value: &mut Knack) -> std::result::Result<Self, Self::Error> {
+        Uuid::kind()
+            .as_kernel_ref()
+            .assert_same(value.kind().as_kernel_ref())?;
+
+        unsafe { Ok(std::mem::transmute::<&mut Knack, &mut UuidRef>(value)) }
+    }
+}
+
+/// Vue sur une chaîne de taille fixe, quelle que soit sa capacité (cf [FixedStr]).
+pub struct FixedStrRef([u8]);
+impl FixedStrRef {
+    pub fn as_str(&self) -> &str {
+        // La chaîne a été validée à la lecture du knack.
+        unsafe { fixed_str::unpad_unchecked(self.as_padded_bytes()) }
+    }
+
+    /// Octets de la chaîne, complétés par des octets nuls.
+    pub fn as_padded_bytes(&self) -> &[u8] {
+        &self.0[2..]
+    }
+
+    pub fn capacity(&self) -> usize {
+        usize::from(self.0[1])
+    }
+
+    pub fn to_owned<const N: usize>(&self) -> KnackResult<FixedStr<N>> {
+        FixedStr::new(self.as_str())
+    }
+
+    /// Remplace la chaîne, la capacité doit être identique.
+    pub fn set<const N: usize>(&mut self, value: &FixedStr<N>) {
+        assert_eq!(self.capacity(), N, "fixed-str capacity mismatch");
+        self.0[2..].copy_from_slice(value.as_padded_bytes())
+    }
+}
+impl PartialEq<Self> for FixedStrRef {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_padded_bytes() == other.as_padded_bytes()
+    }
+}
+impl PartialOrd<Self> for FixedStrRef {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.as_padded_bytes().partial_cmp(other.as_padded_bytes())
+    }
+}
+impl PartialEq<str> for FixedStrRef {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+impl<const N: usize> PartialEq<FixedStr<N>> for FixedStrRef {
+    fn eq(&self, other: &FixedStr<N>) -> bool {
+        self.as_padded_bytes() == other.as_padded_bytes()
+    }
+}
+impl Deref for FixedStrRef {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+impl TryFrom<&Knack> for &FixedStrRef {
+    type Error = KnackError;
+
+    fn try_from(value: &Knack) -> std::result::Result<Self, Self::Error> {
+        if *value.kind().type_id() != kind::FIXED_STR_TYPE_ID {
+            return Err(KnackError::new(error::KnackErrorKind::WrongKind {
+                expected: FixedStr::<1>::kind().as_kernel_ref().to_owned(),
+                got: value.kind().to_owned(),
+            }));
+        }
+
+        let fixed = unsafe { std::mem::transmute::<&Knack, &FixedStrRef>(value) };
+        fixed_str::unpad(fixed.as_padded_bytes())?;
+        Ok(fixed)
+    }
+}
+impl TryFrom<&mut Knack> for &mut FixedStrRef {
+    type Error = KnackError;
+
+    fn try_from(value: &mut Knack) -> std::result::Result<Self, Self::Error> {
+        <&FixedStrRef>::try_from(&*value)?;
+        unsafe { Ok(std::mem::transmute::<&mut Knack, &mut FixedStrRef>(value)) }
+    }
+}
+
 impl FromKnack for u8 {
     type Output = U8;
 
@@ -1155,10 +1510,82 @@ impl FromKnack for str {
         value.try_into()
     }
 }
+impl FromKnack for bool {
+    type Output = Bool;
+
+    fn try_ref_from_knack(value: &Knack) -> KnackResult<&Self::Output> {
+        value.try_into()
+    }
+
+    fn try_mut_from_knack(value: &mut Knack) -> KnackResult<&mut Self::Output> {
+        value.try_into()
+    }
+}
+impl FromKnack for () {
+    type Output = Null;
+
+    fn try_ref_from_knack(value: &Knack) -> KnackResult<&Self::Output> {
+        value.try_into()
+    }
+
+    fn try_mut_from_knack(value: &mut Knack) -> KnackResult<&mut Self::Output> {
+        value.try_into()
+    }
+}
+impl FromKnack for [u8] {
+    type Output = Bytes;
+
+    fn try_ref_from_knack(value: &Knack) -> KnackResult<&Self::Output> {
+        value.try_into()
+    }
+
+    fn try_mut_from_knack(value: &mut Knack) -> KnackResult<&mut Self::Output> {
+        value.try_into()
+    }
+}
+impl FromKnack for Timestamp {
+    type Output = TimestampRef;
+
+    fn try_ref_from_knack(value: &Knack) -> KnackResult<&Self::Output> {
+        value.try_into()
+    }
+
+    fn try_mut_from_knack(value: &mut Knack) -> KnackResult<&mut Self::Output> {
+        value.try_into()
+    }
+}
+impl<const N: usize> FromKnack for FixedStr<N> {
+    type Output = FixedStrRef;
+
+    fn try_ref_from_knack(value: &Knack) -> KnackResult<&Self::Output> {
+        Self::kind().as_kernel_ref().assert_same(value.kind())?;
+        value.try_into()
+    }
+
+    fn try_mut_from_knack(value: &mut Knack) -> KnackResult<&mut Self::Output> {
+        Self::kind().as_kernel_ref().assert_same(value.kind())?;
+        value.try_into()
+    }
+}
+impl FromKnack for Uuid {
+    type Output = UuidRef;
+
+    fn try_ref_from_knack(value: &Knack) -> KnackResult<&Self::Output> {
+        value.try_into()
+    }
+
+    fn try_mut_from_knack(value: &mut Knack) -> KnackResult<&mut Self::Output> {
+        value.try_into()
+    }
+}
 
 #[cfg(test)]
 mod tests {
-    use crate::prelude::IntoKnackBuf;
+    use std::ops::Deref;
+
+    use crate::{knack::marker::kernel::IntoKernel, prelude::IntoKnackBuf};
+
+    use super::{builder::KnackBuilder, fixed_str::FixedStr, timestamp::Timestamp, uuid::Uuid, FromKnack};
 
     #[test]
     fn test_is() {
@@ -1179,6 +1606,52 @@ mod tests {
         assert!(10_u8.into_knack_buf().cast::<u8>() == &10_u8)
     }
 
+    #[test]
+    fn test_extra_kinds() {
+        assert!(true.into_knack_buf().cast::<bool>() == &true);
+        assert!(().into_knack_buf().is::<()>());
+        assert!(b"jarnac".as_slice().into_knack_buf().cast::<[u8]>() == b"jarnac".as_slice());
+
+        let ts = Timestamp::from_micros(-18);
+        assert!(ts.into_knack_buf().cast::<Timestamp>() == &ts);
+
+        let uuid = Uuid::new_v4();
+        assert!(uuid.into_knack_buf().cast::<Uuid>() == &uuid);
+
+        // Les horodatages et les UUID sont comparables.
+        let earlier = Timestamp::from_micros(-1).into_knack_buf();
+        let later = Timestamp::from_micros(1).into_knack_buf();
+        assert!(earlier.try_as_comparable().unwrap() < later.deref());
+
+        let low = Uuid::from_bytes([0; 16]).into_knack_buf();
+        let high = Uuid::from_bytes([0xFF; 16]).into_knack_buf();
+        assert!(low.try_as_comparable().unwrap() < high.deref());
+    }
+
+    #[test]
+    fn test_fixed_str() {
+        let code = FixedStr::<3>::new("FR").unwrap().into_knack_buf();
+        assert_eq!(code.kind().as_bytes(), &[14, 3]);
+        assert_eq!(code.kind().to_string(), "fixed-str(3)");
+        assert_eq!(code.kind().try_as_fixed_sized().unwrap().outer_size(), 5);
+        assert!(code.cast::<FixedStr<3>>() == "FR");
+
+        // La capacité fait partie du type.
+        assert!(code.is::<FixedStr<3>>());
+        assert!(!code.is::<FixedStr<4>>());
+        assert!(FixedStr::<4>::try_ref_from_knack(&code).is_err());
+
+        // Comparaison octet par octet de la valeur complétée.
+        let longer = FixedStr::<3>::new("FRA").unwrap().into_knack_buf();
+        let next = FixedStr::<3>::new("GB").unwrap().into_knack_buf();
+        assert!(code.try_as_comparable().unwrap() < longer.deref());
+        assert!(longer.try_as_comparable().unwrap() < next.deref());
+
+        let mut code = code.into_kernel();
+        code.cast_mut::<FixedStr<3>>().set(&FixedStr::<3>::new("DE").unwrap());
+        assert_eq!(code.cast::<FixedStr<3>>().to_owned::<3>().unwrap().as_str(), "DE");
+    }
+
     #[test]
     fn test_sizes() {
         assert_eq!(
@@ -1232,5 +1705,37 @@ mod tests {
             "signed int128 must have a size of 16"
         );
     }
-}
 
+    #[test]
+    fn test_get_all() {
+        let json = r#"{
+            "items": [{"price": 2.5, "sku": "a"}, {"price": 4.0}, {"sku": "c"}],
+            "by.id": {"x": {"id": 1}, "y": {"id": 2}}
+        }"#;
+        let builder = KnackBuilder::from_json(json).unwrap();
+        let buf = KnackBuilder::from_json(json).unwrap().into_knack_buf();
+
+        let prices: Vec<f64> = buf.get_all("items[*].price").into_iter().map(|k| k.cast::<f64>().to_owned()).collect();
+        assert_eq!(prices, vec![2.5, 4.0]);
+        assert_eq!(builder.get_all("items[*].price").len(), 2);
+
+        let mut ids: Vec<i64> = buf.get_all(r#""by.id".*.id"#).into_iter().map(|k| k.cast::<i64>().to_owned()).collect();
+        ids.sort();
+        assert_eq!(ids, vec![1, 2]);
+        assert_eq!(builder.get_all(r#"by\.id.*.id"#).len(), 2);
+
+        assert_eq!(buf.get("items[2].sku").unwrap().cast::<str>().deref(), "c");
+        assert_eq!(buf.get("items.0.sku").unwrap().cast::<str>().deref(), "a");
+        assert!(buf.get("items[3]").is_none());
+        assert!(buf.get("items.sku").is_none());
+        assert_eq!(buf.get_all("items[*].sku").len(), 2);
+        assert_eq!(buf.get_all("*").len(), 2);
+        assert_eq!(buf.get_all("").len(), 1);
+
+        // Un chemin invalide ne désigne aucune valeur, sauf à en demander l'erreur.
+        assert!(buf.get("items[").is_none());
+        assert!(buf.get_checked("items[").is_err());
+        assert!(builder.get_all("items..sku").is_empty());
+        assert!(builder.get_all_checked("items..sku").is_err());
+    }
+}
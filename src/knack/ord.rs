@@ -3,12 +3,13 @@ use std::borrow::Borrow;
 
 use super::{
     buf::KnackBuf, kind::{
-        KnackKind, F32_TYPE_ID, F64_TYPE_ID, I128_TYPE_ID, I16_TYPE_ID, I32_TYPE_ID, I64_TYPE_ID,
-        I8_TYPE_ID, U128_TYPE_ID, U16_TYPE_ID, U32_TYPE_ID, U64_TYPE_ID, U8_TYPE_ID,
+        KnackKind, BOOL_TYPE_ID, F32_TYPE_ID, FIXED_STR_TYPE_ID, F64_TYPE_ID, I128_TYPE_ID, I16_TYPE_ID, I32_TYPE_ID,
+        I64_TYPE_ID, I8_TYPE_ID, TIMESTAMP_TYPE_ID, U128_TYPE_ID, U16_TYPE_ID, U32_TYPE_ID,
+        U64_TYPE_ID, U8_TYPE_ID, UUID_TYPE_ID,
     }, marker::{
         kernel::AsKernelRef,
         Comparable,
-    }, Knack
+    }, timestamp::Timestamp, uuid::Uuid, FixedStrRef, Knack
 };
 
 impl Deref for Comparable<Knack> {
@@ -69,6 +70,12 @@ impl PartialOrd<Knack> for Comparable<Knack> {
             I128_TYPE_ID => self.cast::<i128>().partial_cmp(other.cast::<i128>()),
             F32_TYPE_ID => self.cast::<f32>().partial_cmp(other.cast::<f32>()),
             F64_TYPE_ID => self.cast::<f64>().partial_cmp(other.cast::<f64>()),
+            BOOL_TYPE_ID => self.cast::<bool>().partial_cmp(other.cast::<bool>()),
+            TIMESTAMP_TYPE_ID => self.cast::<Timestamp>().partial_cmp(other.cast::<Timestamp>()),
+            UUID_TYPE_ID => self.cast::<Uuid>().partial_cmp(other.cast::<Uuid>()),
+            FIXED_STR_TYPE_ID => <&FixedStrRef>::try_from(self.deref())
+                .ok()?
+                .partial_cmp(<&FixedStrRef>::try_from(other.deref()).ok()?),
             _ => None,
         }
     }
@@ -0,0 +1,451 @@
+//! Différence entre deux versions d'un document.
+//!
+//! - [diff] produit un correctif au sens de la RFC 6902 (JSON Patch), composé d'ajouts,
+//!   de retraits et de remplacements ;
+//! - [Patch::apply] applique un correctif, qui peut aussi déplacer, copier ou tester des valeurs ;
+//! - [merge_patch] applique un correctif de fusion au sens de la RFC 7396 (JSON Merge Patch).
+//!
+//! Les chemins sont des [KnackPath] sans joker, exportés en JSON Pointer (RFC 6901).
+use std::{
+    io::{self, Write},
+    ops::Deref,
+};
+
+use super::{
+    array::Array,
+    buf::IntoKnackBuf,
+    builder::{IntoKnackBuilder, KnackBuilder},
+    document::{DocBuilder, Document},
+    error::{KnackError, KnackErrorKind},
+    json::write_str,
+    path::{KnackPath, PathSegment},
+    result::KnackResult,
+    Knack,
+};
+
+/// Opération d'un correctif.
+#[derive(Clone)]
+pub enum PatchOp {
+    /// Ajoute un champ, ou insère un élément dans une liste.
+    Add { path: KnackPath, value: KnackBuilder },
+    Remove { path: KnackPath },
+    Replace { path: KnackPath, value: KnackBuilder },
+    Move { from: KnackPath, path: KnackPath },
+    Copy { from: KnackPath, path: KnackPath },
+    /// Vérifie que la valeur est identique, le correctif échoue sinon.
+    Test { path: KnackPath, value: KnackBuilder },
+}
+
+impl PatchOp {
+    fn name(&self) -> &'static str {
+        match self {
+            PatchOp::Add { .. } => "add",
+            PatchOp::Remove { .. } => "remove",
+            PatchOp::Replace { .. } => "replace",
+            PatchOp::Move { .. } => "move",
+            PatchOp::Copy { .. } => "copy",
+            PatchOp::Test { .. } => "test",
+        }
+    }
+
+    fn path(&self) -> &KnackPath {
+        match self {
+            PatchOp::Add { path, .. }
+            | PatchOp::Remove { path }
+            | PatchOp::Replace { path, .. }
+            | PatchOp::Move { path, .. }
+            | PatchOp::Copy { path, .. }
+            | PatchOp::Test { path, .. } => path,
+        }
+    }
+}
+
+/// Correctif, liste d'opérations appliquées dans l'ordre.
+#[derive(Clone, Default)]
+pub struct Patch(Vec<PatchOp>);
+
+impl Patch {
+    pub fn ops(&self) -> &[PatchOp] {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn push(&mut self, op: PatchOp) {
+        self.0.push(op)
+    }
+
+    /// Applique le correctif.
+    ///
+    /// L'application est atomique : en cas d'erreur, la valeur n'est pas modifiée.
+    pub fn apply(&self, target: &mut KnackBuilder) -> KnackResult<()> {
+        let mut patched = target.clone();
+
+        for op in self.0.iter() {
+            match op {
+                PatchOp::Add { path, value } => add(&mut patched, path, value.clone())?,
+                PatchOp::Remove { path } => {
+                    remove(&mut patched, path)?;
+                }
+                PatchOp::Replace { path, value } => *lookup(&mut patched, path)? = value.clone(),
+                PatchOp::Move { from, path } => {
+                    let value = remove(&mut patched, from)?;
+                    add(&mut patched, path, value)?;
+                }
+                PatchOp::Copy { from, path } => {
+                    let value = lookup(&mut patched, from)?.clone();
+                    add(&mut patched, path, value)?;
+                }
+                PatchOp::Test { path, value } => {
+                    if !same(lookup(&mut patched, path)?, value) {
+                        return Err(KnackError::patch(path, "test failed"));
+                    }
+                }
+            }
+        }
+
+        *target = patched;
+        Ok(())
+    }
+
+    /// Ecrit le correctif au format JSON Patch.
+    pub fn to_json<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        out.write_all(b"[")?;
+
+        for (i, op) in self.0.iter().enumerate() {
+            if i > 0 {
+                out.write_all(b",")?;
+            }
+
+            write!(out, "{{\"op\":\"{0}\",", op.name())?;
+
+            if let PatchOp::Move { from, .. } | PatchOp::Copy { from, .. } = op {
+                out.write_all(b"\"from\":")?;
+                write_str(out, &json_pointer(from))?;
+                out.write_all(b",")?;
+            }
+
+            out.write_all(b"\"path\":")?;
+            write_str(out, &json_pointer(op.path()))?;
+
+            if let PatchOp::Add { value, .. } | PatchOp::Replace { value, .. } | PatchOp::Test { value, .. } = op {
+                out.write_all(b",\"value\":")?;
+                value.clone().into_knack_buf().to_json(out)?;
+            }
+
+            out.write_all(b"}")?;
+        }
+
+        out.write_all(b"]")
+    }
+}
+
+impl KnackError {
+    fn patch(path: &KnackPath, message: &'static str) -> Self {
+        Self::new(KnackErrorKind::Patch { path: path.clone(), message })
+    }
+}
+
+/// Calcule le correctif transformant une valeur en une autre.
+///
+/// Les documents et les listes sont comparés champ par champ et élément par élément,
+/// toute autre différence produit un remplacement.
+pub fn diff(from: &Knack, to: &Knack) -> Patch {
+    let mut patch = Patch::default();
+    diff_into(from, to, &KnackPath::default(), &mut patch);
+    patch
+}
+
+fn diff_into(from: &Knack, to: &Knack, at: &KnackPath, patch: &mut Patch) {
+    if from.as_ref() == to.as_ref() {
+        return;
+    }
+
+    if let (Ok(from), Ok(to)) = (<&Document>::try_from(from), <&Document>::try_from(to)) {
+        for kv in from.iter() {
+            let name = kv.key().cast::<str>().deref();
+
+            match to.get_field(name) {
+                Some(value) => diff_into(kv.value(), value, &at.field(name), patch),
+                None => patch.push(PatchOp::Remove { path: at.field(name) }),
+            }
+        }
+
+        for kv in to.iter() {
+            let name = kv.key().cast::<str>().deref();
+
+            if from.get_field(name).is_none() {
+                patch.push(PatchOp::Add {
+                    path: at.field(name),
+                    value: KnackBuilder::from(kv.value()),
+                });
+            }
+        }
+
+        return;
+    }
+
+    if let (Ok(from), Ok(to)) = (<&Array>::try_from(from), <&Array>::try_from(to)) {
+        let common = from.len().min(to.len());
+
+        for index in 0..common {
+            diff_into(from.get(&index).unwrap(), to.get(&index).unwrap(), &at.index(index), patch);
+        }
+
+        for index in common..to.len() {
+            patch.push(PatchOp::Add {
+                path: at.index(index),
+                value: KnackBuilder::from(to.get(&index).unwrap()),
+            });
+        }
+
+        // Les retraits se font depuis la fin, afin de ne pas décaler les indices suivants.
+        for index in (common..from.len()).rev() {
+            patch.push(PatchOp::Remove { path: at.index(index) });
+        }
+
+        return;
+    }
+
+    patch.push(PatchOp::Replace {
+        path: at.clone(),
+        value: KnackBuilder::from(to),
+    });
+}
+
+/// Applique un correctif de fusion (RFC 7396).
+///
+/// Les champs du correctif remplacent ceux de la valeur, récursivement pour les documents,
+/// un champ nul retire le champ correspondant.
+pub fn merge_patch(target: &mut KnackBuilder, patch: &Knack) {
+    let Ok(patch) = <&Document>::try_from(patch) else {
+        *target = KnackBuilder::from(patch);
+        return;
+    };
+
+    expand(target);
+
+    if !matches!(target, KnackBuilder::Document(_)) {
+        *target = KnackBuilder::Document(DocBuilder::default());
+    }
+
+    let KnackBuilder::Document(doc) = target else {
+        unreachable!()
+    };
+
+    for kv in patch.iter() {
+        let name = kv.key().cast::<str>().deref();
+
+        if kv.value().is::<()>() {
+            doc.remove(name);
+            continue;
+        }
+
+        match doc.get_field_mut(name) {
+            Some(field) => merge_patch(field, kv.value()),
+            None => {
+                let mut field = ().into_knack_builder();
+                merge_patch(&mut field, kv.value());
+                doc.insert(name, field);
+            }
+        }
+    }
+}
+
+/// Chemin au format JSON Pointer (RFC 6901).
+fn json_pointer(path: &KnackPath) -> String {
+    path.segments()
+        .iter()
+        .map(|segment| match segment {
+            PathSegment::Field(name) => format!("/{0}", name.replace('~', "~0").replace('/', "~1")),
+            PathSegment::Index(index) => format!("/{index}"),
+            PathSegment::AnyIndex | PathSegment::Wildcard => "/*".to_owned(),
+        })
+        .collect()
+}
+
+/// Les valeurs sont identiques, une fois stockées.
+fn same(a: &KnackBuilder, b: &KnackBuilder) -> bool {
+    a.clone().into_knack_buf().as_ref() == b.clone().into_knack_buf().as_ref()
+}
+
+/// Remplace un document ou une liste stocké par son constructeur, afin de pouvoir le modifier.
+fn expand(value: &mut KnackBuilder) {
+    let expanded = match value {
+        KnackBuilder::Other(buf) if buf.is::<DocBuilder>() || <&Array>::try_from(&**buf).is_ok() => {
+            KnackBuilder::from(&**buf)
+        }
+        _ => return,
+    };
+
+    *value = expanded;
+}
+
+fn lookup<'a>(root: &'a mut KnackBuilder, path: &KnackPath) -> KnackResult<&'a mut KnackBuilder> {
+    let mut current = root;
+
+    for (depth, segment) in path.segments().iter().enumerate() {
+        expand(current);
+
+        let child = match (segment, current) {
+            (PathSegment::Field(name), KnackBuilder::Document(doc)) => doc.get_field_mut(name),
+            (PathSegment::Field(_) | PathSegment::Index(_), KnackBuilder::Array(array)) => {
+                segment.as_index().and_then(|index| array.get_mut(index))
+            }
+            _ => None,
+        };
+
+        current = child.ok_or_else(|| {
+            let at: KnackPath = path.segments()[..=depth].iter().cloned().collect();
+            KnackError::patch(&at, "no such value")
+        })?;
+    }
+
+    expand(current);
+    Ok(current)
+}
+
+fn add(root: &mut KnackBuilder, path: &KnackPath, value: KnackBuilder) -> KnackResult<()> {
+    let Some((last, parent)) = path.segments().split_last() else {
+        *root = value;
+        return Ok(());
+    };
+
+    match (last, lookup(root, &parent.iter().cloned().collect())?) {
+        (PathSegment::Field(name), KnackBuilder::Document(doc)) => doc.insert(name, value),
+        (PathSegment::Field(_) | PathSegment::Index(_), KnackBuilder::Array(array)) => {
+            let index = last
+                .as_index()
+                .filter(|index| *index <= array.len())
+                .ok_or_else(|| KnackError::patch(path, "index out of bounds"))?;
+            array.insert(index, value);
+        }
+        _ => return Err(KnackError::patch(path, "no such document or array")),
+    }
+
+    Ok(())
+}
+
+fn remove(root: &mut KnackBuilder, path: &KnackPath) -> KnackResult<KnackBuilder> {
+    let Some((last, parent)) = path.segments().split_last() else {
+        return Err(KnackError::patch(path, "cannot remove the value itself"));
+    };
+
+    let removed = match (last, lookup(root, &parent.iter().cloned().collect())?) {
+        (PathSegment::Field(name), KnackBuilder::Document(doc)) => doc.remove(name),
+        (PathSegment::Field(_) | PathSegment::Index(_), KnackBuilder::Array(array)) => last
+            .as_index()
+            .filter(|index| *index < array.len())
+            .map(|index| array.remove(index)),
+        _ => None,
+    };
+
+    removed.ok_or_else(|| KnackError::patch(path, "no such value"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::knack::{
+        buf::{IntoKnackBuf, KnackBuf},
+        builder::KnackBuilder,
+        error::KnackErrorKind,
+        path::KnackPath,
+        Knack,
+    };
+
+    use super::{diff, merge_patch, PatchOp};
+
+    fn knack(json: &str) -> KnackBuf {
+        KnackBuilder::from_json(json).unwrap().into_knack_buf()
+    }
+
+    fn path(path: &str) -> KnackPath {
+        KnackPath::parse(path).unwrap()
+    }
+
+    fn json(knack: &Knack) -> String {
+        let mut out = Vec::default();
+        knack.to_json(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_diff_and_apply() {
+        let pairs = [
+            (r#"{"a": 1, "b": {"c": "x"}}"#, r#"{"a": 1, "b": {"c": "y", "d": null}}"#),
+            (r#"{"items": [1, 2, 3]}"#, r#"{"items": [1, 4]}"#),
+            (r#"{"items": [{"sku": "a"}]}"#, r#"{"items": [{"sku": "b"}, {"sku": "c"}], "n": 2.5}"#),
+            (r#"{"a": [1]}"#, r#"{"a": {"0": 1}}"#),
+            ("1", r#"{"a/b": "~"}"#),
+        ];
+
+        for (from, to) in pairs {
+            let (from, to) = (knack(from), knack(to));
+            let patch = diff(&from, &to);
+
+            let mut patched = KnackBuilder::from(&*from);
+            patch.apply(&mut patched).unwrap();
+            assert_eq!(json(&patched.into_knack_buf()), json(&to));
+        }
+
+        assert!(diff(&knack(r#"{"a": [1]}"#), &knack(r#"{"a": [1]}"#)).is_empty());
+
+        let mut out = Vec::default();
+        diff(&knack(r#"{"a/b": [1, 2], "c": 1}"#), &knack(r#"{"a/b": [1], "d": true}"#)).to_json(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            r#"[{"op":"remove","path":"/a~1b/1"},{"op":"remove","path":"/c"},{"op":"add","path":"/d","value":true}]"#
+        );
+    }
+
+    #[test]
+    fn test_apply_ops() {
+        let mut value = KnackBuilder::from_json(r#"{"a": {"b": 1}, "list": ["x", "z"]}"#).unwrap();
+
+        let mut patch = super::Patch::default();
+        patch.push(PatchOp::Test { path: path("a.b"), value: KnackBuilder::from_json("1").unwrap() });
+        patch.push(PatchOp::Add { path: path("list[1]"), value: KnackBuilder::from_json(r#""y""#).unwrap() });
+        patch.push(PatchOp::Copy { from: path("a"), path: path("copy") });
+        patch.push(PatchOp::Move { from: path("a.b"), path: path("b") });
+        patch.apply(&mut value).unwrap();
+        assert_eq!(
+            json(&value.clone().into_knack_buf()),
+            r#"{"a":{},"b":1,"copy":{"b":1},"list":["x","y","z"]}"#
+        );
+
+        // Un correctif en échec ne modifie pas la valeur.
+        let mut failing = super::Patch::default();
+        failing.push(PatchOp::Remove { path: path("b") });
+        failing.push(PatchOp::Replace { path: path("list[5]"), value: KnackBuilder::from_json("0").unwrap() });
+
+        match failing.apply(&mut value).err().unwrap().kind() {
+            KnackErrorKind::Patch { path: at, .. } => assert_eq!(at, &path("list[5]")),
+            _ => panic!("expecting a patch error"),
+        }
+        assert!(value.get("b").is_some());
+    }
+
+    #[test]
+    fn test_merge_patch() {
+        // Exemple de la RFC 7396.
+        let mut target = KnackBuilder::from_json(
+            r#"{"title": "Goodbye!", "author": {"givenName": "John", "familyName": "Doe"}, "tags": ["example", "sample"], "content": "This will be unchanged"}"#,
+        )
+        .unwrap();
+        let patch = knack(
+            r#"{"title": "Hello!", "phoneNumber": "+01-123-456-7890", "author": {"familyName": null}, "tags": ["example"]}"#,
+        );
+
+        merge_patch(&mut target, &patch);
+        assert_eq!(
+            json(&target.into_knack_buf()),
+            r#"{"author":{"givenName":"John"},"content":"This will be unchanged","phoneNumber":"+01-123-456-7890","tags":["example"],"title":"Hello!"}"#
+        );
+
+        let mut target = KnackBuilder::from_json(r#"{"a": "b"}"#).unwrap();
+        merge_patch(&mut target, &knack(r#"{"a": {"b": null, "c": 1}}"#));
+        assert_eq!(json(&target.into_knack_buf()), r#"{"a":{"c":1}}"#);
+    }
+}
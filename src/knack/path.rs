@@ -1,27 +1,358 @@
-use std::collections::VecDeque;
+//! Chemins vers une valeur imbriquée.
+//!
+//! # Syntaxe
+//! - `a.b` : champ b du document a ;
+//! - `items[3]` ou `items.3` : quatrième élément de la liste items ;
+//! - `items[*].price` : champ price de chaque élément de items ;
+//! - `*.id` : champ id de chaque valeur d'un document (ou élément d'une liste) ;
+//! - `"a.b"`, `["a.b"]` ou `a\.b` : champ dont le nom contient un caractère spécial.
+//!
+//! Dans un nom non délimité, `\` échappe le caractère qui suit (`.`, `[`, `*`, `"` ou `\`).
+use std::{fmt::Display, str::FromStr};
 
+use super::{
+    error::{KnackError, KnackErrorKind},
+    result::KnackResult,
+};
 
+/// Valeur désignant un chemin.
+///
+/// Les chemins textuels sont analysés par [KnackPath::parse], l'erreur est retournée
+/// si le chemin est invalide.
 pub trait IntoKnackPath {
-    fn into_value_path(self) -> KnackPath;
+    fn try_into_value_path(self) -> KnackResult<KnackPath>;
 }
 
-impl<V> IntoKnackPath for V where KnackPath: From<V> {
-    fn into_value_path(self) -> KnackPath {
-        self.into()
+impl IntoKnackPath for KnackPath {
+    fn try_into_value_path(self) -> KnackResult<KnackPath> {
+        Ok(self)
     }
 }
 
-pub struct KnackPath(VecDeque<String>);
+impl IntoKnackPath for &KnackPath {
+    fn try_into_value_path(self) -> KnackResult<KnackPath> {
+        Ok(self.clone())
+    }
+}
+
+impl IntoKnackPath for &str {
+    fn try_into_value_path(self) -> KnackResult<KnackPath> {
+        KnackPath::parse(self)
+    }
+}
+
+impl IntoKnackPath for &String {
+    fn try_into_value_path(self) -> KnackResult<KnackPath> {
+        KnackPath::parse(self)
+    }
+}
+
+/// Segment d'un chemin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    /// Champ d'un document, ou indice d'une liste s'il est numérique.
+    Field(String),
+    /// Elément d'une liste.
+    Index(usize),
+    /// Tous les éléments d'une liste (`[*]`).
+    AnyIndex,
+    /// Toutes les valeurs d'un document ou d'une liste (`*`).
+    Wildcard,
+}
+
+impl PathSegment {
+    /// Indice désigné par le segment, s'il en désigne un.
+    pub fn as_index(&self) -> Option<usize> {
+        match self {
+            PathSegment::Field(name) => name.parse().ok(),
+            PathSegment::Index(index) => Some(*index),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KnackPath(Vec<PathSegment>);
 
 impl KnackPath {
-    pub fn pop(&mut self) -> Option<String> {
-        self.0.pop_front()
+    /// Analyse un chemin.
+    ///
+    /// Les erreurs indiquent la position, en octets, à laquelle l'analyse a échoué.
+    pub fn parse(path: &str) -> KnackResult<Self> {
+        PathParser { bytes: path.as_bytes(), pos: 0 }.parse()
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.0
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn push(&mut self, segment: PathSegment) {
+        self.0.push(segment)
+    }
+
+    /// Chemin vers un champ de la valeur désignée.
+    pub fn field(&self, name: &str) -> Self {
+        let mut path = self.clone();
+        path.push(PathSegment::Field(name.to_owned()));
+        path
+    }
+
+    /// Chemin vers un élément de la liste désignée.
+    pub fn index(&self, index: usize) -> Self {
+        let mut path = self.clone();
+        path.push(PathSegment::Index(index));
+        path
+    }
+}
+
+impl FromIterator<PathSegment> for KnackPath {
+    fn from_iter<T: IntoIterator<Item = PathSegment>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl FromStr for KnackPath {
+    type Err = KnackError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// Forme canonique, relue à l'identique par [KnackPath::parse].
+impl Display for KnackPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            match segment {
+                PathSegment::Field(name) => {
+                    if i > 0 {
+                        f.write_str(".")?;
+                    }
+
+                    if name.is_empty() {
+                        f.write_str("\"\"")?;
+                    }
+
+                    for c in name.chars() {
+                        if matches!(c, '.' | '[' | '*' | '"' | '\\') {
+                            f.write_str("\\")?;
+                        }
+                        write!(f, "{c}")?;
+                    }
+                }
+                PathSegment::Index(index) => write!(f, "[{index}]")?,
+                PathSegment::AnyIndex => f.write_str("[*]")?,
+                PathSegment::Wildcard => {
+                    if i > 0 {
+                        f.write_str(".")?;
+                    }
+                    f.write_str("*")?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl KnackError {
+    fn path(position: usize, message: &'static str) -> Self {
+        Self::new(KnackErrorKind::Path { position, message })
     }
 }
 
-impl From<&str> for KnackPath {
-    fn from(value: &str) -> Self {
-        Self(value.split(".").into_iter().map(|seg| seg.to_owned()).collect())
+struct PathParser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl PathParser<'_> {
+    fn parse(mut self) -> KnackResult<KnackPath> {
+        let mut path = KnackPath::default();
+
+        if self.bytes.is_empty() {
+            return Ok(path);
+        }
+
+        // Le chemin peut débuter par un indice, si la racine est une liste.
+        if self.peek() != Some(b'[') {
+            path.push(self.parse_segment()?);
+        }
+
+        loop {
+            while self.peek() == Some(b'[') {
+                path.push(self.parse_bracket()?);
+            }
+
+            match self.peek() {
+                None => return Ok(path),
+                Some(b'.') => {
+                    self.pos += 1;
+                    path.push(self.parse_segment()?);
+                }
+                Some(_) => return Err(self.error("expecting '.' or '['")),
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn error(&self, message: &'static str) -> KnackError {
+        KnackError::path(self.pos, message)
+    }
+
+    fn parse_segment(&mut self) -> KnackResult<PathSegment> {
+        match self.peek() {
+            Some(b'"') => self.parse_quoted().map(PathSegment::Field),
+            Some(b'*') if matches!(self.bytes.get(self.pos + 1), None | Some(b'.') | Some(b'[')) => {
+                self.pos += 1;
+                Ok(PathSegment::Wildcard)
+            }
+            _ => self.parse_bare().map(PathSegment::Field),
+        }
+    }
+
+    /// Nom non délimité, jusqu'au prochain '.' ou '[' non échappé.
+    fn parse_bare(&mut self) -> KnackResult<String> {
+        let mut name = Vec::default();
+
+        while let Some(b) = self.peek() {
+            match b {
+                b'.' | b'[' => break,
+                b'\\' => {
+                    self.pos += 1;
+                    let escaped = self.peek().ok_or_else(|| self.error("unterminated escape"))?;
+                    name.push(escaped);
+                }
+                _ => name.push(b),
+            }
+            self.pos += 1;
+        }
+
+        if name.is_empty() {
+            return Err(self.error("empty segment"));
+        }
+
+        // Seuls des octets ASCII ont été retirés d'une chaîne valide.
+        Ok(String::from_utf8(name).unwrap())
+    }
+
+    fn parse_quoted(&mut self) -> KnackResult<String> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut name = Vec::default();
+
+        loop {
+            match self.peek() {
+                None => return Err(KnackError::path(start, "unterminated quoted name")),
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(String::from_utf8(name).unwrap());
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let escaped = self.peek().ok_or_else(|| self.error("unterminated escape"))?;
+                    name.push(escaped);
+                }
+                Some(b) => name.push(b),
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn parse_bracket(&mut self) -> KnackResult<PathSegment> {
+        self.pos += 1;
+
+        let segment = match self.peek() {
+            Some(b'*') => {
+                self.pos += 1;
+                PathSegment::AnyIndex
+            }
+            Some(b'"') => PathSegment::Field(self.parse_quoted()?),
+            Some(b'0'..=b'9') => {
+                let start = self.pos;
+                while matches!(self.peek(), Some(b'0'..=b'9')) {
+                    self.pos += 1;
+                }
+                let digits = std::str::from_utf8(&self.bytes[start..self.pos]).unwrap();
+                PathSegment::Index(digits.parse().map_err(|_| KnackError::path(start, "index is too large"))?)
+            }
+            _ => return Err(self.error("expecting an index, '*' or a quoted name")),
+        };
+
+        if self.peek() != Some(b']') {
+            return Err(self.error("expecting ']'"));
+        }
+
+        self.pos += 1;
+        Ok(segment)
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use crate::knack::error::KnackErrorKind;
+
+    use super::{KnackPath, PathSegment};
+
+    fn parse(path: &str) -> KnackPath {
+        KnackPath::parse(path).unwrap()
+    }
+
+    fn field(name: &str) -> PathSegment {
+        PathSegment::Field(name.to_owned())
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!(parse("").is_root());
+        assert_eq!(parse("orders.0.sku").segments(), &[field("orders"), field("0"), field("sku")]);
+        assert_eq!(
+            parse("items[3].price").segments(),
+            &[field("items"), PathSegment::Index(3), field("price")]
+        );
+        assert_eq!(
+            parse("items[*].tags[0]").segments(),
+            &[field("items"), PathSegment::AnyIndex, field("tags"), PathSegment::Index(0)]
+        );
+        assert_eq!(parse("*.id").segments(), &[PathSegment::Wildcard, field("id")]);
+        assert_eq!(parse("[1]").segments(), &[PathSegment::Index(1)]);
+
+        // Echappement et délimitation des noms.
+        assert_eq!(parse(r#"a\.b.c"#).segments(), &[field("a.b"), field("c")]);
+        assert_eq!(parse(r#""a.b".c"#).segments(), &[field("a.b"), field("c")]);
+        assert_eq!(parse(r#"x["a.b"]"#).segments(), &[field("x"), field("a.b")]);
+        assert_eq!(parse(r#"\*.*x"#).segments(), &[field("*"), field("*x")]);
+    }
+
+    #[test]
+    fn test_display() {
+        for path in [r#"a\.b[2].c"#, "items[*].price", "*.id", r#""".q\"x"#, "[0][1]"] {
+            let parsed = parse(path);
+            assert_eq!(parse(&parsed.to_string()), parsed);
+        }
+
+        assert_eq!(KnackPath::default().field("a.b").index(2).to_string(), r#"a\.b[2]"#);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let position = |path: &str| match KnackPath::parse(path).unwrap_err().kind() {
+            KnackErrorKind::Path { position, .. } => *position,
+            _ => panic!("expecting a path error"),
+        };
+
+        assert_eq!(position("a..b"), 2);
+        assert_eq!(position("a[x]"), 2);
+        assert_eq!(position("a[1"), 3);
+        assert_eq!(position(r#""a"#), 0);
+        assert_eq!(position(r#"a\"#), 2);
+        assert_eq!(position("a[1]b"), 4);
+    }
+}
@@ -0,0 +1,349 @@
+//! Sérialisation de valeurs Rust en knacks (cf [serde]).
+//!
+//! # Correspondance
+//! - entiers, flottants et booléens : knack du même type ;
+//! - caractères et chaînes : str ;
+//! - octets bruts : bytes ;
+//! - listes et tuples : liste ;
+//! - structures et dictionnaires : document, les clés des dictionnaires doivent être des chaînes ;
+//! - variantes unitaires : str, le nom de la variante ;
+//! - autres variantes : document à un seul champ, le nom de la variante ;
+//! - valeurs nulles (None, ()) : null.
+use serde::{ser, Serialize};
+
+use super::{
+    buf::{IntoKnackBuf, KnackBuf},
+    builder::{IntoKnackBuilder, KnackBuilder},
+    document::DocBuilder,
+    error::KnackError,
+    result::KnackResult,
+};
+
+/// Sérialise une valeur en un constructeur de knack.
+pub fn to_knack_builder<T: Serialize + ?Sized>(value: &T) -> KnackResult<KnackBuilder> {
+    value.serialize(KnackSerializer)
+}
+
+/// Sérialise une valeur en un knack.
+pub fn to_knack_buf<T: Serialize + ?Sized>(value: &T) -> KnackResult<KnackBuf> {
+    to_knack_builder(value).map(IntoKnackBuf::into_knack_buf)
+}
+
+/// Sérialiseur de knacks.
+pub struct KnackSerializer;
+
+impl ser::Serializer for KnackSerializer {
+    type Ok = KnackBuilder;
+    type Error = KnackError;
+
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = VariantSerializer<SeqSerializer>;
+    type SerializeMap = DocSerializer;
+    type SerializeStruct = DocSerializer;
+    type SerializeStructVariant = VariantSerializer<DocSerializer>;
+
+    fn serialize_bool(self, v: bool) -> KnackResult<Self::Ok> {
+        Ok(v.into_knack_builder())
+    }
+
+    fn serialize_i8(self, v: i8) -> KnackResult<Self::Ok> {
+        Ok(v.into_knack_builder())
+    }
+
+    fn serialize_i16(self, v: i16) -> KnackResult<Self::Ok> {
+        Ok(v.into_knack_builder())
+    }
+
+    fn serialize_i32(self, v: i32) -> KnackResult<Self::Ok> {
+        Ok(v.into_knack_builder())
+    }
+
+    fn serialize_i64(self, v: i64) -> KnackResult<Self::Ok> {
+        Ok(v.into_knack_builder())
+    }
+
+    fn serialize_i128(self, v: i128) -> KnackResult<Self::Ok> {
+        Ok(v.into_knack_builder())
+    }
+
+    fn serialize_u8(self, v: u8) -> KnackResult<Self::Ok> {
+        Ok(v.into_knack_builder())
+    }
+
+    fn serialize_u16(self, v: u16) -> KnackResult<Self::Ok> {
+        Ok(v.into_knack_builder())
+    }
+
+    fn serialize_u32(self, v: u32) -> KnackResult<Self::Ok> {
+        Ok(v.into_knack_builder())
+    }
+
+    fn serialize_u64(self, v: u64) -> KnackResult<Self::Ok> {
+        Ok(v.into_knack_builder())
+    }
+
+    fn serialize_u128(self, v: u128) -> KnackResult<Self::Ok> {
+        Ok(v.into_knack_builder())
+    }
+
+    fn serialize_f32(self, v: f32) -> KnackResult<Self::Ok> {
+        Ok(v.into_knack_builder())
+    }
+
+    fn serialize_f64(self, v: f64) -> KnackResult<Self::Ok> {
+        Ok(v.into_knack_builder())
+    }
+
+    fn serialize_char(self, v: char) -> KnackResult<Self::Ok> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> KnackResult<Self::Ok> {
+        Ok(v.into_knack_builder())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> KnackResult<Self::Ok> {
+        Ok(v.into_knack_builder())
+    }
+
+    fn serialize_none(self) -> KnackResult<Self::Ok> {
+        Ok(().into_knack_builder())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> KnackResult<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> KnackResult<Self::Ok> {
+        Ok(().into_knack_builder())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> KnackResult<Self::Ok> {
+        Ok(().into_knack_builder())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> KnackResult<Self::Ok> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> KnackResult<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> KnackResult<Self::Ok> {
+        let mut doc = DocBuilder::default();
+        doc.insert(variant, value.serialize(KnackSerializer)?);
+        Ok(doc.into_knack_builder())
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> KnackResult<Self::SerializeSeq> {
+        Ok(SeqSerializer::with_capacity(len.unwrap_or_default()))
+    }
+
+    fn serialize_tuple(self, len: usize) -> KnackResult<Self::SerializeTuple> {
+        Ok(SeqSerializer::with_capacity(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> KnackResult<Self::SerializeTupleStruct> {
+        Ok(SeqSerializer::with_capacity(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> KnackResult<Self::SerializeTupleVariant> {
+        Ok(VariantSerializer {
+            variant,
+            inner: SeqSerializer::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> KnackResult<Self::SerializeMap> {
+        Ok(DocSerializer::default())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> KnackResult<Self::SerializeStruct> {
+        Ok(DocSerializer::default())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> KnackResult<Self::SerializeStructVariant> {
+        Ok(VariantSerializer {
+            variant,
+            inner: DocSerializer::default(),
+        })
+    }
+}
+
+/// Sérialise une structure ou un dictionnaire en document.
+#[derive(Default)]
+pub struct DocSerializer {
+    doc: DocBuilder,
+    key: Option<String>,
+}
+
+impl DocSerializer {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> KnackResult<()> {
+        self.doc.insert(key, value.serialize(KnackSerializer)?);
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for DocSerializer {
+    type Ok = KnackBuilder;
+    type Error = KnackError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> KnackResult<()> {
+        match key.serialize(KnackSerializer)? {
+            key if key.is::<str>() => {
+                self.key = Some(key.cast::<str>().to_string());
+                Ok(())
+            }
+            _ => Err(ser::Error::custom("map keys must be strings")),
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> KnackResult<()> {
+        let key = self.key.take().expect("value serialized before its key");
+        self.insert(&key, value)
+    }
+
+    fn end(self) -> KnackResult<Self::Ok> {
+        Ok(self.doc.into_knack_builder())
+    }
+}
+
+impl ser::SerializeStruct for DocSerializer {
+    type Ok = KnackBuilder;
+    type Error = KnackError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> KnackResult<()> {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> KnackResult<Self::Ok> {
+        Ok(self.doc.into_knack_builder())
+    }
+}
+
+/// Sérialise une liste ou un tuple en liste.
+pub struct SeqSerializer(Vec<KnackBuilder>);
+
+impl SeqSerializer {
+    fn with_capacity(capacity: usize) -> Self {
+        Self(Vec::with_capacity(capacity))
+    }
+
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> KnackResult<()> {
+        self.0.push(value.serialize(KnackSerializer)?);
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = KnackBuilder;
+    type Error = KnackError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> KnackResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> KnackResult<Self::Ok> {
+        Ok(self.0.into_knack_builder())
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = KnackBuilder;
+    type Error = KnackError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> KnackResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> KnackResult<Self::Ok> {
+        Ok(self.0.into_knack_builder())
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = KnackBuilder;
+    type Error = KnackError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> KnackResult<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> KnackResult<Self::Ok> {
+        Ok(self.0.into_knack_builder())
+    }
+}
+
+/// Sérialise une variante de structure ou de tuple en un document à un seul champ.
+pub struct VariantSerializer<Inner> {
+    variant: &'static str,
+    inner: Inner,
+}
+
+impl<Inner> VariantSerializer<Inner> {
+    fn wrap<V: IntoKnackBuilder>(variant: &str, value: V) -> KnackBuilder {
+        let mut doc = DocBuilder::default();
+        doc.insert(variant, value);
+        doc.into_knack_builder()
+    }
+}
+
+impl ser::SerializeStructVariant for VariantSerializer<DocSerializer> {
+    type Ok = KnackBuilder;
+    type Error = KnackError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> KnackResult<()> {
+        self.inner.insert(key, value)
+    }
+
+    fn end(self) -> KnackResult<Self::Ok> {
+        Ok(Self::wrap(self.variant, self.inner.doc))
+    }
+}
+
+impl ser::SerializeTupleVariant for VariantSerializer<SeqSerializer> {
+    type Ok = KnackBuilder;
+    type Error = KnackError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> KnackResult<()> {
+        self.inner.push(value)
+    }
+
+    fn end(self) -> KnackResult<Self::Ok> {
+        Ok(Self::wrap(self.variant, self.inner.0))
+    }
+}
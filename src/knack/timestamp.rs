@@ -0,0 +1,79 @@
+//! Horodatage UTC, stocké en microsecondes depuis l'epoch Unix.
+use std::{
+    fmt::Display,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Timestamp(i64);
+
+impl Timestamp {
+    const MICROS_PER_SEC: i64 = 1_000_000;
+    const MICROS_PER_DAY: i64 = 86_400 * Self::MICROS_PER_SEC;
+
+    pub const fn from_micros(micros: i64) -> Self {
+        Self(micros)
+    }
+
+    /// Nombre de microsecondes depuis l'epoch Unix
+    pub const fn as_micros(&self) -> i64 {
+        self.0
+    }
+
+    pub fn now() -> Self {
+        Self::from(SystemTime::now())
+    }
+}
+
+impl From<SystemTime> for Timestamp {
+    fn from(value: SystemTime) -> Self {
+        match value.duration_since(UNIX_EPOCH) {
+            Ok(after) => Self(i64::try_from(after.as_micros()).unwrap()),
+            Err(before) => Self(-i64::try_from(before.duration().as_micros()).unwrap()),
+        }
+    }
+}
+
+/// Format RFC 3339, par exemple 2025-03-01T12:30:00.000000Z.
+impl Display for Timestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let days = self.0.div_euclid(Self::MICROS_PER_DAY);
+        let micros = self.0.rem_euclid(Self::MICROS_PER_DAY);
+        let secs = micros / Self::MICROS_PER_SEC;
+
+        // Conversion du nombre de jours en date du calendrier grégorien (H. Hinnant, civil_from_days).
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + i64::from(month <= 2);
+
+        write!(
+            f,
+            "{year:04}-{month:02}-{day:02}T{0:02}:{1:02}:{2:02}.{3:06}Z",
+            secs / 3_600,
+            secs / 60 % 60,
+            secs % 60,
+            micros % Self::MICROS_PER_SEC
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Timestamp;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Timestamp::from_micros(0).to_string(), "1970-01-01T00:00:00.000000Z");
+        assert_eq!(
+            Timestamp::from_micros(1_709_296_200_000_042).to_string(),
+            "2024-03-01T12:30:00.000042Z"
+        );
+        assert_eq!(Timestamp::from_micros(-1).to_string(), "1969-12-31T23:59:59.999999Z");
+    }
+}
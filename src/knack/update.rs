@@ -0,0 +1,441 @@
+//! Mise à jour d'un document stocké, champ par champ.
+//!
+//! # Opérateurs
+//! - set : remplace la valeur, en créant les documents intermédiaires manquants ;
+//! - unset : retire le champ, ou l'élément de la liste ;
+//! - inc : incrémente un nombre, l'incrément doit être du même type que la valeur ;
+//! - push : ajoute un élément à une liste, créée si besoin ;
+//! - pull : retire d'une liste tous les éléments identiques (même type, même valeur) ;
+//! - add_to_set : ajoute un élément à une liste s'il n'y figure pas déjà.
+//!
+//! Les opérateurs sont appliqués dans l'ordre, leurs chemins peuvent contenir des jokers (cf [KnackPath]).
+//!
+//! Chaque opérateur effectif produit un nouveau document complet : les documents et listes
+//! situés sur son chemin sont reconstruits (cf [DocBuilder]), les autres valeurs y sont recopiées
+//! octet pour octet, puis l'ensemble est resérialisé. Les pages de débordement ne sont pas
+//! prises en compte : le document est mis à jour tel qu'il est fourni, entièrement chargé.
+//! Une mise à jour sans effet ne produit pas de nouveau document.
+use std::ops::Deref;
+
+use serde::de::DeserializeOwned;
+
+use super::{
+    array::Array,
+    buf::{IntoKnackBuf, KnackBuf},
+    builder::{IntoKnackBuilder, KnackBuilder},
+    de::from_knack,
+    document::{DocBuilder, Document},
+    error::{KnackError, KnackErrorKind},
+    kind::{
+        F32_TYPE_ID, F64_TYPE_ID, I128_TYPE_ID, I16_TYPE_ID, I32_TYPE_ID, I64_TYPE_ID, I8_TYPE_ID,
+        U128_TYPE_ID, U16_TYPE_ID, U32_TYPE_ID, U64_TYPE_ID, U8_TYPE_ID,
+    },
+    path::{IntoKnackPath, KnackPath, PathSegment},
+    result::KnackResult,
+    Knack,
+};
+
+/// Opérateur de mise à jour.
+pub enum UpdateOp {
+    Set(KnackBuf),
+    Unset,
+    Inc(KnackBuf),
+    Push(KnackBuf),
+    Pull(KnackBuf),
+    AddToSet(KnackBuf),
+}
+
+/// Liste d'opérateurs à appliquer à un document.
+///
+/// Un chemin textuel invalide fait échouer [Update::apply].
+///
+/// # Exemple
+/// let update = Update::default()
+///     .set("address.city", "Jarnac")
+///     .inc("orders_count", 1i64)
+///     .add_to_set("tags", "vip");
+#[derive(Default)]
+pub struct Update(Vec<(KnackResult<KnackPath>, UpdateOp)>);
+
+impl Update {
+    pub fn set<P: IntoKnackPath, V: IntoKnackBuilder>(self, path: P, value: V) -> Self {
+        self.with_op(path, UpdateOp::Set(value.into_knack_builder().into_knack_buf()))
+    }
+
+    pub fn unset<P: IntoKnackPath>(self, path: P) -> Self {
+        self.with_op(path, UpdateOp::Unset)
+    }
+
+    pub fn inc<P: IntoKnackPath, V: IntoKnackBuilder>(self, path: P, by: V) -> Self {
+        self.with_op(path, UpdateOp::Inc(by.into_knack_builder().into_knack_buf()))
+    }
+
+    pub fn push<P: IntoKnackPath, V: IntoKnackBuilder>(self, path: P, value: V) -> Self {
+        self.with_op(path, UpdateOp::Push(value.into_knack_builder().into_knack_buf()))
+    }
+
+    pub fn pull<P: IntoKnackPath, V: IntoKnackBuilder>(self, path: P, value: V) -> Self {
+        self.with_op(path, UpdateOp::Pull(value.into_knack_builder().into_knack_buf()))
+    }
+
+    pub fn add_to_set<P: IntoKnackPath, V: IntoKnackBuilder>(self, path: P, value: V) -> Self {
+        self.with_op(path, UpdateOp::AddToSet(value.into_knack_builder().into_knack_buf()))
+    }
+
+    pub fn with_op<P: IntoKnackPath>(mut self, path: P, op: UpdateOp) -> Self {
+        self.0.push((path.try_into_value_path(), op));
+        self
+    }
+
+    /// Applique la mise à jour, retourne le nouveau document s'il a été modifié.
+    pub fn apply(&self, doc: &Document) -> KnackResult<Option<KnackBuf>> {
+        let mut current: Option<KnackBuf> = None;
+
+        for (path, op) in self.0.iter() {
+            let path = path.as_ref().map_err(Clone::clone)?;
+
+            if path.is_root() {
+                return Err(KnackError::update(path, "cannot update the document itself"));
+            }
+
+            let knack = current.as_deref().unwrap_or(Knack::from_ref(doc.deref()));
+
+            if let Rewrite::Replace(value) = rewrite(Some(knack), path.segments(), &KnackPath::default(), op)? {
+                current = Some(value.into_knack_buf());
+            }
+        }
+
+        Ok(current)
+    }
+}
+
+impl KnackError {
+    fn update(path: &KnackPath, message: &'static str) -> Self {
+        Self::new(KnackErrorKind::Update { path: path.clone(), message })
+    }
+}
+
+/// Effet d'un opérateur sur une valeur.
+enum Rewrite {
+    Unchanged,
+    Replace(KnackBuilder),
+    Remove,
+}
+
+/// Recopie une valeur sans l'analyser.
+fn raw(value: &Knack) -> KnackBuilder {
+    KnackBuilder::Other(value.to_owned())
+}
+
+fn rewrite(value: Option<&Knack>, segments: &[PathSegment], at: &KnackPath, op: &UpdateOp) -> KnackResult<Rewrite> {
+    let Some((segment, tail)) = segments.split_first() else {
+        return op.apply(value, at);
+    };
+
+    let Some(value) = value else {
+        return match segment {
+            _ if !op.creates() => Ok(Rewrite::Unchanged),
+            PathSegment::Field(name) => match rewrite(None, tail, &at.field(name), op)? {
+                Rewrite::Replace(child) => {
+                    let mut doc = DocBuilder::default();
+                    doc.insert(name, child);
+                    Ok(Rewrite::Replace(doc.into_knack_builder()))
+                }
+                _ => Ok(Rewrite::Unchanged),
+            },
+            PathSegment::Index(_) => Err(KnackError::update(at, "cannot create an array element")),
+            PathSegment::AnyIndex | PathSegment::Wildcard => Ok(Rewrite::Unchanged),
+        };
+    };
+
+    if let Ok(doc) = <&Document>::try_from(value) {
+        rewrite_document(doc, segment, tail, at, op)
+    } else if let Ok(array) = <&Array>::try_from(value) {
+        rewrite_array(array, segment, tail, at, op)
+    } else if op.creates() {
+        Err(KnackError::update(at, "cannot traverse a scalar value"))
+    } else {
+        Ok(Rewrite::Unchanged)
+    }
+}
+
+fn rewrite_document(
+    doc: &Document,
+    segment: &PathSegment,
+    tail: &[PathSegment],
+    at: &KnackPath,
+    op: &UpdateOp,
+) -> KnackResult<Rewrite> {
+    let names: Vec<&str> = match segment {
+        PathSegment::Field(name) => vec![name.as_str()],
+        PathSegment::Wildcard => doc.iter().map(|kv| kv.key().cast::<str>().deref()).collect(),
+        _ if op.creates() => return Err(KnackError::update(at, "expecting an array, got a document")),
+        _ => return Ok(Rewrite::Unchanged),
+    };
+
+    let mut changes = Vec::default();
+
+    for name in names {
+        match rewrite(doc.get_field(name), tail, &at.field(name), op)? {
+            Rewrite::Unchanged => {}
+            change => changes.push((name, change)),
+        }
+    }
+
+    if changes.is_empty() {
+        return Ok(Rewrite::Unchanged);
+    }
+
+    let mut builder: DocBuilder = doc
+        .iter()
+        .map(|kv| (kv.key().cast::<str>().to_owned(), raw(kv.value())))
+        .collect();
+
+    for (name, change) in changes {
+        match change {
+            Rewrite::Replace(value) => builder.insert(name, value),
+            Rewrite::Remove => {
+                builder.remove(name);
+            }
+            Rewrite::Unchanged => {}
+        }
+    }
+
+    Ok(Rewrite::Replace(builder.into_knack_builder()))
+}
+
+fn rewrite_array(
+    array: &Array,
+    segment: &PathSegment,
+    tail: &[PathSegment],
+    at: &KnackPath,
+    op: &UpdateOp,
+) -> KnackResult<Rewrite> {
+    let indices: Vec<usize> = match (segment, segment.as_index()) {
+        (PathSegment::AnyIndex | PathSegment::Wildcard, _) => (0..array.len()).collect(),
+        (_, Some(index)) if index < array.len() => vec![index],
+        (_, Some(_)) if op.creates() => return Err(KnackError::update(at, "index out of bounds")),
+        (_, None) if op.creates() => return Err(KnackError::update(at, "expecting a document, got an array")),
+        _ => return Ok(Rewrite::Unchanged),
+    };
+
+    let mut elements: Vec<Option<KnackBuilder>> = array.iter().map(raw).map(Some).collect();
+    let mut changed = false;
+
+    for index in indices {
+        match rewrite(array.get(&index), tail, &at.index(index), op)? {
+            Rewrite::Unchanged => continue,
+            Rewrite::Replace(value) => elements[index] = Some(value),
+            Rewrite::Remove => elements[index] = None,
+        }
+        changed = true;
+    }
+
+    if !changed {
+        return Ok(Rewrite::Unchanged);
+    }
+
+    Ok(Rewrite::Replace(elements.into_iter().flatten().collect::<Vec<_>>().into_knack_builder()))
+}
+
+impl UpdateOp {
+    /// L'opérateur crée la valeur si elle n'existe pas.
+    fn creates(&self) -> bool {
+        !matches!(self, UpdateOp::Unset | UpdateOp::Pull(_))
+    }
+
+    fn apply(&self, value: Option<&Knack>, at: &KnackPath) -> KnackResult<Rewrite> {
+        let Some(value) = value else {
+            return Ok(match self {
+                UpdateOp::Set(new) | UpdateOp::Inc(new) => Rewrite::Replace(raw(new)),
+                UpdateOp::Push(new) | UpdateOp::AddToSet(new) => Rewrite::Replace(vec![raw(new)].into_knack_builder()),
+                UpdateOp::Unset | UpdateOp::Pull(_) => Rewrite::Unchanged,
+            });
+        };
+
+        match self {
+            UpdateOp::Set(new) if value.as_ref() == new.as_ref() => Ok(Rewrite::Unchanged),
+            UpdateOp::Set(new) => Ok(Rewrite::Replace(raw(new))),
+            UpdateOp::Unset => Ok(Rewrite::Remove),
+            UpdateOp::Inc(by) => increment(value, by, at).map(Rewrite::Replace),
+            UpdateOp::Push(new) | UpdateOp::AddToSet(new) | UpdateOp::Pull(new) => {
+                let array = <&Array>::try_from(value).map_err(|_| KnackError::update(at, "expecting an array"))?;
+                let contains = array.iter().any(|element| element.as_ref() == new.as_ref());
+
+                let elements: Vec<KnackBuilder> = match self {
+                    UpdateOp::Pull(_) if !contains => return Ok(Rewrite::Unchanged),
+                    UpdateOp::Pull(_) => array
+                        .iter()
+                        .filter(|element| element.as_ref() != new.as_ref())
+                        .map(raw)
+                        .collect(),
+                    UpdateOp::AddToSet(_) if contains => return Ok(Rewrite::Unchanged),
+                    _ => array.iter().chain([new.deref()]).map(raw).collect(),
+                };
+
+                Ok(Rewrite::Replace(elements.into_knack_builder()))
+            }
+        }
+    }
+}
+
+/// Addition vérifiée, un flottant ne doit pas devenir infini.
+trait Increment: Sized {
+    fn increment(self, by: Self) -> Option<Self>;
+}
+
+macro_rules! impl_integer_increment {
+    ($($ty:ty),*) => {
+        $(
+            impl Increment for $ty {
+                fn increment(self, by: Self) -> Option<Self> {
+                    self.checked_add(by)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_float_increment {
+    ($($ty:ty),*) => {
+        $(
+            impl Increment for $ty {
+                fn increment(self, by: Self) -> Option<Self> {
+                    Some(self + by).filter(|sum| sum.is_finite())
+                }
+            }
+        )*
+    };
+}
+
+impl_integer_increment!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+impl_float_increment!(f32, f64);
+
+fn checked_increment<T: Increment + DeserializeOwned + IntoKnackBuilder>(value: &Knack, by: &Knack) -> Option<KnackBuilder> {
+    let value: T = from_knack(value).ok()?;
+    let by: T = from_knack(by).ok()?;
+    value.increment(by).map(IntoKnackBuilder::into_knack_builder)
+}
+
+fn increment(value: &Knack, by: &Knack, at: &KnackPath) -> KnackResult<KnackBuilder> {
+    if value.kind() != by.kind() {
+        return Err(KnackError::update(at, "increment must be of the same kind as the value"));
+    }
+
+    let sum = match *value.kind().type_id() {
+        U8_TYPE_ID => checked_increment::<u8>(value, by),
+        U16_TYPE_ID => checked_increment::<u16>(value, by),
+        U32_TYPE_ID => checked_increment::<u32>(value, by),
+        U64_TYPE_ID => checked_increment::<u64>(value, by),
+        U128_TYPE_ID => checked_increment::<u128>(value, by),
+        I8_TYPE_ID => checked_increment::<i8>(value, by),
+        I16_TYPE_ID => checked_increment::<i16>(value, by),
+        I32_TYPE_ID => checked_increment::<i32>(value, by),
+        I64_TYPE_ID => checked_increment::<i64>(value, by),
+        I128_TYPE_ID => checked_increment::<i128>(value, by),
+        F32_TYPE_ID => checked_increment::<f32>(value, by),
+        F64_TYPE_ID => checked_increment::<f64>(value, by),
+        _ => return Err(KnackError::update(at, "can only increment a number")),
+    };
+
+    sum.ok_or_else(|| KnackError::update(at, "increment overflows"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Deref;
+
+    use crate::knack::{
+        buf::{IntoKnackBuf, KnackBuf},
+        builder::KnackBuilder,
+        document::DocBuilder,
+        error::KnackErrorKind,
+        Knack,
+    };
+
+    use super::Update;
+
+    fn doc(json: &str) -> KnackBuf {
+        KnackBuilder::from_json(json).unwrap().into_knack_buf()
+    }
+
+    fn json(knack: &Knack) -> String {
+        let mut out = Vec::default();
+        knack.to_json(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn apply(update: Update, json: &str) -> Option<KnackBuf> {
+        update.apply(doc(json).cast::<DocBuilder>()).unwrap()
+    }
+
+    #[test]
+    fn test_set_and_unset() {
+        let updated = apply(
+            Update::default().set("address.city", "Jarnac").unset("nickname").unset("missing"),
+            r#"{"name": "Jean", "nickname": "JN"}"#,
+        )
+        .unwrap();
+        assert_eq!(json(&updated), r#"{"address":{"city":"Jarnac"},"name":"Jean"}"#);
+
+        let updated = apply(Update::default().set("items[1]", 0i64).unset("items.0"), r#"{"items": [1, 2]}"#).unwrap();
+        assert_eq!(json(&updated), r#"{"items":[0]}"#);
+
+        // Un chemin invalide fait échouer la mise à jour.
+        assert!(Update::default().set("a..b", 1i64).apply(doc("{}").cast::<DocBuilder>()).is_err());
+
+        // Aucun effet, aucun nouveau document.
+        assert!(apply(Update::default().set("name", "Jean").unset("nickname"), r#"{"name": "Jean"}"#).is_none());
+    }
+
+    #[test]
+    fn test_inc() {
+        let updated = apply(
+            Update::default().inc("count", 2i64).inc("items[*].qty", 1i64).inc("created", 1i64),
+            r#"{"count": 40, "items": [{"qty": 1}, {"qty": 2}]}"#,
+        )
+        .unwrap();
+        assert_eq!(json(&updated), r#"{"count":42,"created":1,"items":[{"qty":2},{"qty":3}]}"#);
+
+        let error = |update: Update, json: &str| match update.apply(doc(json).cast::<DocBuilder>()).err().unwrap().kind() {
+            KnackErrorKind::Update { path, message } => format!("{path}: {message}"),
+            _ => panic!("expecting an update error"),
+        };
+
+        assert_eq!(
+            error(Update::default().inc("a.count", 1u8), r#"{"a": {"count": 1}}"#),
+            "a.count: increment must be of the same kind as the value"
+        );
+        assert_eq!(error(Update::default().inc("a", 1i64), r#"{"a": "1"}"#), "a: increment must be of the same kind as the value");
+        assert_eq!(error(Update::default().inc("a", 1i64), &format!(r#"{{"a": {0}}}"#, i64::MAX)), "a: increment overflows");
+        assert_eq!(error(Update::default().set("a.b", 1i64), r#"{"a": 1}"#), "a: cannot traverse a scalar value");
+        assert_eq!(error(Update::default().set("a[3]", 1i64), r#"{"a": []}"#), "a: index out of bounds");
+    }
+
+    #[test]
+    fn test_arrays() {
+        let updated = apply(
+            Update::default()
+                .push("tags", "new")
+                .pull("tags", "old")
+                .add_to_set("tags", "vip")
+                .add_to_set("roles", "admin")
+                .push("orders", DocBuilder::from_iter([("sku".to_owned(), KnackBuilder::from_json(r#""JRN-18""#).unwrap())])),
+            r#"{"tags": ["old", "vip", "old"], "orders": []}"#,
+        )
+        .unwrap();
+        assert_eq!(json(&updated), r#"{"orders":[{"sku":"JRN-18"}],"roles":["admin"],"tags":["vip","new"]}"#);
+
+        // Les documents sont comparés à l'identique.
+        let updated = apply(
+            Update::default().pull("orders", DocBuilder::from_iter([("qty".to_owned(), KnackBuilder::from_json("1").unwrap())])),
+            r#"{"orders": [{"qty": 1}, {"qty": 2}]}"#,
+        )
+        .unwrap();
+        assert_eq!(updated.get("orders").unwrap().get_all("[*].qty").len(), 1);
+
+        assert!(apply(Update::default().add_to_set("tags", "vip").pull("tags", "old"), r#"{"tags": ["vip"]}"#).is_none());
+        assert!(Update::default().push("tags", 1u8).apply(doc(r#"{"tags": 1}"#).cast::<DocBuilder>()).is_err());
+        assert_eq!(apply(Update::default().push("n", 1u8).push("n", 2u8), "{}").unwrap().get("n[1]").unwrap().cast::<u8>().deref(), &2);
+    }
+}
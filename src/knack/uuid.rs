@@ -0,0 +1,84 @@
+//! Identifiant unique universel (RFC 9562).
+use std::{fmt::Display, str::FromStr};
+
+use super::error::{KnackError, KnackErrorKind};
+
+/// Les octets sont stockés dans l'ordre canonique, l'ordre des identifiants est donc l'ordre lexicographique.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Uuid([u8; 16]);
+
+impl Uuid {
+    pub const fn from_bytes(bytes: [u8; 16]) -> Self {
+        Self(bytes)
+    }
+
+    pub const fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+
+    /// Génère un identifiant aléatoire (version 4).
+    pub fn new_v4() -> Self {
+        let mut bytes: [u8; 16] = rand::random();
+        bytes[6] = (bytes[6] & 0x0F) | 0x40;
+        bytes[8] = (bytes[8] & 0x3F) | 0x80;
+        Self(bytes)
+    }
+}
+
+/// Format hyphéné, par exemple 67e55044-10b1-426f-9247-bb680e5fe0c8.
+impl Display for Uuid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if matches!(i, 4 | 6 | 8 | 10) {
+                write!(f, "-")?;
+            }
+
+            write!(f, "{byte:02x}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Uuid {
+    type Err = KnackError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || KnackError::new(KnackErrorKind::Custom(format!("invalid uuid {s}")));
+        let hex = s.replace('-', "");
+
+        if s.len() != 36 || hex.len() != 32 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        let mut bytes = [0u8; 16];
+
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).map_err(|_| invalid())?;
+        }
+
+        let uuid = Self(bytes);
+
+        // Les tirets doivent être à leur place.
+        if uuid.to_string() != s.to_ascii_lowercase() {
+            return Err(invalid());
+        }
+
+        Ok(uuid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Uuid;
+
+    #[test]
+    fn test_parse_and_display() {
+        let uuid: Uuid = "67E55044-10b1-426f-9247-bb680e5fe0c8".parse().unwrap();
+        assert_eq!(uuid.to_string(), "67e55044-10b1-426f-9247-bb680e5fe0c8");
+        assert!("67e5504410b1-426f-9247-bb680e5fe0c8-".parse::<Uuid>().is_err());
+
+        let random = Uuid::new_v4();
+        assert_eq!(random.as_bytes()[6] >> 4, 4);
+    }
+}
@@ -22,3 +22,6 @@ pub mod tag;
 pub mod arena;
 pub mod bpt;
 pub mod free;
+pub mod compression;
+pub mod crypto;
+pub mod check;
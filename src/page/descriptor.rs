@@ -1,9 +1,11 @@
 use crate::{result::Result, tag::JarTag};
+use parking_lot::{Condvar, Mutex};
 use std::{
     fmt::Debug,
     marker::PhantomData,
     ptr::NonNull,
-    sync::atomic::{AtomicIsize, AtomicUsize, Ordering as SyncOrdering},
+    sync::atomic::{fence, AtomicIsize, AtomicUsize, Ordering as SyncOrdering},
+    time::Instant,
 };
 
 use super::{MutPage, PageSlice, RefPage};
@@ -57,6 +59,7 @@ impl PageDescriptor<'_> {
         inner.rw_counter = AtomicIsize::new(0);
         inner.use_counter = AtomicUsize::new(0);
         inner.ref_counter = AtomicUsize::new(1);
+        inner.waiters = AtomicUsize::new(0);
         self.borrow_mut(true).fill(0);
     }
 
@@ -119,8 +122,9 @@ impl PageDescriptor<'_> {
     pub fn release_write_lock_and_acquire_read_lock(&self) {
         self.as_ref_inner()
             .rw_counter
-            .compare_exchange(-1, 1, SyncOrdering::Relaxed, SyncOrdering::Relaxed)
+            .compare_exchange(-1, 1, SyncOrdering::AcqRel, SyncOrdering::Relaxed)
             .unwrap();
+        self.notify_lock_released();
     }
 
     /// Récupère un verrou en écriture.
@@ -129,36 +133,112 @@ impl PageDescriptor<'_> {
     pub fn acquire_write_lock(&self) -> bool {
         self.as_ref_inner()
             .rw_counter
-            .compare_exchange(0, -1, SyncOrdering::Relaxed, SyncOrdering::Relaxed)
+            .compare_exchange(0, -1, SyncOrdering::AcqRel, SyncOrdering::Relaxed)
             .is_ok()
     }
 
+    /// Récupère un verrou en écriture, en attendant sa libération si besoin.
+    ///
+    /// Sans échéance, l'attente n'est pas bornée. La fonction retourne *false*
+    /// si l'échéance est dépassée avant que le verrou n'ait pu être récupéré.
+    pub fn acquire_write_lock_until(&self, deadline: Option<Instant>) -> bool {
+        self.wait_for_lock(deadline, Self::acquire_write_lock)
+    }
+
     /// Libère le verrou en écriture.
     ///
     /// La fonction panique si aucun verrou en écriture n'a été préalablement acquis.
     pub fn release_write_lock(&self) {
         self.as_ref_inner()
             .rw_counter
-            .compare_exchange(-1, 0, SyncOrdering::Relaxed, SyncOrdering::Relaxed)
+            .compare_exchange(-1, 0, SyncOrdering::AcqRel, SyncOrdering::Relaxed)
             .unwrap();
+        self.notify_lock_released();
     }
 
+    /// Récupère un verrou en lecture.
+    ///
+    /// La fonction retourne *false* si un verrou en écriture est détenu.
     pub fn acquire_read_lock(&self) -> bool {
-        let rw = self.get_rw_counter(SyncOrdering::Acquire);
-
-        if rw < 0 {
-            return false;
+        let rw_counter = &self.as_ref_inner().rw_counter;
+        let mut rw = rw_counter.load(SyncOrdering::Acquire);
+
+        while rw >= 0 {
+            match rw_counter.compare_exchange_weak(
+                rw,
+                rw + 1,
+                SyncOrdering::AcqRel,
+                SyncOrdering::Acquire,
+            ) {
+                Ok(_) => return true,
+                Err(current) => rw = current,
+            }
         }
 
-        self.as_ref_inner()
-            .rw_counter
-            .fetch_add(1, SyncOrdering::Release)
-            >= 0
+        false
+    }
+
+    /// Récupère un verrou en lecture, en attendant sa libération si besoin.
+    ///
+    /// Sans échéance, l'attente n'est pas bornée. La fonction retourne *false*
+    /// si l'échéance est dépassée avant que le verrou n'ait pu être récupéré.
+    pub fn acquire_read_lock_until(&self, deadline: Option<Instant>) -> bool {
+        self.wait_for_lock(deadline, Self::acquire_read_lock)
     }
 
     pub fn release_read_lock(&self) {
         unsafe {
-            self.dec_rw_counter(SyncOrdering::Relaxed);
+            self.dec_rw_counter(SyncOrdering::Release);
+        }
+        self.notify_lock_released();
+    }
+
+    /// Attend la libération du verrou de la page jusqu'à l'échéance.
+    fn wait_for_lock<F>(&self, deadline: Option<Instant>, acquire: F) -> bool
+    where
+        F: Fn(&Self) -> bool,
+    {
+        if acquire(self) {
+            return true;
+        }
+
+        let inner = self.as_ref_inner();
+        inner.waiters.fetch_add(1, SyncOrdering::SeqCst);
+        // Ordonne l'inscription avant la nouvelle tentative (cf [Self::notify_lock_released]).
+        fence(SyncOrdering::SeqCst);
+        let mut guard = inner.lock_mutex.lock();
+
+        let acquired = loop {
+            if acquire(self) {
+                break true;
+            }
+
+            match deadline {
+                Some(deadline) => {
+                    if inner.lock_released.wait_until(&mut guard, deadline).timed_out() {
+                        break acquire(self);
+                    }
+                }
+                None => inner.lock_released.wait(&mut guard),
+            }
+        };
+
+        drop(guard);
+        inner.waiters.fetch_sub(1, SyncOrdering::SeqCst);
+        acquired
+    }
+
+    /// Réveille les emprunteurs en attente du verrou de la page.
+    fn notify_lock_released(&self) {
+        let inner = self.as_ref_inner();
+
+        // La libération du verrou doit précéder la lecture du nombre d'emprunteurs en attente :
+        // sinon, un emprunteur qui ne voit pas la libération pourrait ne jamais être réveillé.
+        fence(SyncOrdering::SeqCst);
+
+        if inner.waiters.load(SyncOrdering::SeqCst) > 0 {
+            let _guard = inner.lock_mutex.lock();
+            inner.lock_released.notify_all();
         }
     }
 
@@ -234,6 +314,10 @@ pub(crate) struct PageDescriptorInner {
     pub use_counter: AtomicUsize,
     pub rw_counter: AtomicIsize,
     pub ref_counter: AtomicUsize,
+    /// Nombre d'emprunteurs en attente de la libération du verrou.
+    pub waiters: AtomicUsize,
+    pub lock_mutex: Mutex<()>,
+    pub lock_released: Condvar,
 }
 
 impl Debug for PageDescriptorInner {
@@ -261,6 +345,9 @@ impl PageDescriptorInner {
             use_counter: Default::default(),
             rw_counter: Default::default(),
             ref_counter: Default::default(),
+            waiters: Default::default(),
+            lock_mutex: Default::default(),
+            lock_released: Default::default(),
         }
     }
 
@@ -17,7 +17,9 @@ pub enum PageKind {
     /// La page représentant un noeud intérieur d'un arbre B+ (cf [crate::bp_tree::BPTreeInteriorPage])
     BPlusTreeInterior = 3,
     /// La page représentant une feuille d'un arbre B+ (cf [crate::bp_tree::BPTreeLeafPage])
-    BPlusTreeLeaf = 4
+    BPlusTreeLeaf = 4,
+    /// Une page de la table des générations des pages (cf [crate::pager::lsn])
+    Lsn = 5,
 }
 
 impl Display for PageKind {
@@ -28,6 +30,7 @@ impl Display for PageKind {
             PageKind::BPlusTree => write!(f, "b+ tree"),
             PageKind::BPlusTreeInterior => write!(f, "b+ tree interior"),
             PageKind::BPlusTreeLeaf => write!(f, "b+ tree leaf"),
+            PageKind::Lsn => write!(f, "lsn"),
         }
     }
 }
@@ -53,6 +56,7 @@ impl TryFrom<u8> for PageKind {
             2 => Ok(Self::BPlusTree),
             3 => Ok(Self::BPlusTreeInterior),
             4 => Ok(Self::BPlusTreeLeaf),
+            5 => Ok(Self::Lsn),
             invalid_code => Err(Error::new(ErrorKind::InvalidPageKind(invalid_code))),
         }
     }
@@ -26,6 +26,7 @@ use std::{
     marker::PhantomData,
     mem::forget,
     ops::{Deref, DerefMut, Range},
+    time::Instant,
 };
 
 use crate::{
@@ -91,6 +92,18 @@ impl<'pager> RefPage<'pager> {
         }
     }
 
+    /// Emprunte la page en lecture, en attendant la libération du verrou.
+    ///
+    /// Sans échéance, l'attente n'est pas bornée.
+    /// Retourne [ErrorKind::LockTimeout] si l'échéance est dépassée.
+    pub(crate) fn new_until(descriptor: PageDescriptor<'pager>, deadline: Option<Instant>) -> Result<Self> {
+        if descriptor.acquire_read_lock_until(deadline) {
+            Ok(Self(descriptor))
+        } else {
+            Err(Error::new(ErrorKind::LockTimeout))
+        }
+    }
+
     /// Transforme la référence en référence faible.
     pub fn downgrade(self) -> WeakPage<'pager> {
         WeakPage(self.0.clone())
@@ -103,6 +116,16 @@ impl<'pager> RefPage<'pager> {
     pub fn tag(&self) -> &JarTag {
         self.0.tag()
     }
+
+    /// La page a été modifiée depuis son chargement.
+    pub fn is_dirty(&self) -> bool {
+        self.0.is_dirty()
+    }
+
+    /// Marque la page comme propre, une fois son contenu persisté.
+    pub(crate) fn set_clean(&self) {
+        self.0.clear_flags();
+    }
 }
 
 /// Référence mutable vers une page.
@@ -174,6 +197,18 @@ impl<'pager> MutPage<'pager> {
         }
     }
 
+    /// Emprunte la page en écriture, en attendant la libération du verrou.
+    ///
+    /// Sans échéance, l'attente n'est pas bornée.
+    /// Retourne [ErrorKind::LockTimeout] si l'échéance est dépassée.
+    pub(crate) fn new_until(inner: PageDescriptor<'pager>, deadline: Option<Instant>) -> Result<Self> {
+        if inner.acquire_write_lock_until(deadline) {
+            Ok(Self { dry: false, inner })
+        } else {
+            Err(Error::new(ErrorKind::LockTimeout))
+        }
+    }
+
     /// Transforme la référence en référence faible.
     pub fn downgrade(self) -> WeakPage<'pager> {
         WeakPage(self.inner.clone())
@@ -182,7 +217,7 @@ impl<'pager> MutPage<'pager> {
     /// Transforme la référence mutable en référence simple.
     pub fn into_ref(self) -> RefPage<'pager> {
         self.inner.release_write_lock_and_acquire_read_lock();
-        let rf = RefPage(self.inner.clone());
+        let rf = RefPage(unsafe { std::ptr::read(&self.inner) });
         forget(self);
         rf
     }
@@ -232,4 +267,22 @@ impl<'pager> WeakPage<'pager> {
     pub fn upgrade_mut(self) -> MutPage<'pager> {
         MutPage::try_new(self.0).unwrap()
     }
+
+    /// Transforme la référence faible en référence, en attendant la libération
+    /// d'une éventuelle référence mutable.
+    ///
+    /// Sans échéance, l'attente n'est pas bornée.
+    /// Retourne [ErrorKind::LockTimeout] si l'échéance est dépassée.
+    pub fn upgrade_ref_until(self, deadline: Option<Instant>) -> Result<RefPage<'pager>> {
+        RefPage::new_until(self.0, deadline)
+    }
+
+    /// Transforme la référence faible en référence mutable, en attendant la libération
+    /// des références en cours.
+    ///
+    /// Sans échéance, l'attente n'est pas bornée.
+    /// Retourne [ErrorKind::LockTimeout] si l'échéance est dépassée.
+    pub fn upgrade_mut_until(self, deadline: Option<Instant>) -> Result<MutPage<'pager>> {
+        MutPage::new_until(self.0, deadline)
+    }
 }
@@ -108,7 +108,7 @@ impl<'pager> IntoRefPageSlice for RefPage<'pager> {
     fn into_page_slice<Idx: PageSliceIndex>(self, idx: Idx) -> Self::RefPageSlice {
         unsafe {
             let slice = RefPageSlice {
-                inner: self.0.clone(),
+                inner: std::ptr::read(&self.0),
                 slice: &self.0.get_content_ptr().as_ref()[idx],
             };
             forget(self);
@@ -136,7 +136,7 @@ impl IntoRefPageSlice for RefPageSlice<'_> {
 
     fn into_page_slice<Idx: PageSliceIndex>(self, idx: Idx) -> Self::RefPageSlice {
         let slice = Self {
-            inner: self.inner.clone(),
+            inner: unsafe { std::ptr::read(&self.inner) },
             slice: &self.slice[idx],
         };
         forget(self);
@@ -164,7 +164,7 @@ impl<'pager> IntoMutPageSlice for MutPage<'pager> {
     fn into_mut_page_slice<Idx: PageSliceIndex>(self, idx: Idx) -> Self::MutPageSlice {
         unsafe {
             let slice = MutPageSlice {
-                inner: self.inner.clone(),
+                inner: std::ptr::read(&self.inner),
                 slice: &mut self.inner.get_content_ptr().as_mut()[idx],
             };
             forget(self);
@@ -180,7 +180,7 @@ impl IntoMutPageSlice for MutPageSlice<'_> {
             let slice = std::ptr::from_mut(&mut self.slice[idx]);
 
             let slice = MutPageSlice {
-                inner: self.inner.clone(),
+                inner: std::ptr::read(&self.inner),
                 slice: slice.as_mut().unwrap(),
             };
             forget(self);
@@ -255,7 +255,7 @@ impl<'buf> From<MutPageSlice<'buf>> for RefPageSlice<'buf> {
             let slice = std::ptr::from_mut(val.slice).as_ref().unwrap();
 
             let slice = RefPageSlice {
-                inner: val.inner.clone(),
+                inner: std::ptr::read(&val.inner),
                 slice,
             };
 
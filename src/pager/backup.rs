@@ -0,0 +1,589 @@
+//! Sauvegarde à chaud d'un pager.
+//!
+//! Une sauvegarde complète (voir [Pager::backup_to]) peut être complétée par
+//! des sauvegardes incrémentales (voir [Pager::incremental_backup]), qui ne
+//! contiennent que les pages écrites depuis la sauvegarde précédente. Le jar est
+//! reconstruit avec [restore].
+use std::{
+    collections::BTreeSet,
+    io::{self, Read, Seek, SeekFrom, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use zerocopy::{FromBytes, IntoBytes};
+use zerocopy_derive::{FromBytes, Immutable, IntoBytes, KnownLayout};
+
+use crate::{
+    buffer::IBufferPool,
+    error::{Error, ErrorKind},
+    fs::{FileOpenOptions, FilePtr, IFileSystem},
+    page::{AsRefPageSlice, PageId, PageSize, RefPage},
+    result::Result,
+};
+
+use super::{lsn::Lsn, IPager, Pager, PagerCompression, PagerDescription};
+
+/// Témoin inscrit en tête d'une sauvegarde incrémentale
+const DELTA_MAGIC: &[u8; 8] = b"JARNACDL";
+
+/// En-tête d'une sauvegarde incrémentale.
+///
+/// L'en-tête est suivi des pages sauvegardées, chacune précédée de son
+/// identifiant. La page de description est la dernière.
+#[derive(FromBytes, IntoBytes, KnownLayout, Immutable)]
+#[repr(C, packed)]
+struct DeltaHeader {
+    magic: [u8; 8],
+    page_size: PageSize,
+    /// Génération de la sauvegarde précédente
+    since: Lsn,
+    /// Génération de la sauvegarde
+    lsn: Lsn,
+    /// Nombre de pages sauvegardées
+    count: u64,
+}
+
+/// Avancement d'une sauvegarde.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackupProgress {
+    /// Nombre de pages copiées, recopies comprises
+    pub copied: u64,
+    /// Nombre de pages du pager
+    pub total: u64,
+}
+
+/// Suivi des pages modifiées pendant une sauvegarde.
+///
+/// Hors sauvegarde, le signalement d'une écriture ne prend aucun verrou.
+#[derive(Default)]
+pub(super) struct BackupTracker {
+    active: AtomicBool,
+    modified: Mutex<BTreeSet<PageId>>,
+}
+
+impl BackupTracker {
+    fn start(&self) {
+        self.modified.lock().unwrap().clear();
+        self.active.store(true, Ordering::SeqCst);
+    }
+
+    fn stop(&self) {
+        self.active.store(false, Ordering::SeqCst);
+        self.modified.lock().unwrap().clear();
+    }
+
+    /// Signale que la page est susceptible d'être modifiée.
+    pub fn on_write(&self, pid: PageId) {
+        if self.active.load(Ordering::SeqCst) {
+            self.modified.lock().unwrap().insert(pid);
+        }
+    }
+
+    /// Retire les pages modifiées depuis le dernier appel.
+    fn take(&self) -> BTreeSet<PageId> {
+        std::mem::take(&mut *self.modified.lock().unwrap())
+    }
+}
+
+impl Pager<'_> {
+    /// Nombre de pages copiées entre deux rapports d'avancement.
+    pub const BACKUP_STEP: u64 = 64;
+
+    /// Sauvegarde le pager dans un fichier, sans interrompre les écritures.
+    ///
+    /// Les pages sont copiées par lots de [Pager::BACKUP_STEP], l'avancement est
+    /// rapporté après chaque lot. Les pages modifiées entre deux lots sont recopiées
+    /// une fois la copie terminée, puis la page de description en dernier : le
+    /// fichier obtenu est l'image du pager à la fin de la sauvegarde.
+    ///
+    /// Les pages recopiées sont verrouillées en lecture pendant la recopie ; une
+    /// page empruntée en écriture est attendue jusqu'à sa libération. Le suivi des
+    /// écritures s'arrête une fois toutes les pages modifiées verrouillées.
+    ///
+    /// La sauvegarde n'est ni compressée, ni chiffrée, et s'ouvre avec un
+    /// [super::storage::FsPagerStorage] sans option.
+    ///
+    /// Retourne la génération de la sauvegarde (voir [super::lsn]).
+    pub fn backup_to<Fs, F>(&self, dest: FilePtr<Fs>, mut progress: F) -> Result<Lsn>
+    where
+        Fs: IFileSystem,
+        F: FnMut(&BackupProgress),
+    {
+        self.stamp_dirty_pages()?;
+        let lsn = self.close_lsn();
+
+        self.backup.start();
+        let result = self.copy_to(&dest, &mut progress);
+        self.backup.stop();
+        result.map(|_| lsn)
+    }
+
+    /// Sauvegarde les pages écrites depuis la génération *since_lsn*, qui est
+    /// celle d'une sauvegarde précédente.
+    ///
+    /// Retourne la génération de la sauvegarde.
+    pub fn incremental_backup<Fs>(&self, since_lsn: Lsn, dest: FilePtr<Fs>) -> Result<Lsn>
+    where
+        Fs: IFileSystem,
+    {
+        self.stamp_dirty_pages()?;
+        let lsn = self.close_lsn();
+
+        let pids = (0..)
+            .zip(self.page_lsns(self.len())?)
+            .filter(|(pid, page_lsn)| *pid != 0 && *page_lsn > since_lsn)
+            .map(|(pid, _)| pid)
+            .chain(std::iter::once(0))
+            .collect::<Vec<PageId>>();
+
+        let page_size = self.pool.page_size();
+        let header = DeltaHeader {
+            magic: *DELTA_MAGIC,
+            page_size,
+            since: since_lsn,
+            lsn,
+            count: u64::try_from(pids.len()).unwrap(),
+        };
+
+        let mut file = dest.open(FileOpenOptions::new().create(true).write(true))?;
+        file.write_all(header.as_bytes())?;
+
+        let mut page = vec![0u8; usize::from(page_size)];
+
+        for pid in pids {
+            match pid {
+                0 => self.read_description_to(&mut page)?,
+                pid => self.read_page_to(pid, &mut page)?,
+            }
+
+            file.write_all(pid.as_bytes())?;
+            file.write_all(&page)?;
+        }
+
+        file.flush()?;
+        drop(file);
+
+        let len = size_of::<DeltaHeader>() as u64 + header.count * (size_of::<PageId>() as u64 + u64::from(page_size));
+        dest.set_len(len)?;
+
+        Ok(lsn)
+    }
+
+    fn copy_to<Fs, F>(&self, dest: &FilePtr<Fs>, progress: &mut F) -> Result<()>
+    where
+        Fs: IFileSystem,
+        F: FnMut(&BackupProgress),
+    {
+        let page_size = usize::from(self.pool.page_size());
+        let mut file = dest.open(FileOpenOptions::new().create(true).write(true))?;
+        let mut buf = vec![0u8; page_size * usize::try_from(Self::BACKUP_STEP).unwrap()];
+        let mut copied = 0;
+        let mut start = 1;
+
+        // Le pager peut grandir pendant la copie.
+        while start < self.len() {
+            let count = Self::BACKUP_STEP.min(self.len() - start);
+            let chunk = &mut buf[..page_size * usize::try_from(count).unwrap()];
+
+            for (pid, page) in (start..).zip(chunk.chunks_mut(page_size)) {
+                match self.read_page_to(pid, page) {
+                    // La page sera recopiée une fois libérée.
+                    Err(err) if matches!(err.kind, ErrorKind::PageCurrentlyBorrowed) => {
+                        self.backup.on_write(pid)
+                    }
+                    res => res?,
+                }
+            }
+
+            file.seek(SeekFrom::Start(start * u64::try_from(page_size).unwrap()))?;
+            file.write_all(chunk)?;
+
+            start += count;
+            copied += count;
+            progress(&BackupProgress { copied, total: self.len() });
+        }
+
+        // La description et les pages à recopier sont verrouillées en lecture
+        // jusqu'à la fin de la sauvegarde : les écritures en cours sont attendues,
+        // aucune autre ne peut plus les modifier. Les pages modifiées pendant la pose
+        // des verrous le sont à leur tour, jusqu'à ce qu'il n'y en ait plus : le suivi
+        // s'arrête alors, la sauvegarde est l'image du pager à cet instant.
+        let mut latches = vec![self.latch_page(0)?];
+        let mut pids = BTreeSet::default();
+
+        loop {
+            let modified = self.backup.take().into_iter().filter(|pid| !pids.contains(pid)).collect::<Vec<_>>();
+
+            if modified.is_empty() {
+                break;
+            }
+
+            for pid in modified {
+                latches.push(self.latch_page(pid)?);
+                pids.insert(pid);
+            }
+        }
+
+        self.backup.stop();
+
+        let len = self.len();
+        let page = &mut buf[..page_size];
+
+        for pid in pids.into_iter().filter(|pid| (1..len).contains(pid)) {
+            self.read_page_to(pid, page)?;
+            file.seek(SeekFrom::Start(pid * u64::try_from(page_size).unwrap()))?;
+            file.write_all(page)?;
+            copied += 1;
+        }
+
+        self.read_description_to(page)?;
+
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(page)?;
+        file.flush()?;
+        drop(file);
+
+        dest.set_len(len * u64::try_from(page_size).unwrap())?;
+        progress(&BackupProgress { copied: copied + 1, total: len });
+
+        Ok(())
+    }
+
+    /// Lit la page de description, telle qu'elle doit figurer dans une sauvegarde.
+    fn read_description_to(&self, dest: &mut [u8]) -> Result<()> {
+        self.read_page_to(0, dest)?;
+        let description = PagerDescription::mut_from_bytes(&mut dest[..size_of::<PagerDescription>()]).unwrap();
+        description.set_compression(PagerCompression::None);
+        description.frame_table = 0;
+        Ok(())
+    }
+
+    /// Verrouille en lecture une page présente dans le tampon, en attendant
+    /// la fin des écritures en cours.
+    ///
+    /// Une page absente du tampon ne peut être modifiée sans y être chargée.
+    fn latch_page(&self, pid: PageId) -> Result<Option<RefPage<'_>>> {
+        let tag = self.tag().in_page(pid);
+
+        if !self.pool.contains(&tag) {
+            return Ok(None);
+        }
+
+        self.pool.get_ref_until(&tag, None)
+    }
+
+    /// Lit le contenu courant d'une page, sans la charger dans le tampon.
+    fn read_page_to(&self, pid: PageId, dest: &mut [u8]) -> Result<()> {
+        let tag = self.tag().in_page(pid);
+
+        if self.pool.contains(&tag) {
+            let page = self
+                .pool
+                .try_get_ref(&tag)?
+                .ok_or_else(|| Error::new(ErrorKind::UnexistingPage(tag)))?;
+            dest.copy_from_slice(page.as_bytes());
+            return Ok(());
+        }
+
+        self.storage
+            .as_ref()
+            .ok_or_else(|| Error::new(ErrorKind::UnexistingPage(tag)))?
+            .read_page(pid, dest)
+    }
+}
+
+/// Reconstruit un jar à partir d'une sauvegarde complète, et des sauvegardes
+/// incrémentales qui l'ont suivie, dans l'ordre.
+///
+/// Retourne [ErrorKind::InvalidFormat] si une sauvegarde incrémentale manque.
+pub fn restore<Fs>(dest: FilePtr<Fs>, base: &FilePtr<Fs>, deltas: &[FilePtr<Fs>]) -> Result<()>
+where
+    Fs: IFileSystem,
+{
+    let mut src = base.open(FileOpenOptions::new().read(true))?;
+    let mut description = [0u8; size_of::<PagerDescription>()];
+    src.read_exact(&mut description)?;
+    let description = PagerDescription::read_from_bytes(&description).unwrap();
+
+    let page_size = description.page_size;
+    let mut page_count = description.page_count;
+    // La description est copiée après la clôture de la génération sauvegardée.
+    let mut lsn = description.lsn().saturating_sub(1);
+
+    let mut file = dest.open(FileOpenOptions::new().create(true).write(true))?;
+    src.seek(SeekFrom::Start(0))?;
+    io::copy(&mut src, &mut file)?;
+    drop(src);
+
+    let mut page = vec![0u8; usize::from(page_size)];
+
+    for delta in deltas {
+        let mut src = delta.open(FileOpenOptions::new().read(true))?;
+        let mut header = [0u8; size_of::<DeltaHeader>()];
+        src.read_exact(&mut header)?;
+        let header = DeltaHeader::read_from_bytes(&header).unwrap();
+
+        if header.magic != *DELTA_MAGIC || header.page_size != page_size || header.since > lsn {
+            return Err(Error::new(ErrorKind::InvalidFormat));
+        }
+
+        for _ in 0..header.count {
+            let mut pid = [0u8; size_of::<PageId>()];
+            src.read_exact(&mut pid)?;
+            let pid = PageId::read_from_bytes(&pid).unwrap();
+            src.read_exact(&mut page)?;
+
+            if pid == 0 {
+                page_count = PagerDescription::ref_from_bytes(&page[..size_of::<PagerDescription>()])
+                    .unwrap()
+                    .page_count;
+            }
+
+            file.seek(SeekFrom::Start(pid * u64::from(page_size)))?;
+            file.write_all(&page)?;
+        }
+
+        lsn = header.lsn;
+    }
+
+    file.flush()?;
+    drop(file);
+
+    dest.set_len(page_count * u64::from(page_size))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Seek, SeekFrom},
+        rc::Rc,
+        sync::mpsc,
+        thread,
+        time::Duration,
+    };
+
+    use crate::{
+        arena::IArena,
+        buffer::{stress::stubs::StressStub, BufferPool, IBufferPool},
+        error::ErrorKind,
+        fs::{in_memory::InMemoryFs, FileOpenOptions, FilePtr, IFileSystem},
+        page::{AsMutPageSlice, AsRefPageSlice},
+        pager::{
+            storage::{FsPagerStorage, IPagerStorage},
+            IPager, Pager, PagerCompression,
+        },
+    };
+
+    use super::restore;
+
+    #[test]
+    fn test_backup_during_writes() {
+        let fs = Rc::new(InMemoryFs::default());
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let storage = FsPagerStorage::new(fs.clone(), "jar", 4096)
+            .with_compression(PagerCompression::Rle)
+            .into_boxed();
+        let pager = Pager::open(0, &buf_pool, storage).unwrap();
+
+        for _ in 1..100 {
+            let mut page = pager.new_element().unwrap();
+            let pid = page.tag().page_id;
+            page.as_mut_bytes()[0..8].copy_from_slice(&pid.to_le_bytes());
+        }
+        pager.flush().unwrap();
+
+        // Des écritures ont lieu entre deux lots : une page déjà copiée est
+        // modifiée, et une nouvelle page est ajoutée.
+        let mut reports = Vec::default();
+        pager
+            .backup_to(FilePtr::new(fs.clone(), "backup"), |progress| {
+                if reports.is_empty() {
+                    let mut page = pager.borrow_mut_element(&pager.tag().in_page(3)).unwrap();
+                    page.as_mut_bytes()[0..8].copy_from_slice(&42u64.to_le_bytes());
+                    let mut page = pager.new_element().unwrap();
+                    let pid = page.tag().page_id;
+                    page.as_mut_bytes()[0..8].copy_from_slice(&pid.to_le_bytes());
+                }
+                reports.push(*progress);
+            })
+            .unwrap();
+
+        // La page 100 contient la table des générations.
+        assert_eq!(reports.len(), 3);
+        assert_eq!(reports[0].copied, Pager::BACKUP_STEP);
+        assert_eq!(reports[2].total, 102);
+
+        let storage = FsPagerStorage::new(fs.clone(), "backup", 4096);
+        assert_eq!(storage.len().unwrap(), 102);
+
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let backup = Pager::open(0, &buf_pool, storage.into_boxed()).unwrap();
+        assert_eq!(backup.len(), 102);
+        assert_eq!(backup.compression().unwrap(), PagerCompression::None);
+
+        for pid in (1..102u64).filter(|pid| *pid != 100) {
+            let expected = match pid {
+                3 => 42,
+                pid => pid,
+            };
+            let page = backup.borrow_element(&backup.tag().in_page(pid)).unwrap();
+            assert_eq!(page.as_bytes()[0..8], expected.to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn test_backup_with_concurrent_writer() {
+        let fs = Rc::new(InMemoryFs::default());
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let storage = FsPagerStorage::new(fs.clone(), "jar", 4096).into_boxed();
+        let pager = Pager::open(0, &buf_pool, storage).unwrap();
+
+        for _ in 1..100 {
+            let mut page = pager.new_element().unwrap();
+            let pid = page.tag().page_id;
+            page.as_mut_bytes()[0..8].copy_from_slice(&pid.to_le_bytes());
+        }
+        pager.flush().unwrap();
+
+        let tag = pager.tag().in_page(3);
+        let buf_pool = &buf_pool;
+
+        thread::scope(|scope| {
+            // Un autre fil emprunte la page 3 en écriture, et ne la modifie
+            // qu'une fois la copie des pages terminée.
+            let (borrowed, on_borrowed) = mpsc::channel();
+            let writer = scope.spawn(move || {
+                let mut page = buf_pool.get_mut_until(&tag, None).unwrap().unwrap();
+                borrowed.send(()).unwrap();
+                thread::sleep(Duration::from_millis(100));
+                page.as_mut_bytes()[0..8].copy_from_slice(&42u64.to_le_bytes());
+            });
+
+            on_borrowed.recv().unwrap();
+            pager.backup_to(FilePtr::new(fs.clone(), "backup"), |_| {}).unwrap();
+            writer.join().unwrap();
+        });
+
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let storage = FsPagerStorage::new(fs.clone(), "backup", 4096).into_boxed();
+        let backup = Pager::open(0, &buf_pool, storage).unwrap();
+
+        let page = backup.borrow_element(&backup.tag().in_page(3)).unwrap();
+        assert_eq!(page.as_bytes()[0..8], 42u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_backup_with_concurrent_writes_on_two_pages() {
+        let fs = Rc::new(InMemoryFs::default());
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let storage = FsPagerStorage::new(fs.clone(), "jar", 4096).into_boxed();
+        let pager = Pager::open(0, &buf_pool, storage).unwrap();
+
+        for _ in 1..100 {
+            let mut page = pager.new_element().unwrap();
+            let pid = page.tag().page_id;
+            page.as_mut_bytes()[0..8].copy_from_slice(&pid.to_le_bytes());
+        }
+        pager.flush().unwrap();
+
+        let tags = [pager.tag().in_page(3), pager.tag().in_page(70)];
+        let buf_pool = &buf_pool;
+
+        thread::scope(|scope| {
+            // Un autre fil emprunte les pages 3 et 70 en écriture, modifie la page 3,
+            // puis la page 70 une fois la page 3 libérée.
+            let (borrowed, on_borrowed) = mpsc::channel();
+            let writer = scope.spawn(move || {
+                let [mut first, mut second] = tags.map(|tag| buf_pool.get_mut_until(&tag, None).unwrap().unwrap());
+                borrowed.send(()).unwrap();
+
+                thread::sleep(Duration::from_millis(50));
+                first.as_mut_bytes()[0..8].copy_from_slice(&42u64.to_le_bytes());
+                drop(first);
+
+                thread::sleep(Duration::from_millis(50));
+                second.as_mut_bytes()[0..8].copy_from_slice(&43u64.to_le_bytes());
+            });
+
+            on_borrowed.recv().unwrap();
+            pager.backup_to(FilePtr::new(fs.clone(), "backup"), |_| {}).unwrap();
+            writer.join().unwrap();
+        });
+
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let storage = FsPagerStorage::new(fs.clone(), "backup", 4096).into_boxed();
+        let backup = Pager::open(0, &buf_pool, storage).unwrap();
+
+        for (pid, expected) in [(3, 42u64), (70, 43u64)] {
+            let page = backup.borrow_element(&backup.tag().in_page(pid)).unwrap();
+            assert_eq!(page.as_bytes()[0..8], expected.to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn test_incremental_backup() {
+        let fs = Rc::new(InMemoryFs::default());
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let storage = FsPagerStorage::new(fs.clone(), "jar", 4096).into_boxed();
+        let pager = Pager::open(0, &buf_pool, storage).unwrap();
+
+        let write = |pid: u64, value: u64| {
+            pager.borrow_mut_element(&pager.tag().in_page(pid)).unwrap().as_mut_bytes()[0..8]
+                .copy_from_slice(&value.to_le_bytes());
+        };
+
+        for _ in 1..10 {
+            let mut page = pager.new_element().unwrap();
+            let pid = page.tag().page_id;
+            page.as_mut_bytes()[0..8].copy_from_slice(&pid.to_le_bytes());
+        }
+        pager.flush().unwrap();
+
+        let base = pager.backup_to(FilePtr::new(fs.clone(), "base"), |_| {}).unwrap();
+
+        write(2, 20);
+        pager.new_element().unwrap();
+        write(11, 110);
+        pager.flush().unwrap();
+        assert_eq!(pager.page_lsn(2).unwrap(), base + 1);
+        assert_eq!(pager.page_lsn(3).unwrap(), base);
+
+        // Seules les pages 2 et 11, la table des générations et la description sont sauvegardées.
+        let first = pager.incremental_backup(base, FilePtr::new(fs.clone(), "delta-1")).unwrap();
+        let size = fs
+            .open(&"delta-1".into(), FileOpenOptions::new().read(true))
+            .unwrap()
+            .seek(SeekFrom::End(0))
+            .unwrap();
+        assert!(size < 5 * 4096);
+
+        // Les pages modifiées mais pas encore écrites sont sauvegardées.
+        write(5, 50);
+        pager.incremental_backup(first, FilePtr::new(fs.clone(), "delta-2")).unwrap();
+
+        let deltas = [FilePtr::new(fs.clone(), "delta-1"), FilePtr::new(fs.clone(), "delta-2")];
+        restore(FilePtr::new(fs.clone(), "restored"), &FilePtr::new(fs.clone(), "base"), &deltas).unwrap();
+
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let storage = FsPagerStorage::new(fs.clone(), "restored", 4096).into_boxed();
+        let restored = Pager::open(0, &buf_pool, storage).unwrap();
+        assert_eq!(restored.len(), pager.len());
+
+        for pid in [1, 2, 5, 11] {
+            let expected = pager.borrow_element(&pager.tag().in_page(pid)).unwrap();
+            let page = restored.borrow_element(&restored.tag().in_page(pid)).unwrap();
+            assert_eq!(page.as_bytes(), expected.as_bytes());
+        }
+
+        // Une sauvegarde incrémentale manquante est détectée.
+        let err = restore(FilePtr::new(fs.clone(), "broken"), &FilePtr::new(fs.clone(), "base"), &deltas[1..])
+            .err()
+            .unwrap();
+        assert!(matches!(err.kind, ErrorKind::InvalidFormat));
+    }
+}
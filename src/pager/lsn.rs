@@ -0,0 +1,238 @@
+//! Générations des pages.
+//!
+//! Chaque écriture d'une page modifiée dans le stockage lui attribue la génération
+//! courante du pager, son LSN. La génération avance à chaque sauvegarde, ce qui
+//! permet de retrouver les pages modifiées depuis (voir [Pager::incremental_backup]).
+//!
+//! Les générations sont stockées dans une table répartie sur des pages chaînées,
+//! la n-ième page de la table couvrant les pages *[n × capacité, (n + 1) × capacité[*.
+use std::collections::BTreeSet;
+
+use zerocopy::{FromBytes, IntoBytes, TryFromBytes};
+use zerocopy_derive::*;
+
+use crate::{
+    arena::IArena,
+    buffer::IBufferPool,
+    error::ErrorKind,
+    page::{AsMutPageSlice, AsRefPageSlice, InPage, OptionalPageId, PageId, PageKind},
+    result::Result,
+    tag::DataArea,
+};
+
+use super::{IPager, Pager};
+
+/// Génération d'une page
+pub type Lsn = u64;
+
+/// Une page de la table des générations.
+pub struct LsnPage<Page>(Page)
+where
+    Page: AsRefPageSlice;
+
+impl<Page> LsnPage<Page>
+where
+    Page: AsRefPageSlice,
+{
+    /// Initialise une page de la table, sans génération.
+    pub fn new(mut page: Page) -> Self
+    where
+        Page: AsMutPageSlice,
+    {
+        page.as_mut().fill(0);
+        page.as_mut().as_mut_bytes()[0] = PageKind::Lsn as u8;
+        Self(page)
+    }
+
+    pub fn try_from(page: Page) -> Result<Self> {
+        let kind: PageKind = page.as_ref().as_bytes()[0].try_into()?;
+        PageKind::Lsn.assert(kind).map(|_| Self(page))
+    }
+
+    /// Page suivante de la table
+    pub fn get_next(&self) -> Option<PageId> {
+        self.as_meta().next.into()
+    }
+
+    /// Nombre de générations stockées dans la page.
+    pub fn capacity(&self) -> usize {
+        capacity_of(self.0.as_bytes().len())
+    }
+
+    pub fn get(&self, idx: usize) -> Lsn {
+        Lsn::read_from_bytes(&self.0.as_bytes()[Self::entry_range(idx)]).unwrap()
+    }
+
+    fn entry_range(idx: usize) -> std::ops::Range<usize> {
+        let start = LsnPageMeta::AREA.end + idx * size_of::<Lsn>();
+        start..(start + size_of::<Lsn>())
+    }
+
+    fn as_meta(&self) -> &LsnPageMeta {
+        LsnPageMeta::try_ref_from_bytes(&self.0.as_bytes()[LsnPageMeta::AREA]).unwrap()
+    }
+}
+
+impl<Page> LsnPage<Page>
+where
+    Page: AsMutPageSlice,
+{
+    pub fn set_next(&mut self, next: Option<PageId>) {
+        self.as_mut_meta().next = next.into();
+    }
+
+    pub fn set(&mut self, idx: usize, lsn: Lsn) {
+        self.0.as_mut_bytes()[Self::entry_range(idx)].copy_from_slice(lsn.as_bytes());
+    }
+
+    fn as_mut_meta(&mut self) -> &mut LsnPageMeta {
+        LsnPageMeta::try_mut_from_bytes(&mut self.0.as_mut_bytes()[LsnPageMeta::AREA]).unwrap()
+    }
+}
+
+#[derive(FromBytes, IntoBytes, KnownLayout, Immutable)]
+#[repr(C, packed)]
+/// Représente les données stockées dans une page de la table des générations.
+pub struct LsnPageMeta {
+    /// Page suivante de la table
+    next: OptionalPageId,
+}
+
+impl DataArea for LsnPageMeta {
+    const AREA: std::ops::Range<usize> = InPage::<Self>::AREA;
+}
+
+/// Nombre de générations stockées dans une page de la table.
+fn capacity_of(page_size: usize) -> usize {
+    (page_size - LsnPageMeta::AREA.end) / size_of::<Lsn>()
+}
+
+impl Pager<'_> {
+    /// Génération courante, attribuée aux pages écrites jusqu'à la prochaine sauvegarde.
+    pub fn lsn(&self) -> Lsn {
+        self.get_descriptor().as_description().lsn()
+    }
+
+    /// Génération de la dernière écriture de la page, 0 si elle n'a jamais été écrite.
+    pub fn page_lsn(&self, pid: PageId) -> Result<Lsn> {
+        Ok(self.page_lsns(pid + 1)?[usize::try_from(pid).unwrap()])
+    }
+
+    /// Passe à la génération suivante, et retourne la génération close.
+    pub(super) fn close_lsn(&self) -> Lsn {
+        let mut desc = self.get_mut_descriptor();
+        let lsn = desc.as_description().lsn();
+        desc.as_mut_description().set_lsn(lsn + 1);
+        lsn
+    }
+
+    /// Générations des *len* premières pages.
+    pub(super) fn page_lsns(&self, len: u64) -> Result<Vec<Lsn>> {
+        let len = usize::try_from(len).unwrap();
+        let mut lsns = Vec::with_capacity(len);
+        let mut current = self.get_descriptor().as_description().get_lsn_head();
+
+        while let Some(pid) = current.filter(|_| lsns.len() < len) {
+            let page = self
+                .borrow_element(&self.tag().in_page(pid))
+                .and_then(LsnPage::try_from)?;
+            let count = page.capacity().min(len - lsns.len());
+            lsns.extend((0..count).map(|idx| page.get(idx)));
+            current = page.get_next();
+        }
+
+        lsns.resize(len, 0);
+        Ok(lsns)
+    }
+
+    /// Attribue la génération courante aux pages modifiées du tampon.
+    ///
+    /// La mise à jour de la table modifie à son tour des pages, jusqu'à ce que
+    /// toutes les pages modifiées aient reçu leur génération.
+    pub(super) fn stamp_dirty_pages(&self) -> Result<()> {
+        let lsn = self.lsn();
+        let mut stamped = BTreeSet::default();
+
+        loop {
+            let mut dirty = Vec::default();
+
+            for tag in self.pool.jar_tags(self.id) {
+                if tag.page_id == 0 || stamped.contains(&tag.page_id) {
+                    continue;
+                }
+
+                match self.pool.try_get_ref(&tag) {
+                    // Une page en cours d'écriture sera estampillée à un prochain passage.
+                    Err(err) if matches!(err.kind, ErrorKind::PageCurrentlyBorrowed) => {}
+                    page => {
+                        if page?.is_some_and(|page| page.is_dirty()) {
+                            dirty.push(tag.page_id);
+                        }
+                    }
+                }
+            }
+
+            if dirty.is_empty() {
+                return Ok(());
+            }
+
+            dirty.sort_unstable();
+            self.set_page_lsns(&dirty, lsn)?;
+            stamped.extend(dirty);
+        }
+    }
+
+    /// Attribue la génération aux pages, triées par ordre croissant.
+    fn set_page_lsns(&self, pids: &[PageId], lsn: Lsn) -> Result<()> {
+        let capacity = u64::try_from(capacity_of(self.size_of())).unwrap();
+        let mut index = 0;
+        let mut prev = None;
+        let mut current = self.get_descriptor().as_description().get_lsn_head();
+
+        for &pid in pids {
+            let target = pid / capacity;
+
+            let table_pid = loop {
+                let table_pid = match current {
+                    Some(table_pid) => table_pid,
+                    None => self.append_lsn_page(prev)?,
+                };
+                current = Some(table_pid);
+
+                if index == target {
+                    break table_pid;
+                }
+
+                prev = current;
+                current = self
+                    .borrow_element(&self.tag().in_page(table_pid))
+                    .and_then(LsnPage::try_from)?
+                    .get_next();
+                index += 1;
+            };
+
+            self.borrow_mut_element(&self.tag().in_page(table_pid))
+                .and_then(LsnPage::try_from)?
+                .set(usize::try_from(pid % capacity).unwrap(), lsn);
+        }
+
+        Ok(())
+    }
+
+    /// Ajoute une page en fin de table.
+    fn append_lsn_page(&self, prev: Option<PageId>) -> Result<PageId> {
+        let page = self.new_element()?;
+        let pid = page.tag().page_id;
+        LsnPage::new(page);
+
+        match prev {
+            Some(prev) => self
+                .borrow_mut_element(&self.tag().in_page(prev))
+                .and_then(LsnPage::try_from)?
+                .set_next(Some(pid)),
+            None => self.get_mut_descriptor().as_mut_description().set_lsn_head(Some(pid)),
+        }
+
+        Ok(pid)
+    }
+}
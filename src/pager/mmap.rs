@@ -0,0 +1,317 @@
+//! Pager en lecture seule adossé à une projection en mémoire du fichier d'un jar.
+//!
+//! Les pages empruntées pointent directement dans la projection, sans copie
+//! dans le tampon de pages ; adapté aux jars ouverts en lecture et parcourus.
+use std::{
+    cell::UnsafeCell,
+    fs::File,
+    os::fd::AsRawFd,
+    path::Path,
+    pin::Pin,
+    ptr::NonNull,
+    time::Instant,
+};
+
+use dashmap::DashMap;
+use zerocopy::FromBytes;
+
+use crate::{
+    arena::IArena,
+    error::{Error, ErrorKind},
+    page::{
+        descriptor::{PageDescriptor, PageDescriptorInner},
+        MutPage, PageId, PageSize, PageSlice, RefPage,
+    },
+    result::Result,
+    tag::{JarId, JarTag},
+};
+
+use super::{IPager, PagerCompression, PagerDescription};
+
+/// Projection en mémoire du fichier d'un jar.
+///
+/// Les pages empruntées par un [MmapPager] pointent dans la projection, qui doit leur survivre :
+/// le pager l'emprunte, comme [super::Pager] emprunte le tampon de pages.
+///
+/// ```no_run
+/// use jarnac::{arena::IArena, pager::{mmap::{JarMmap, MmapPager}, IPager}};
+///
+/// let mmap = JarMmap::open("jar").unwrap();
+/// let pager = MmapPager::new(0, &mmap);
+/// let page = pager.borrow_element(&pager.tag().in_page(1)).unwrap();
+/// drop(page);
+/// drop(mmap);
+/// ```
+///
+/// Une page ne peut survivre à la projection :
+///
+/// ```compile_fail
+/// use jarnac::{arena::IArena, pager::{mmap::{JarMmap, MmapPager}, IPager}};
+///
+/// let mmap = JarMmap::open("jar").unwrap();
+/// let pager = MmapPager::new(0, &mmap);
+/// let page = pager.borrow_element(&pager.tag().in_page(1)).unwrap();
+/// drop(mmap);
+/// drop(page);
+/// ```
+pub struct JarMmap {
+    /// Début de la projection
+    ptr: NonNull<u8>,
+    /// Taille de la projection
+    size: usize,
+    page_size: PageSize,
+    page_count: u64,
+    /// Page de zéros, pour les pages au-delà de la fin du fichier
+    zeroes: Box<[u8]>,
+    descriptors: DashMap<JarTag, Pin<Box<UnsafeCell<PageDescriptorInner>>>>,
+}
+
+unsafe impl Send for JarMmap {}
+unsafe impl Sync for JarMmap {}
+
+impl JarMmap {
+    /// Ouvre le fichier du jar et le projette en mémoire.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path)?;
+        let size = usize::try_from(file.metadata()?.len()).unwrap();
+
+        if size < size_of::<PagerDescription>() {
+            return Err(Error::new(ErrorKind::InvalidFormat));
+        }
+
+        let ptr = unsafe {
+            let ptr = libc::mmap(
+                std::ptr::null_mut(),
+                size,
+                libc::PROT_READ,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            );
+
+            if ptr == libc::MAP_FAILED {
+                return Err(std::io::Error::last_os_error().into());
+            }
+
+            libc::madvise(ptr, size, libc::MADV_SEQUENTIAL);
+            NonNull::new(ptr.cast::<u8>()).unwrap()
+        };
+
+        let mut mmap = Self {
+            ptr,
+            size,
+            page_size: 0,
+            page_count: 0,
+            zeroes: Box::default(),
+            descriptors: Default::default(),
+        };
+
+        let (page_size, page_count, compression) = PagerDescription::ref_from_bytes(
+            &mmap.as_bytes()[0..size_of::<PagerDescription>()],
+        )
+        .map(|desc| (desc.page_size, desc.len(), desc.compression()))
+        .unwrap();
+
+        // Les pages compressées ne peuvent être projetées telles quelles.
+        if compression? != PagerCompression::None {
+            return Err(Error::new(ErrorKind::InvalidFormat));
+        }
+
+        mmap.page_size = page_size;
+        mmap.page_count = page_count;
+        mmap.zeroes = vec![0; usize::from(mmap.page_size)].into_boxed_slice();
+
+        if mmap.page_size == 0 {
+            return Err(Error::new(ErrorKind::InvalidFormat));
+        }
+
+        Ok(mmap)
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.size) }
+    }
+
+    /// Retourne le contenu de la page dans la projection.
+    fn page_content(&self, pid: PageId) -> NonNull<PageSlice> {
+        let page_size = usize::from(self.page_size);
+        let start = usize::try_from(pid).unwrap() * page_size;
+
+        let content: &[u8] = if start + page_size <= self.size {
+            &self.as_bytes()[start..start + page_size]
+        } else {
+            &self.zeroes
+        };
+
+        NonNull::from(<&PageSlice>::from(content))
+    }
+
+    /// Les descripteurs vivent aussi longtemps que la projection, et donc que l'emprunt *'buf*.
+    fn get_page_descriptor<'buf>(&'buf self, tag: &JarTag) -> Result<PageDescriptor<'buf>> {
+        if tag.page_id >= self.page_count {
+            return Err(Error::new(ErrorKind::UnexistingPage(*tag)));
+        }
+
+        let desc = self.descriptors.entry(*tag).or_insert_with(|| {
+            let buf_id = usize::try_from(tag.page_id).unwrap();
+            let inner = PageDescriptorInner::new(buf_id, *tag, self.page_content(tag.page_id));
+            Box::pin(UnsafeCell::new(inner))
+        });
+
+        unsafe { Ok(PageDescriptor::from_raw_ptr(NonNull::new(desc.get()).unwrap())) }
+    }
+}
+
+impl Drop for JarMmap {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr.as_ptr().cast(), self.size);
+        }
+    }
+}
+
+/// Pager en lecture seule sur la projection en mémoire du fichier d'un jar.
+///
+/// Toute opération d'écriture retourne l'erreur [ErrorKind::ReadOnly].
+pub struct MmapPager<'buf> {
+    id: JarId,
+    mmap: &'buf JarMmap,
+}
+
+impl<'buf> MmapPager<'buf> {
+    pub fn new(id: JarId, mmap: &'buf JarMmap) -> Self {
+        Self { id, mmap }
+    }
+
+    fn get_page_descriptor(&self, tag: &JarTag) -> Result<PageDescriptor<'buf>> {
+        if tag.jar_id != self.id {
+            return Err(Error::new(ErrorKind::UnexistingPage(*tag)));
+        }
+
+        self.mmap.get_page_descriptor(tag)
+    }
+}
+
+impl<'buf> IPager<'buf> for MmapPager<'buf> {
+    fn tag(&self) -> JarTag {
+        JarTag::in_jar(self.id)
+    }
+
+    fn len(&self) -> u64 {
+        self.mmap.page_count
+    }
+
+    /// Indique au noyau que les pages vont être prochainement lues.
+    fn prefetch(&self, tags: &[JarTag]) -> Result<()> {
+        let page_size = usize::from(self.mmap.page_size);
+
+        for tag in tags.iter().filter(|tag| tag.jar_id == self.id) {
+            let start = usize::try_from(tag.page_id).unwrap() * page_size;
+
+            if start + page_size <= self.mmap.size {
+                unsafe {
+                    libc::madvise(
+                        self.mmap.ptr.as_ptr().add(start).cast(),
+                        page_size,
+                        libc::MADV_WILLNEED,
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'buf> IArena for MmapPager<'buf> {
+    type Ref = RefPage<'buf>;
+    type RefMut = MutPage<'buf>;
+
+    fn new_element(&self) -> Result<Self::RefMut> {
+        Err(Error::new(ErrorKind::ReadOnly))
+    }
+
+    fn delete_element(&self, _tag: &JarTag) -> Result<()> {
+        Err(Error::new(ErrorKind::ReadOnly))
+    }
+
+    fn try_borrow_element(&self, tag: &JarTag) -> Result<Option<Self::Ref>> {
+        self.get_page_descriptor(tag).and_then(RefPage::try_new).map(Some)
+    }
+
+    fn try_borrow_mut_element(&self, _tag: &JarTag) -> Result<Option<Self::RefMut>> {
+        Err(Error::new(ErrorKind::ReadOnly))
+    }
+
+    fn borrow_element_until(&self, tag: &JarTag, deadline: Option<Instant>) -> Result<Self::Ref> {
+        self.get_page_descriptor(tag)
+            .and_then(|desc| RefPage::new_until(desc, deadline))
+    }
+
+    fn borrow_mut_element_until(&self, _tag: &JarTag, _deadline: Option<Instant>) -> Result<Self::RefMut> {
+        Err(Error::new(ErrorKind::ReadOnly))
+    }
+
+    fn size_of(&self) -> usize {
+        usize::from(self.mmap.page_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Read, rc::Rc};
+
+    use crate::{
+        arena::IArena,
+        buffer::{stress::stubs::StressStub, BufferPool},
+        error::ErrorKind,
+        fs::{in_memory::InMemoryFs, FileOpenOptions, IFileSystem},
+        page::{AsMutPageSlice, AsRefPageSlice},
+        pager::{storage::FsPagerStorage, IPager, Pager},
+    };
+
+    use super::{JarMmap, MmapPager};
+
+    #[test]
+    fn test_mmap_pager() {
+        let fs = Rc::new(InMemoryFs::default());
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let storage = FsPagerStorage::new(fs.clone(), "jar", 4096).into_boxed();
+        let pager = Pager::open(0, &buf_pool, storage).unwrap();
+
+        for _ in 1..5 {
+            let mut page = pager.new_element().unwrap();
+            let pid = page.tag().page_id;
+            page.as_mut_bytes()[0..8].copy_from_slice(&pid.to_le_bytes());
+        }
+        // Page jamais écrite, au-delà de la fin du fichier.
+        pager.new_element().unwrap();
+        pager.flush().unwrap();
+
+        let mut bytes = Vec::default();
+        fs.open(&"jar".into(), FileOpenOptions::new().read(true))
+            .unwrap()
+            .read_to_end(&mut bytes)
+            .unwrap();
+
+        let path = std::env::temp_dir().join(format!("jarnac-mmap-{}.jar", std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+
+        let mapping = JarMmap::open(&path).unwrap();
+        let mmap = MmapPager::new(0, &mapping);
+        // La table des générations occupe une page supplémentaire.
+        assert_eq!(mmap.len(), 7);
+
+        let page = mmap.borrow_element(&mmap.tag().in_page(3)).unwrap();
+        assert_eq!(page.as_bytes()[0..8], 3u64.to_le_bytes());
+        assert!(mmap.borrow_element(&mmap.tag().in_page(5)).unwrap().iter().all(|b| *b == 0));
+
+        let err = mmap.borrow_mut_element(&mmap.tag().in_page(3)).err().unwrap();
+        assert!(matches!(err.kind, ErrorKind::ReadOnly));
+        assert!(matches!(mmap.new_element().err().unwrap().kind, ErrorKind::ReadOnly));
+
+        drop(page);
+        drop(mapping);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,10 +1,24 @@
+pub mod backup;
+pub mod lsn;
+pub mod storage;
+#[cfg(unix)]
+pub mod mmap;
+mod read_ahead;
+mod vacuum;
+
 use std::mem::MaybeUninit;
+use std::time::Instant;
 
+use backup::BackupTracker;
+use itertools::Itertools;
+use read_ahead::ReadAhead;
+use storage::PagerStorage;
 use zerocopy::FromBytes;
 use zerocopy_derive::{FromBytes, Immutable, IntoBytes, KnownLayout};
 
 use crate::arena::IArena;
 use crate::buffer::{BufferPool, IBufferPool};
+use crate::error::{Error, ErrorKind};
 use crate::free::{pop_free_page, push_free_page};
 use crate::page::{AsMutPageSlice, AsRefPageSlice, MutPage, OptionalPageId, PageId, PageSize, RefPage};
 use crate::result::Result;
@@ -21,25 +35,109 @@ pub trait IPager<'pager>: IArena<Ref = RefPage<'pager>, RefMut = MutPage<'pager>
     fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Indique que les pages vont être prochainement empruntées.
+    ///
+    /// Le pager peut en profiter pour les charger à l'avance, par lots de
+    /// pages contiguës. L'indication peut être ignorée.
+    fn prefetch(&self, _tags: &[JarTag]) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// Interface permettant de manipuler un pager
 pub struct Pager<'buf> {
     pool: &'buf BufferPool,
-    id: JarId
+    id: JarId,
+    /// Stockage persistant des pages
+    storage: Option<PagerStorage>,
+    /// Lecture anticipée des pages lors des parcours séquentiels
+    read_ahead: ReadAhead,
+    /// Suivi des pages modifiées pendant une sauvegarde
+    backup: BackupTracker,
 }
 
 impl<'buf> Pager<'buf> {
     /// Créé un nouveau pager
     pub fn new(id: JarId, pool: &'buf BufferPool) -> Result<Self> {
-        let pager = Self {id, pool};
-        
-        let mut desc = pool.alloc(&JarTag::in_jar(id).in_page(0)).map(PagerDescriptor)?;
-        desc.new(pool.page_size());
+        let pager = Self {id, pool, storage: None, read_ahead: Default::default(), backup: Default::default()};
+        pager.init_descriptor()?;
+        Ok(pager)
+    }
+
+    /// Ouvre un pager adossé à un stockage persistant.
+    ///
+    /// Si le stockage est vide, un nouveau pager y est créé.
+    pub fn open(id: JarId, pool: &'buf BufferPool, storage: PagerStorage) -> Result<Self> {
+        if storage.page_size() != pool.page_size() {
+            return Err(Error::new(ErrorKind::InvalidFormat));
+        }
+
+        let is_empty = storage.is_empty()?;
+        let compression = storage.compression()?;
+        let pager = Self {id, pool, storage: Some(storage), read_ahead: Default::default(), backup: Default::default()};
+
+        if is_empty {
+            pager.init_descriptor()?;
+            pager.get_mut_descriptor().as_mut_description().set_compression(compression);
+        } else {
+            pager.load_pages(0, 1)?;
+
+            if pager.get_descriptor().as_description().page_size != pool.page_size() {
+                return Err(Error::new(ErrorKind::InvalidFormat));
+            }
+        }
 
         Ok(pager)
     }
 
+    /// Nombre de pages libres, en attente de réutilisation.
+    pub fn free_count(&self) -> u64 {
+        self.get_descriptor().as_description().free_count()
+    }
+
+    /// Mode de compression des pages du jar.
+    pub fn compression(&self) -> Result<PagerCompression> {
+        self.get_descriptor().as_description().compression()
+    }
+
+    /// Écrit les pages modifiées dans le stockage.
+    ///
+    /// Les pages écrites reçoivent la génération courante (voir [lsn]), la page
+    /// de description est écrite en dernier.
+    pub fn flush(&self) -> Result<()> {
+        let Some(storage) = self.storage.as_ref() else {
+            return Ok(());
+        };
+
+        self.stamp_dirty_pages()?;
+
+        let tags = self
+            .pool
+            .jar_tags(self.id)
+            .into_iter()
+            .sorted_by_key(|tag| (tag.page_id == 0, tag.page_id));
+
+        for tag in tags {
+            if let Some(page) = self.pool.try_get_ref(&tag)? {
+                if page.is_dirty() {
+                    storage.write_page(tag.page_id, page.as_bytes())?;
+                    page.set_clean();
+                }
+            }
+        }
+
+        storage.sync()
+    }
+
+    fn init_descriptor(&self) -> Result<()> {
+        let mut desc = self.pool.alloc(&self.tag().in_page(0)).map(PagerDescriptor)?;
+        desc.new(self.pool.page_size());
+        // La page 0 contient la description du pager.
+        desc.as_mut_description().inc_len();
+        Ok(())
+    }
+
     fn get_descriptor(&self) -> PagerDescriptor<RefPage<'buf>> {
         self.borrow_element(&self.tag().in_page(0))
         .map(PagerDescriptor)
@@ -52,8 +150,73 @@ impl<'buf> Pager<'buf> {
         .unwrap()
     }
 
-    fn load_page(&self, _tag: &JarTag) -> Result<()> {
-        todo!("implement page loading")
+    /// Charge une page depuis le stockage.
+    ///
+    /// Si les défauts de page sont séquentiels, les pages suivantes sont
+    /// chargées dans la même lecture (voir [ReadAhead]).
+    fn load_page(&self, tag: &JarTag) -> Result<()> {
+        let pid = tag.page_id;
+
+        if pid == 0 {
+            return self.load_pages(0, 1);
+        }
+
+        let page_count = self.len();
+
+        if tag.jar_id != self.id || pid >= page_count {
+            return Err(Error::new(ErrorKind::UnexistingPage(*tag)));
+        }
+
+        let window = self.read_ahead.on_miss(pid);
+        let count = (pid..(pid + window).min(page_count))
+            .take_while(|&pid| pid == tag.page_id || !self.pool.contains(&self.tag().in_page(pid)))
+            .count();
+        
+        self.load_pages(pid, u64::try_from(count).unwrap())?;
+        self.read_ahead.on_loaded(pid + u64::try_from(count).unwrap() - 1);
+
+        Ok(())
+    }
+
+    /// Charge des pages contiguës depuis le stockage en une seule lecture.
+    ///
+    /// Seule la première page est requise, les suivantes ne sont pas chargées
+    /// si le tampon est plein.
+    fn load_pages(&self, start: PageId, count: u64) -> Result<()> {
+        let storage = self
+            .storage
+            .as_ref()
+            .ok_or_else(|| Error::new(ErrorKind::UnexistingPage(self.tag().in_page(start))))?;
+
+        let page_size = usize::from(self.pool.page_size());
+        let mut buf = vec![0u8; page_size * usize::try_from(count).unwrap()];
+
+        storage.read_pages(start, &mut buf).map_err(|source| {
+            Error::new(ErrorKind::PageLoadingFailed {
+                tag: self.tag().in_page(start),
+                source: Box::new(source),
+            })
+        })?;
+
+        // On garde la première page afin qu'elle ne soit pas évincée par les suivantes.
+        let mut first = None;
+
+        for (pid, content) in (start..).zip(buf.chunks(page_size)) {
+            let tag = self.tag().in_page(pid);
+
+            match self.pool.alloc_with_options(&tag, true) {
+                Ok(mut page) => {
+                    page.as_mut_bytes().copy_from_slice(content);
+                    if first.is_none() {
+                        first = Some(page);
+                    }
+                }
+                Err(err) if pid == start => return Err(err),
+                Err(_) => break,
+            }
+        }
+
+        Ok(())
     }
 }
 
@@ -65,6 +228,40 @@ impl<'buf> IPager<'buf> for Pager<'buf> {
     fn len(&self) -> u64 {
         self.get_descriptor().as_description().len()
     }
+
+    fn prefetch(&self, tags: &[JarTag]) -> Result<()> {
+        if self.storage.is_none() {
+            return Ok(());
+        }
+
+        let page_count = self.len();
+
+        let pids = tags
+            .iter()
+            .filter(|tag| tag.jar_id == self.id && tag.page_id < page_count)
+            .filter(|tag| !self.pool.contains(tag))
+            .map(|tag| tag.page_id)
+            .sorted()
+            .dedup()
+            .collect::<Vec<_>>();
+
+        // On regroupe les pages contiguës afin de les lire en une fois.
+        let runs = pids
+            .into_iter()
+            .enumerate()
+            .chunk_by(|(i, pid)| pid - u64::try_from(*i).unwrap());
+
+        for (_, run) in &runs {
+            let run = run.map(|(_, pid)| pid).collect::<Vec<_>>();
+
+            match self.load_pages(run[0], u64::try_from(run.len()).unwrap()) {
+                Err(err) if matches!(err.kind, ErrorKind::BufferFull) => break,
+                res => res?,
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl<'buf> IArena for Pager<'buf> {
@@ -72,14 +269,27 @@ impl<'buf> IArena for Pager<'buf> {
     type RefMut = MutPage<'buf>;
 
     fn new_element(&self) -> Result<Self::RefMut> {
-        if let Some(tag) = pop_free_page(self, self.get_mut_descriptor().as_mut_description())? {
-            self.borrow_mut_element(&tag)
-        } else {
-            let pid = self.get_descriptor().as_description().len();
-            self.get_mut_descriptor().as_mut_description().inc_len();
-            let tag = self.tag().in_page(pid);
-            self.pool.alloc(&tag)
+        let free = pop_free_page(self, self.get_mut_descriptor().as_mut_description())?;
+
+        if let Some(tag) = free {
+            // Le contenu d'une page libre est périmé, inutile de le charger depuis le stockage.
+            if !self.pool.contains(&tag) {
+                self.backup.on_write(tag.page_id);
+                return self.pool.alloc(&tag);
+            }
+
+            let mut page = self.borrow_mut_element(&tag)?;
+            page.fill(0);
+            return Ok(page);
         }
+
+        let mut desc = self.get_mut_descriptor();
+        let pid = desc.as_description().len();
+        desc.as_mut_description().inc_len();
+        drop(desc);
+
+        self.backup.on_write(pid);
+        self.pool.alloc(&self.tag().in_page(pid))
     }
 
     fn delete_element(&self, tag: &JarTag) -> Result<()> {
@@ -99,6 +309,8 @@ impl<'buf> IArena for Pager<'buf> {
     }
 
     fn try_borrow_mut_element(&self, tag: &JarTag) -> crate::result::Result<Option<Self::RefMut>> {
+        self.backup.on_write(tag.page_id);
+
         if !self.pool.contains(tag) {
             self.load_page(tag)?;
         }
@@ -106,6 +318,28 @@ impl<'buf> IArena for Pager<'buf> {
         self.pool.try_get_mut(tag)
     }
 
+    fn borrow_element_until(&self, tag: &JarTag, deadline: Option<Instant>) -> Result<Self::Ref> {
+        if !self.pool.contains(tag) {
+            self.load_page(tag)?;
+        }
+
+        self.pool
+            .get_ref_until(tag, deadline)?
+            .ok_or_else(|| Error::new(ErrorKind::UnexistingPage(*tag)))
+    }
+
+    fn borrow_mut_element_until(&self, tag: &JarTag, deadline: Option<Instant>) -> Result<Self::RefMut> {
+        self.backup.on_write(tag.page_id);
+
+        if !self.pool.contains(tag) {
+            self.load_page(tag)?;
+        }
+
+        self.pool
+            .get_mut_until(tag, deadline)?
+            .ok_or_else(|| Error::new(ErrorKind::UnexistingPage(*tag)))
+    }
+
     fn size_of(&self) -> usize {
         usize::from(self.get_descriptor().as_description().page_size)
     }
@@ -144,8 +378,18 @@ pub struct PagerDescription {
     pub page_count: u64,
     /// Début de la liste chaînée des pages libres
     pub free_head: OptionalPageId,
+    /// Mode de compression des pages (voir [PagerCompression])
+    pub compression: u8,
+    /// Position de la table des trames compressées, géré par le stockage
+    pub frame_table: u64,
+    /// Nombre de pages libres
+    pub free_count: u64,
+    /// Génération courante des pages (voir [lsn])
+    pub lsn: u64,
+    /// Début de la table des générations des pages
+    pub lsn_head: OptionalPageId,
     /// Données réservées
-    pub reserved: [u8; 100],
+    pub reserved: [u8; 67],
 }
 
 impl PagerDescription {
@@ -154,10 +398,23 @@ impl PagerDescription {
             page_size,
             page_count: 0,
             free_head: None.into(),
-            reserved: [0; 100],
+            compression: PagerCompression::None as u8,
+            frame_table: 0,
+            free_count: 0,
+            lsn: 1,
+            lsn_head: None.into(),
+            reserved: [0; 67],
         }
     }
 
+    pub fn compression(&self) -> Result<PagerCompression> {
+        PagerCompression::try_from(self.compression)
+    }
+
+    pub fn set_compression(&mut self, compression: PagerCompression) {
+        self.compression = compression as u8;
+    }
+
     pub fn get_free_head(&self) -> Option<PageId> {
         self.free_head.into()
     }
@@ -166,6 +423,26 @@ impl PagerDescription {
         self.free_head = head.into();
     }
 
+    pub fn free_count(&self) -> u64 {
+        self.free_count
+    }
+
+    pub fn lsn(&self) -> u64 {
+        self.lsn
+    }
+
+    pub fn set_lsn(&mut self, lsn: u64) {
+        self.lsn = lsn;
+    }
+
+    pub fn get_lsn_head(&self) -> Option<PageId> {
+        self.lsn_head.into()
+    }
+
+    pub fn set_lsn_head(&mut self, head: Option<PageId>) {
+        self.lsn_head = head.into();
+    }
+
     pub fn inc_len(&mut self) {
         self.page_count += 1
     }
@@ -175,6 +452,31 @@ impl PagerDescription {
     }
 }
 
+/// Mode de compression des pages d'un jar.
+///
+/// Le mode est fixé à la création du jar, et inscrit dans sa description.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PagerCompression {
+    /// Les pages sont stockées telles quelles.
+    #[default]
+    None = 0,
+    /// Les pages sont compressées par plages (voir [crate::compression]).
+    Rle = 1,
+}
+
+impl TryFrom<u8> for PagerCompression {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Rle),
+            _ => Err(Error::new(ErrorKind::InvalidFormat)),
+        }
+    }
+}
+
 pub mod stub {
     use std::{
         cell::{RefCell, UnsafeCell},
@@ -182,10 +484,12 @@ pub mod stub {
         marker::PhantomData,
         pin::Pin,
         ptr::NonNull,
+        time::Instant,
     };
 
     use crate::{
         arena::IArena,
+        error::{Error, ErrorKind},
         page::{
             descriptor::{PageDescriptor, PageDescriptorInner},
             MutPage, PageSlice, RefPage,
@@ -254,7 +558,8 @@ pub mod stub {
                     NonNull::slice_from_raw_parts(NonNull::new(content_ptr).unwrap(), PAGE_SIZE),
                 );
 
-                let tag = JarTag::in_jar(0).in_page(u64::try_from(buf_id).unwrap());
+                // Comme pour le paginateur, la page 0 est réservée à sa description.
+                let tag = JarTag::in_jar(0).in_page(u64::try_from(buf_id).unwrap() + 1);
 
                 let desc = PageDescriptorInner::new(buf_id, tag, content);
 
@@ -276,6 +581,18 @@ pub mod stub {
             self.get_page_descriptor(tag).map(MutPage::try_new).flip()
         }
 
+        fn borrow_element_until(&self, tag: &JarTag, deadline: Option<Instant>) -> Result<Self::Ref> {
+            self.get_page_descriptor(tag)
+                .ok_or_else(|| Error::new(ErrorKind::UnexistingPage(*tag)))
+                .and_then(|desc| RefPage::new_until(desc, deadline))
+        }
+
+        fn borrow_mut_element_until(&self, tag: &JarTag, deadline: Option<Instant>) -> Result<Self::RefMut> {
+            self.get_page_descriptor(tag)
+                .ok_or_else(|| Error::new(ErrorKind::UnexistingPage(*tag)))
+                .and_then(|desc| MutPage::new_until(desc, deadline))
+        }
+
         fn size_of(&self) -> usize {
             PAGE_SIZE
         }
@@ -297,9 +614,25 @@ pub mod stub {
 
 #[cfg(test)]
 mod tests {
-    use crate::{arena::IArena, buffer::{stress::stubs::StressStub, BufferPool}};
+    use std::{
+        io::{Read, Seek, SeekFrom, Write},
+        rc::Rc,
+    };
+
+    use crate::{
+        arena::IArena,
+        buffer::{stress::stubs::StressStub, BufferPool},
+        error::ErrorKind,
+        free::FreePage,
+        fs::{in_memory::InMemoryFs, FileOpenOptions, IFileSystem},
+        page::PageId,
+    };
 
-    use super::Pager;
+    use super::{
+        storage::{EncryptedPagerStorage, FsPagerStorage, IPagerStorage, ENCRYPTION_OVERHEAD},
+        IPager, Pager, PagerCompression,
+    };
+    use crate::page::{AsMutPageSlice, AsRefPageSlice};
 
     #[test]
     fn test_new_element() {
@@ -309,4 +642,284 @@ mod tests {
 
         assert!(buf_pool.contains(page.tag()));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_free_trunks() {
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let pager = Pager::new(0, &buf_pool).unwrap();
+
+        let tags = (0..600)
+            .map(|_| *pager.new_element().unwrap().tag())
+            .collect::<Vec<_>>();
+
+        for tag in tags.iter() {
+            pager.delete_element(tag).unwrap();
+        }
+        assert_eq!(pager.free_count(), 600);
+
+        // Un tronc de 4096 octets répertorie 510 feuilles, il en faut donc deux.
+        let head = pager.get_descriptor().as_description().get_free_head().unwrap();
+        assert_eq!(head, tags[511].page_id);
+
+        let trunk = pager
+            .borrow_element(&pager.tag().in_page(head))
+            .and_then(FreePage::try_from)
+            .unwrap();
+        assert_eq!(trunk.len(), 88);
+        assert_eq!(trunk.get_next(), Some(tags[0].page_id));
+        drop(trunk);
+
+        // Les feuilles sont réutilisées avant les troncs.
+        assert_eq!(pager.new_element().unwrap().tag(), &tags[599]);
+
+        for _ in 1..600 {
+            let page = pager.new_element().unwrap();
+            assert!(page.as_bytes().iter().all(|b| *b == 0));
+        }
+
+        assert_eq!(pager.free_count(), 0);
+        assert_eq!(pager.get_descriptor().as_description().get_free_head(), None);
+        assert_eq!(pager.len(), 601);
+    }
+
+    /// Crée un pager de *count* pages, dont le contenu est l'identifiant de la page.
+    fn create_stored_pager(fs: &Rc<InMemoryFs>, count: PageId) {
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let storage = FsPagerStorage::new(fs.clone(), "jar", 4096).into_boxed();
+        let pager = Pager::open(0, &buf_pool, storage).unwrap();
+
+        for _ in 1..count {
+            let mut page = pager.new_element().unwrap();
+            let pid = page.tag().page_id;
+            page.as_mut_bytes()[0..8].copy_from_slice(&pid.to_le_bytes());
+        }
+
+        pager.flush().unwrap();
+    }
+
+    #[test]
+    fn test_open_and_load() {
+        let fs = Rc::new(InMemoryFs::default());
+        create_stored_pager(&fs, 10);
+
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let storage = FsPagerStorage::new(fs.clone(), "jar", 4096).into_boxed();
+        let pager = Pager::open(0, &buf_pool, storage).unwrap();
+        // La table des générations occupe une page supplémentaire.
+        assert_eq!(pager.len(), 11);
+
+        let page = pager.borrow_element(&pager.tag().in_page(7)).unwrap();
+        assert_eq!(page.as_bytes()[0..8], 7u64.to_le_bytes());
+        assert!(!page.is_dirty());
+    }
+
+    #[test]
+    fn test_sequential_read_ahead() {
+        let fs = Rc::new(InMemoryFs::default());
+        create_stored_pager(&fs, 20);
+
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let storage = FsPagerStorage::new(fs.clone(), "jar", 4096).into_boxed();
+        let pager = Pager::open(0, &buf_pool, storage).unwrap();
+
+        // Un accès isolé ne charge qu'une page.
+        pager.borrow_element(&pager.tag().in_page(1)).unwrap();
+        assert!(!buf_pool.contains(&pager.tag().in_page(3)));
+
+        // Un second accès consécutif déclenche la lecture anticipée.
+        pager.borrow_element(&pager.tag().in_page(2)).unwrap();
+        assert!((2..6).all(|pid| buf_pool.contains(&pager.tag().in_page(pid))));
+        assert!(!buf_pool.contains(&pager.tag().in_page(6)));
+
+        // La fenêtre double tant que le parcours reste séquentiel.
+        pager.borrow_element(&pager.tag().in_page(6)).unwrap();
+        assert!(buf_pool.contains(&pager.tag().in_page(13)));
+        assert!(!buf_pool.contains(&pager.tag().in_page(14)));
+
+        for pid in 3..20 {
+            let page = pager.borrow_element(&pager.tag().in_page(pid)).unwrap();
+            assert_eq!(page.as_bytes()[0..8], pid.to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn test_prefetch() {
+        let fs = Rc::new(InMemoryFs::default());
+        create_stored_pager(&fs, 20);
+
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let storage = FsPagerStorage::new(fs.clone(), "jar", 4096).into_boxed();
+        let pager = Pager::open(0, &buf_pool, storage).unwrap();
+
+        let tags = [3, 4, 5, 12, 30].map(|pid| pager.tag().in_page(pid));
+        pager.prefetch(&tags).unwrap();
+
+        assert!([3, 4, 5, 12].iter().all(|pid| buf_pool.contains(&pager.tag().in_page(*pid))));
+        assert!(!buf_pool.contains(&pager.tag().in_page(6)));
+
+        let page = pager.borrow_element(&pager.tag().in_page(12)).unwrap();
+        assert_eq!(page.as_bytes()[0..8], 12u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_compressed_pages() {
+        let fs = Rc::new(InMemoryFs::default());
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let storage = FsPagerStorage::new(fs.clone(), "jar", 4096)
+            .with_compression(PagerCompression::Rle)
+            .into_boxed();
+        let pager = Pager::open(0, &buf_pool, storage).unwrap();
+
+        for _ in 1..10 {
+            let mut page = pager.new_element().unwrap();
+            let pid = page.tag().page_id;
+            page.as_mut_bytes()[0..8].copy_from_slice(&pid.to_le_bytes());
+        }
+        pager.flush().unwrap();
+
+        // Une page réécrite change de taille de trame.
+        pager.borrow_mut_element(&pager.tag().in_page(4)).unwrap().as_mut_bytes()[100..300]
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, b)| *b = i as u8);
+        pager.flush().unwrap();
+        drop(pager);
+
+        let size = fs
+            .open(&"jar".into(), FileOpenOptions::new().read(true))
+            .unwrap()
+            .seek(SeekFrom::End(0))
+            .unwrap();
+        assert!(size < 2 * 4096);
+
+        // Le mode de compression est lu depuis la description du jar.
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let storage = FsPagerStorage::new(fs.clone(), "jar", 4096).into_boxed();
+        let pager = Pager::open(0, &buf_pool, storage).unwrap();
+        assert_eq!(pager.compression().unwrap(), PagerCompression::Rle);
+        assert_eq!(pager.len(), 11);
+
+        for pid in 1..10 {
+            let page = pager.borrow_element(&pager.tag().in_page(pid)).unwrap();
+            assert_eq!(page.as_bytes()[0..8], pid.to_le_bytes());
+        }
+
+        let page = pager.borrow_element(&pager.tag().in_page(4)).unwrap();
+        assert_eq!(page.as_bytes()[299], 199);
+    }
+
+    #[test]
+    fn test_compressed_pages_before_sync() {
+        let fs = Rc::new(InMemoryFs::default());
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let storage = FsPagerStorage::new(fs.clone(), "jar", 4096)
+            .with_compression(PagerCompression::Rle)
+            .into_boxed();
+        let pager = Pager::open(0, &buf_pool, storage).unwrap();
+
+        for _ in 1..10 {
+            let mut page = pager.new_element().unwrap();
+            let pid = page.tag().page_id;
+            page.as_mut_bytes()[0..8].copy_from_slice(&pid.to_le_bytes());
+        }
+        pager.flush().unwrap();
+        drop(pager);
+
+        // La trame de la page 4 grandit, celle de la page 5 pourrait réutiliser son emplacement.
+        let storage = FsPagerStorage::new(fs.clone(), "jar", 4096);
+        let mut page = vec![0u8; 4096];
+        page.iter_mut().enumerate().for_each(|(i, b)| *b = i as u8);
+        storage.write_page(4, &page).unwrap();
+        page.fill(0);
+        page[0..8].copy_from_slice(&55u64.to_le_bytes());
+        storage.write_page(5, &page).unwrap();
+
+        // Tant que la table n'est pas synchronisée, le fichier reste celui de la dernière synchronisation.
+        let stored = FsPagerStorage::new(fs.clone(), "jar", 4096);
+        for pid in 1..10u64 {
+            stored.read_page(pid, &mut page).unwrap();
+            assert_eq!(page[0..8], pid.to_le_bytes());
+        }
+
+        storage.sync().unwrap();
+
+        let stored = FsPagerStorage::new(fs.clone(), "jar", 4096);
+        stored.read_page(4, &mut page).unwrap();
+        assert_eq!(page[4095], 255);
+        stored.read_page(5, &mut page).unwrap();
+        assert_eq!(page[0..8], 55u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_encrypted_pages() {
+        let fs = Rc::new(InMemoryFs::default());
+        let open_storage = |key: [u8; 32]| {
+            EncryptedPagerStorage::open(fs.clone(), "jar", 0, 4096, key.into())
+                .map(EncryptedPagerStorage::into_boxed)
+        };
+
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let pager = Pager::open(0, &buf_pool, open_storage([1; 32]).unwrap()).unwrap();
+        for _ in 1..4 {
+            pager.new_element().unwrap().as_mut_bytes()[0..12].copy_from_slice(b"confidential");
+        }
+        pager.flush().unwrap();
+        drop(pager);
+
+        let mut bytes = Vec::default();
+        fs.open(&"jar".into(), FileOpenOptions::new().read(true))
+            .unwrap()
+            .read_to_end(&mut bytes)
+            .unwrap();
+        assert!(!bytes.windows(12).any(|w| w == b"confidential"));
+
+        let err = open_storage([2; 32]).err().unwrap();
+        assert!(matches!(err.kind, ErrorKind::WrongKey));
+
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let pager = Pager::open(0, &buf_pool, open_storage([1; 32]).unwrap()).unwrap();
+        // La table des générations occupe une page supplémentaire.
+        assert_eq!(pager.len(), 5);
+
+        let page = pager.borrow_element(&pager.tag().in_page(3)).unwrap();
+        assert_eq!(&page.as_bytes()[0..12], b"confidential");
+    }
+
+    #[test]
+    fn test_encrypted_pages_tampering() {
+        let fs = Rc::new(InMemoryFs::default());
+        let slot_size = 4096 + u64::from(ENCRYPTION_OVERHEAD);
+        let storage = EncryptedPagerStorage::open(fs.clone(), "jar", 1, 4096, [1; 32].into()).unwrap();
+        let mut page = vec![7u8; 4096];
+
+        // Les pages 1 et 2, jamais écrites, sont scellées vides.
+        storage.write_page(3, &page).unwrap();
+        storage.read_page(1, &mut page).unwrap();
+        assert!(page.iter().all(|b| *b == 0));
+        // Au-delà de la fin du stockage, la page est lue remplie de zéros.
+        storage.read_page(4, &mut page).unwrap();
+
+        let mut bytes = Vec::default();
+        fs.open(&"jar".into(), FileOpenOptions::new().read(true))
+            .unwrap()
+            .read_to_end(&mut bytes)
+            .unwrap();
+
+        // Une page ne peut être transplantée dans un autre jar partageant la clé.
+        fs.open(&"other".into(), FileOpenOptions::new().create(true).write(true))
+            .unwrap()
+            .write_all(&bytes)
+            .unwrap();
+        let other = EncryptedPagerStorage::open(fs.clone(), "other", 2, 4096, [1; 32].into()).unwrap();
+        let err = other.read_page(3, &mut page).err().unwrap();
+        assert!(matches!(err.kind, ErrorKind::DecryptionFailed));
+
+        // Une page dont l'en-tête est effacé n'est pas lue vide.
+        let mut file = fs.open(&"jar".into(), FileOpenOptions::new().write(true)).unwrap();
+        file.seek(SeekFrom::Start(4 * slot_size)).unwrap();
+        file.write_all(&[0; ENCRYPTION_OVERHEAD as usize]).unwrap();
+
+        let err = storage.read_page(3, &mut page).err().unwrap();
+        assert!(matches!(err.kind, ErrorKind::DecryptionFailed));
+    }
+}
@@ -0,0 +1,48 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::page::PageId;
+
+/// Détection des parcours séquentiels pour la lecture anticipée des pages.
+///
+/// Tant que les défauts de page portent sur des pages consécutives, la
+/// fenêtre de lecture anticipée double, jusqu'à [ReadAhead::MAX_WINDOW] pages.
+pub(super) struct ReadAhead {
+    /// Dernière page chargée
+    last: AtomicU64,
+    /// Taille de la fenêtre courante
+    window: AtomicU64,
+}
+
+impl Default for ReadAhead {
+    fn default() -> Self {
+        Self {
+            last: AtomicU64::new(u64::MAX),
+            window: AtomicU64::new(1),
+        }
+    }
+}
+
+impl ReadAhead {
+    pub const MIN_WINDOW: u64 = 4;
+    pub const MAX_WINDOW: u64 = 32;
+
+    /// Enregistre un défaut sur la page et retourne le nombre de pages à charger.
+    pub fn on_miss(&self, pid: PageId) -> u64 {
+        let last = self.last.load(Ordering::Acquire);
+
+        let window = if last.checked_add(1) == Some(pid) {
+            let window = self.window.load(Ordering::Acquire);
+            (window * 2).clamp(Self::MIN_WINDOW, Self::MAX_WINDOW)
+        } else {
+            1
+        };
+
+        self.window.store(window, Ordering::Release);
+        window
+    }
+
+    /// Enregistre la dernière page chargée.
+    pub fn on_loaded(&self, last: PageId) {
+        self.last.store(last, Ordering::Release);
+    }
+}
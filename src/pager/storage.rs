@@ -0,0 +1,608 @@
+use std::{
+    io::{Read, Seek, SeekFrom, Write},
+    mem::offset_of,
+    sync::{Mutex, MutexGuard},
+};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use zerocopy::FromBytes;
+
+use crate::{
+    compression::{self, FrameSpace},
+    crypto::{self, EncryptionKey, NONCE_SIZE, TAG_SIZE},
+    error::{Error, ErrorKind},
+    fs::{FileOpenOptions, FilePtr, IFileSystem},
+    page::{PageId, PageSize},
+    result::Result,
+    tag::{JarId, JarTag},
+};
+
+use super::{PagerCompression, PagerDescription};
+
+/// Stockage persistant des pages d'un pager.
+///
+/// La page 0 contient la description du pager (voir [super::PagerDescription]).
+pub trait IPagerStorage {
+    /// Taille d'une page
+    fn page_size(&self) -> PageSize;
+
+    /// Nombre de pages présentes dans le stockage.
+    fn len(&self) -> Result<u64>;
+
+    /// Aucune page n'est présente dans le stockage.
+    fn is_empty(&self) -> Result<bool> {
+        self.len().map(|len| len == 0)
+    }
+
+    /// Lit une page.
+    ///
+    /// Une page au-delà de la fin du stockage est lue remplie de zéros.
+    fn read_page(&self, pid: PageId, dest: &mut [u8]) -> Result<()> {
+        self.read_pages(pid, dest)
+    }
+
+    /// Lit des pages contiguës, à partir de *start*, en une seule lecture.
+    ///
+    /// La taille de *dest* est un multiple de la taille d'une page.
+    fn read_pages(&self, start: PageId, dest: &mut [u8]) -> Result<()>;
+
+    /// Écrit une page.
+    fn write_page(&self, pid: PageId, src: &[u8]) -> Result<()>;
+
+    /// Mode de compression des pages stockées.
+    fn compression(&self) -> Result<PagerCompression> {
+        Ok(PagerCompression::None)
+    }
+
+    /// Réduit le stockage aux *len* premières pages.
+    fn truncate(&self, len: u64) -> Result<()>;
+
+    /// Garantit la persistance des écritures.
+    fn sync(&self) -> Result<()>;
+}
+
+pub type PagerStorage = Box<dyn IPagerStorage>;
+
+/// Stockage des pages dans un fichier (cf [IFileSystem]).
+///
+/// Sans compression, la page *pid* est stockée à l'adresse *pid × page_size*.
+///
+/// Avec compression, la page 0 est stockée telle quelle au début du fichier,
+/// les autres pages dans des trames de taille variable. La table des trames
+/// est écrite lors de la synchronisation, et sa position inscrite dans la
+/// description du pager (voir [PagerDescription::frame_table]).
+pub struct FsPagerStorage<Fs: IFileSystem> {
+    file: FilePtr<Fs>,
+    page_size: PageSize,
+    /// Mode de compression d'un nouveau fichier
+    compression: PagerCompression,
+    /// Disposition des pages, chargée au premier accès
+    layout: Mutex<Option<Layout>>,
+}
+
+/// Disposition des pages dans le fichier
+enum Layout {
+    Raw,
+    Compressed(FrameTable),
+}
+
+/// Emplacement de la trame d'une page compressée.
+///
+/// Une trame vide correspond à une page remplie de zéros.
+#[derive(Default, Clone, Copy)]
+struct Frame {
+    offset: u64,
+    len: u32,
+}
+
+/// Table des trames des pages compressées.
+struct FrameTable {
+    /// Trames des pages, à partir de la page 1
+    frames: Vec<Frame>,
+    /// Espace occupé par les trames et la table
+    space: FrameSpace,
+    /// Emplacement de la table dans le fichier
+    location: Option<(u64, u64)>,
+    /// La table a été modifiée depuis la dernière synchronisation
+    dirty: bool,
+    /// Trames remplacées depuis la dernière synchronisation.
+    ///
+    /// La table stockée les référence encore : leur espace n'est libéré qu'une
+    /// fois la nouvelle table écrite.
+    superseded: Vec<(u64, u64)>,
+}
+
+/// Taille d'une entrée de la table des trames
+const FRAME_ENTRY_SIZE: u64 = 12;
+
+impl<Fs: IFileSystem> FsPagerStorage<Fs> {
+    pub fn new<Path: Into<Fs::Path>>(fs: Fs, path: Path, page_size: PageSize) -> Self {
+        Self {
+            file: FilePtr::new(fs, path),
+            page_size,
+            compression: PagerCompression::None,
+            layout: Mutex::default(),
+        }
+    }
+
+    /// Stockage des pages telles quelles, sans lecture de la description.
+    pub(crate) fn raw<Path: Into<Fs::Path>>(fs: Fs, path: Path, page_size: PageSize) -> Self {
+        Self {
+            layout: Mutex::new(Some(Layout::Raw)),
+            ..Self::new(fs, path, page_size)
+        }
+    }
+
+    /// Compresse les pages si le fichier est nouveau.
+    ///
+    /// Le mode d'un fichier existant est celui inscrit dans sa description.
+    pub fn with_compression(mut self, compression: PagerCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn into_boxed(self) -> PagerStorage
+    where
+        Fs: 'static,
+    {
+        Box::new(self)
+    }
+
+    fn loc(&self, pid: PageId) -> u64 {
+        pid * u64::from(self.page_size)
+    }
+
+    /// Retourne la disposition des pages, en la chargeant si besoin.
+    fn layout(&self) -> Result<MutexGuard<'_, Option<Layout>>> {
+        let mut layout = self.layout.lock().unwrap();
+
+        if layout.is_none() {
+            *layout = Some(self.load_layout()?);
+        }
+
+        Ok(layout)
+    }
+
+    fn load_layout(&self) -> Result<Layout> {
+        if !self.file.exists() {
+            return Ok(match self.compression {
+                PagerCompression::None => Layout::Raw,
+                PagerCompression::Rle => Layout::Compressed(FrameTable::new(self.loc(1))),
+            });
+        }
+
+        let mut header = vec![0u8; size_of::<PagerDescription>()];
+        self.read_at(0, &mut header)?;
+        let desc = PagerDescription::ref_from_bytes(&header).unwrap();
+
+        match desc.compression()? {
+            PagerCompression::None => Ok(Layout::Raw),
+            PagerCompression::Rle if desc.frame_table == 0 => {
+                Ok(Layout::Compressed(FrameTable::new(self.loc(1))))
+            }
+            PagerCompression::Rle => {
+                let mut file = self.file.open(FileOpenOptions::new().read(true))?;
+                file.seek(SeekFrom::Start(desc.frame_table))?;
+                FrameTable::read_from(&mut file, desc.frame_table, self.loc(1)).map(Layout::Compressed)
+            }
+        }
+    }
+
+    /// Lit à partir de *offset*, la partie au-delà de la fin du fichier est remplie de zéros.
+    fn read_at(&self, offset: u64, dest: &mut [u8]) -> Result<()> {
+        if !self.file.exists() {
+            dest.fill(0);
+            return Ok(());
+        }
+
+        let mut file = self.file.open(FileOpenOptions::new().read(true))?;
+        file.seek(SeekFrom::Start(offset))?;
+
+        let mut read = 0;
+        while read < dest.len() {
+            match file.read(&mut dest[read..])? {
+                0 => break,
+                n => read += n,
+            }
+        }
+        dest[read..].fill(0);
+
+        Ok(())
+    }
+
+    fn write_at(&self, offset: u64, src: &[u8]) -> Result<()> {
+        let mut file = self
+            .file
+            .open(FileOpenOptions::new().create(true).write(true))?;
+
+        file.seek(SeekFrom::Start(offset))?;
+        file.write_all(src)?;
+        Ok(())
+    }
+
+    /// Écrit la page 0, en y inscrivant la position de la table des trames.
+    fn write_header(&self, table: &FrameTable, src: &[u8]) -> Result<()> {
+        let mut header = src.to_vec();
+        let desc =
+            PagerDescription::mut_from_bytes(&mut header[0..size_of::<PagerDescription>()]).unwrap();
+        desc.frame_table = table.location.map(|(offset, _)| offset).unwrap_or_default();
+        self.write_at(0, &header)
+    }
+
+    /// Écrit la table des trames, et inscrit sa position dans la description.
+    fn write_frame_table(&self, table: &mut FrameTable) -> Result<()> {
+        let mut buf = Vec::default();
+        table.write_to(&mut buf)?;
+
+        let len = u64::try_from(buf.len()).unwrap();
+        let offset = table.space.reserve(len);
+        self.write_at(offset, &buf)?;
+        self.write_at(
+            u64::try_from(offset_of!(PagerDescription, frame_table)).unwrap(),
+            &offset.to_le_bytes(),
+        )?;
+
+        if let Some(old) = table.location.replace((offset, len)) {
+            table.superseded.push(old);
+        }
+        table.dirty = false;
+
+        Ok(())
+    }
+}
+
+impl FrameTable {
+    fn new(start: u64) -> Self {
+        Self {
+            frames: Vec::default(),
+            space: FrameSpace::new(start, std::iter::empty()),
+            location: None,
+            dirty: false,
+            superseded: Vec::default(),
+        }
+    }
+
+    /// Lit la table des trames située à *offset*.
+    fn read_from<R: Read>(src: &mut R, offset: u64, start: u64) -> Result<Self> {
+        let count = src.read_u64::<LittleEndian>()?;
+        let frames = (0..count)
+            .map(|_| -> Result<Frame> {
+                Ok(Frame {
+                    offset: src.read_u64::<LittleEndian>()?,
+                    len: src.read_u32::<LittleEndian>()?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let location = (offset, 8 + count * FRAME_ENTRY_SIZE);
+        let used = frames
+            .iter()
+            .filter(|frame| frame.len > 0)
+            .map(|frame| (frame.offset, u64::from(frame.len)))
+            .chain(std::iter::once(location));
+
+        Ok(Self {
+            space: FrameSpace::new(start, used),
+            frames,
+            location: Some(location),
+            dirty: false,
+            superseded: Vec::default(),
+        })
+    }
+
+    fn write_to<W: Write>(&self, dest: &mut W) -> Result<()> {
+        dest.write_u64::<LittleEndian>(u64::try_from(self.frames.len()).unwrap())?;
+
+        for frame in &self.frames {
+            dest.write_u64::<LittleEndian>(frame.offset)?;
+            dest.write_u32::<LittleEndian>(frame.len)?;
+        }
+
+        Ok(())
+    }
+
+    /// Libère les trames des pages au-delà des *len* premières.
+    fn truncate(&mut self, len: u64) {
+        let len = usize::try_from(len.saturating_sub(1)).unwrap();
+
+        for frame in self.frames.drain(len.min(self.frames.len())..) {
+            if frame.len > 0 {
+                self.superseded.push((frame.offset, u64::from(frame.len)));
+            }
+        }
+
+        self.dirty = true;
+    }
+
+    /// Libère l'espace des trames remplacées, une fois la table synchronisée.
+    fn release_superseded(&mut self) {
+        for (offset, len) in self.superseded.drain(..) {
+            self.space.release(offset, len);
+        }
+    }
+
+    fn get(&self, pid: PageId) -> Option<Frame> {
+        let index = usize::try_from(pid.checked_sub(1)?).unwrap();
+        self.frames.get(index).copied().filter(|frame| frame.len > 0)
+    }
+
+    /// Remplace la trame de la page, et retourne l'emplacement de la nouvelle trame.
+    fn replace(&mut self, pid: PageId, len: u32) -> u64 {
+        let index = usize::try_from(pid - 1).unwrap();
+
+        if index >= self.frames.len() {
+            self.frames.resize(index + 1, Frame::default());
+        }
+
+        let old = self.frames[index];
+        if old.len > 0 {
+            self.superseded.push((old.offset, u64::from(old.len)));
+        }
+
+        let offset = self.space.reserve(u64::from(len));
+        self.frames[index] = Frame { offset, len };
+        self.dirty = true;
+
+        offset
+    }
+}
+
+impl<Fs: IFileSystem> IPagerStorage for FsPagerStorage<Fs> {
+    fn page_size(&self) -> PageSize {
+        self.page_size
+    }
+
+    fn compression(&self) -> Result<PagerCompression> {
+        match self.layout()?.as_ref().unwrap() {
+            Layout::Raw => Ok(PagerCompression::None),
+            Layout::Compressed(_) => Ok(PagerCompression::Rle),
+        }
+    }
+
+    fn len(&self) -> Result<u64> {
+        if !self.file.exists() {
+            return Ok(0);
+        }
+
+        match self.layout()?.as_ref().unwrap() {
+            Layout::Raw => {
+                let mut file = self.file.open(FileOpenOptions::new().read(true))?;
+                let size = file.seek(SeekFrom::End(0))?;
+                Ok(size.div_ceil(u64::from(self.page_size)))
+            }
+            Layout::Compressed(table) => Ok(u64::try_from(table.frames.len()).unwrap() + 1),
+        }
+    }
+
+    fn read_pages(&self, start: PageId, dest: &mut [u8]) -> Result<()> {
+        let layout = self.layout()?;
+
+        let Some(Layout::Compressed(table)) = layout.as_ref() else {
+            // On lit jusqu'à la fin du fichier, le reste est rempli de zéros.
+            return self.read_at(self.loc(start), dest);
+        };
+
+        let page_size = usize::from(self.page_size);
+
+        for (pid, dest) in (start..).zip(dest.chunks_mut(page_size)) {
+            if pid == 0 {
+                self.read_at(0, dest)?;
+                continue;
+            }
+
+            match table.get(pid) {
+                Some(frame) => {
+                    let mut buf = vec![0u8; usize::try_from(frame.len).unwrap()];
+                    self.read_at(frame.offset, &mut buf)?;
+                    compression::decompress(&buf, dest)?;
+                }
+                None => dest.fill(0),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_page(&self, pid: PageId, src: &[u8]) -> Result<()> {
+        let mut layout = self.layout()?;
+
+        let Some(Layout::Compressed(table)) = layout.as_mut() else {
+            return self.write_at(self.loc(pid), src);
+        };
+
+        if pid == 0 {
+            return self.write_header(table, src);
+        }
+
+        let frame = compression::compress(src);
+        let offset = table.replace(pid, u32::try_from(frame.len()).unwrap());
+        self.write_at(offset, &frame)
+    }
+
+    fn truncate(&self, len: u64) -> Result<()> {
+        if !self.file.exists() {
+            return Ok(());
+        }
+
+        match self.layout()?.as_mut().unwrap() {
+            Layout::Raw => Ok(self.file.set_len(self.loc(len))?),
+            // L'espace des trames libérées est restitué après la synchronisation.
+            Layout::Compressed(table) => {
+                table.truncate(len);
+                Ok(())
+            }
+        }
+    }
+
+    fn sync(&self) -> Result<()> {
+        let mut layout = self.layout()?;
+        let mut table = match layout.as_mut() {
+            Some(Layout::Compressed(table)) => Some(table),
+            _ => None,
+        };
+
+        if let Some(table) = table.as_mut().filter(|table| table.dirty) {
+            self.write_frame_table(table)?;
+        }
+
+        if self.file.exists() {
+            self.file
+                .open(FileOpenOptions::new().write(true))?
+                .flush()?;
+        }
+
+        // La nouvelle table est écrite : les trames remplacées peuvent être réutilisées,
+        // et l'espace libéré en fin de fichier est restitué.
+        if let Some(table) = table.filter(|table| !table.superseded.is_empty()) {
+            table.release_superseded();
+            self.file.set_len(table.space.end())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Surcoût d'une page chiffrée : son nonce et son code d'authentification.
+pub const ENCRYPTION_OVERHEAD: PageSize = (NONCE_SIZE + TAG_SIZE) as PageSize;
+
+/// Témoin inscrit en tête du stockage chiffré
+const KEY_CHECK_MAGIC: &[u8; 8] = b"JARNACEK";
+
+/// Stockage chiffré des pages dans un fichier (voir [crate::crypto]).
+///
+/// Chaque page est stockée dans un emplacement de [ENCRYPTION_OVERHEAD] octets
+/// de plus, contenant son nonce et son code d'authentification. Le premier
+/// emplacement contient un témoin permettant de détecter une clé erronée à l'ouverture.
+///
+/// Le chiffré est lié au jar et à la page (voir [crypto::tag_aad]) : une page ne peut
+/// être déplacée vers un autre emplacement, ou un autre jar partageant la clé.
+/// Toutes les pages en deçà de la fin du stockage sont scellées, y compris les pages
+/// jamais écrites : seule une page au-delà de la fin peut être lue remplie de zéros.
+pub struct EncryptedPagerStorage {
+    inner: PagerStorage,
+    jar_id: JarId,
+    key: EncryptionKey,
+}
+
+impl EncryptedPagerStorage {
+    /// Ouvre le stockage chiffré, en vérifiant la clé.
+    ///
+    /// Retourne l'erreur [ErrorKind::WrongKey] si la clé est erronée.
+    pub fn open<Fs, Path>(fs: Fs, path: Path, jar_id: JarId, page_size: PageSize, key: EncryptionKey) -> Result<Self>
+    where
+        Fs: IFileSystem + 'static,
+        Path: Into<Fs::Path>,
+    {
+        let slot_size = page_size
+            .checked_add(ENCRYPTION_OVERHEAD)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidFormat))?;
+
+        let inner = FsPagerStorage::raw(fs, path, slot_size).into_boxed();
+        let storage = Self { inner, jar_id, key };
+        let mut slot = vec![0u8; usize::from(storage.inner.page_size())];
+        let magic = 0..KEY_CHECK_MAGIC.len();
+        let nonce = magic.end..magic.end + NONCE_SIZE;
+        let tag = nonce.end..nonce.end + TAG_SIZE;
+
+        if storage.inner.is_empty()? {
+            let check_nonce = crypto::random_nonce();
+            let check_tag = crypto::seal(&storage.key, &check_nonce, KEY_CHECK_MAGIC, &mut []);
+
+            slot[magic].copy_from_slice(KEY_CHECK_MAGIC);
+            slot[nonce].copy_from_slice(&check_nonce);
+            slot[tag].copy_from_slice(&check_tag);
+            storage.inner.write_page(0, &slot)?;
+
+            return Ok(storage);
+        }
+
+        storage.inner.read_page(0, &mut slot)?;
+
+        if slot[magic] != *KEY_CHECK_MAGIC {
+            return Err(Error::new(ErrorKind::InvalidFormat));
+        }
+
+        let check_nonce = slot[nonce].try_into().unwrap();
+        let check_tag = slot[tag].try_into().unwrap();
+        crypto::open(&storage.key, &check_nonce, KEY_CHECK_MAGIC, &mut [], &check_tag)
+            .map_err(|_| Error::new(ErrorKind::WrongKey))?;
+
+        Ok(storage)
+    }
+
+    pub fn into_boxed(self) -> PagerStorage {
+        Box::new(self)
+    }
+
+    fn aad(&self, pid: PageId) -> [u8; 16] {
+        crypto::tag_aad(&JarTag::in_jar(self.jar_id).in_page(pid))
+    }
+
+    /// Chiffre et écrit une page, sans combler les pages manquantes.
+    fn seal_page(&self, pid: PageId, src: &[u8]) -> Result<()> {
+        let nonce = crypto::random_nonce();
+        let mut content = src.to_vec();
+        let tag = crypto::seal(&self.key, &nonce, &self.aad(pid), &mut content);
+
+        let mut slot = Vec::with_capacity(usize::from(self.inner.page_size()));
+        slot.extend_from_slice(&nonce);
+        slot.extend_from_slice(&tag);
+        slot.extend_from_slice(&content);
+
+        self.inner.write_page(pid + 1, &slot)
+    }
+}
+
+impl IPagerStorage for EncryptedPagerStorage {
+    fn page_size(&self) -> PageSize {
+        self.inner.page_size() - ENCRYPTION_OVERHEAD
+    }
+
+    fn len(&self) -> Result<u64> {
+        self.inner.len().map(|len| len.saturating_sub(1))
+    }
+
+    fn read_pages(&self, start: PageId, dest: &mut [u8]) -> Result<()> {
+        let page_size = usize::from(self.page_size());
+        let slot_size = usize::from(self.inner.page_size());
+        let count = dest.len() / page_size;
+
+        let len = self.len()?;
+
+        let mut slots = vec![0u8; count * slot_size];
+        self.inner.read_pages(start + 1, &mut slots)?;
+
+        for ((pid, slot), dest) in (start..).zip(slots.chunks_mut(slot_size)).zip(dest.chunks_mut(page_size)) {
+            // Page au-delà de la fin du stockage
+            if pid >= len {
+                dest.fill(0);
+                continue;
+            }
+
+            let (header, content) = slot.split_at_mut(NONCE_SIZE + TAG_SIZE);
+            let (nonce, tag) = header.split_at(NONCE_SIZE);
+            crypto::open(&self.key, nonce.try_into().unwrap(), &self.aad(pid), content, tag.try_into().unwrap())?;
+            dest.copy_from_slice(content);
+        }
+
+        Ok(())
+    }
+
+    fn write_page(&self, pid: PageId, src: &[u8]) -> Result<()> {
+        // Les pages manquantes sont scellées vides, afin d'être authentifiées à la lecture.
+        let empty = vec![0u8; usize::from(self.page_size())];
+        for missing in self.len()?..pid {
+            self.seal_page(missing, &empty)?;
+        }
+
+        self.seal_page(pid, src)
+    }
+
+    fn truncate(&self, len: u64) -> Result<()> {
+        self.inner.truncate(len + 1)
+    }
+
+    fn sync(&self) -> Result<()> {
+        self.inner.sync()
+    }
+}
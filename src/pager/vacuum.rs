@@ -0,0 +1,345 @@
+//! Compactage d'un pager.
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{
+    arena::IArena,
+    bpt::{descriptor::BPTreeDescriptor, interior::BPlusTreeInterior, leaf::BPlusTreeLeaf},
+    error::{Error, ErrorKind},
+    free::{push_free_page, FreePage},
+    page::{AsMutPageSlice, AsRefPageSlice, PageId, PageKind},
+    result::Result,
+    var::SpillPage,
+};
+
+use super::{IPager, Pager};
+
+/// Table de déplacement des pages
+type Relocation = HashMap<PageId, PageId>;
+
+fn relocate(relocation: &Relocation, pid: Option<PageId>) -> Option<PageId> {
+    pid.map(|pid| relocation.get(&pid).copied().unwrap_or(pid))
+}
+
+impl Pager<'_> {
+    /// Compacte le pager, et restitue l'espace des pages libres au stockage.
+    ///
+    /// Les pages des arbres B+ (noeuds et pages de débordement des valeurs)
+    /// situées en fin de pager sont déplacées dans les pages libres, et leurs
+    /// références mises à jour. Le pager est ensuite tronqué après sa dernière
+    /// page vivante.
+    ///
+    /// Les autres pages, dont les pages d'entrée des arbres B+ référencées hors
+    /// du pager, ne sont pas déplacées.
+    ///
+    /// Retourne le nombre de pages restituées, ou [ErrorKind::PageCurrentlyBorrowed]
+    /// si une page du pager est empruntée.
+    pub fn vacuum(&self) -> Result<u64> {
+        if self.pool.jar_tags(self.id).iter().any(|tag| self.pool.is_borrowed(tag)) {
+            return Err(Error::new(ErrorKind::PageCurrentlyBorrowed));
+        }
+
+        let page_count = self.len();
+        let free = self.free_pages()?;
+
+        // Les pages d'entrée des arbres B+, et les pages qui en dépendent.
+        let mut trees = Vec::default();
+        let mut movable = BTreeSet::default();
+
+        for pid in (1..page_count).filter(|pid| !free.contains(pid)) {
+            let page = self.borrow_element(&self.tag().in_page(pid))?;
+
+            if page.as_bytes()[0] == PageKind::BPlusTree as u8 {
+                drop(page);
+                movable.extend(self.tree_pages(pid)?);
+                trees.push(pid);
+            }
+        }
+
+        // Les pages déplaçables les plus éloignées vont dans les pages libres les plus proches.
+        let mut holes = free.clone();
+        let mut relocation = Relocation::default();
+
+        for pid in movable.iter().rev() {
+            match holes.first() {
+                Some(&hole) if hole < *pid => {
+                    holes.pop_first();
+                    relocation.insert(*pid, hole);
+                }
+                _ => break,
+            }
+        }
+
+        for (from, to) in relocation.iter() {
+            let src = self.borrow_element(&self.tag().in_page(*from))?;
+            let mut dest = self.borrow_mut_element(&self.tag().in_page(*to))?;
+            dest.as_mut_bytes().copy_from_slice(src.as_bytes());
+        }
+
+        for pid in trees {
+            self.relocate_tree(pid, &relocation)?;
+        }
+
+        let new_len = (0..page_count)
+            .rev()
+            .find(|pid| !holes.contains(pid) && !relocation.contains_key(pid))
+            .map(|pid| pid + 1)
+            .unwrap_or(1);
+
+        // La liste des pages libres est reconstruite à partir des pages libres restantes.
+        let mut desc = self.get_mut_descriptor();
+        let description = desc.as_mut_description();
+        description.set_free_head(None);
+        description.free_count = 0;
+
+        for pid in holes.iter().rev().filter(|pid| **pid < new_len) {
+            push_free_page(self, description, &self.tag().in_page(*pid))?;
+        }
+
+        description.page_count = new_len;
+        drop(desc);
+
+        for pid in new_len..page_count {
+            self.pool.discard(&self.tag().in_page(pid))?;
+        }
+
+        if let Some(storage) = self.storage.as_ref() {
+            self.flush()?;
+            storage.truncate(new_len)?;
+            storage.sync()?;
+        }
+
+        Ok(page_count - new_len)
+    }
+
+    /// Pages de la liste des pages libres, troncs et feuilles.
+    fn free_pages(&self) -> Result<BTreeSet<PageId>> {
+        let mut free = BTreeSet::default();
+        let mut current = self.get_descriptor().as_description().get_free_head();
+
+        while let Some(pid) = current {
+            let trunk = self
+                .borrow_element(&self.tag().in_page(pid))
+                .and_then(FreePage::try_from)?;
+
+            // Une page répertoriée deux fois signifie que la liste est corrompue.
+            for pid in std::iter::once(pid).chain(trunk.iter()) {
+                if !free.insert(pid) {
+                    return Err(Error::new(ErrorKind::InvalidFormat));
+                }
+            }
+
+            current = trunk.get_next();
+        }
+
+        Ok(free)
+    }
+
+    /// Pages dépendant d'un arbre B+ : ses noeuds, et les pages de débordement de ses valeurs.
+    fn tree_pages(&self, desc_pid: PageId) -> Result<Vec<PageId>> {
+        let desc = self
+            .borrow_element(&self.tag().in_page(desc_pid))
+            .and_then(BPTreeDescriptor::try_from)?;
+        let spills = desc.is_var_sized();
+        let mut stack = desc.root().into_iter().collect::<Vec<_>>();
+        drop(desc);
+
+        let mut pages = Vec::default();
+
+        while let Some(pid) = stack.pop() {
+            pages.push(pid);
+            let page = self.borrow_element(&self.tag().in_page(pid))?;
+
+            match PageKind::try_from(page.as_bytes()[0])? {
+                PageKind::BPlusTreeInterior => {
+                    let interior = BPlusTreeInterior::try_from(page)?;
+                    stack.extend(interior.iter().filter_map(|cell| cell.left()));
+                    stack.extend(interior.tail());
+                }
+                PageKind::BPlusTreeLeaf if spills => {
+                    let leaf = BPlusTreeLeaf::try_from(page)?;
+                    let heads = leaf
+                        .iter()
+                        .filter_map(|cell| cell.borrow_var().spill_page())
+                        .collect::<Vec<_>>();
+                    drop(leaf);
+
+                    for head in heads {
+                        pages.extend(self.spill_chain(head)?);
+                    }
+                }
+                PageKind::BPlusTreeLeaf => {}
+                kind => {
+                    return Err(Error::new(ErrorKind::WrongPageKind {
+                        expected: PageKind::BPlusTreeLeaf,
+                        got: kind,
+                    }))
+                }
+            }
+        }
+
+        Ok(pages)
+    }
+
+    /// Pages d'une liste chaînée de pages de débordement.
+    fn spill_chain(&self, head: PageId) -> Result<Vec<PageId>> {
+        let mut pages = Vec::default();
+        let mut current = Some(head);
+
+        while let Some(pid) = current {
+            pages.push(pid);
+            current = self
+                .borrow_element(&self.tag().in_page(pid))
+                .and_then(SpillPage::try_from)?
+                .get_next();
+        }
+
+        Ok(pages)
+    }
+
+    /// Met à jour les références d'un arbre B+ vers les pages déplacées.
+    fn relocate_tree(&self, desc_pid: PageId, relocation: &Relocation) -> Result<()> {
+        let mut desc = self
+            .borrow_mut_element(&self.tag().in_page(desc_pid))
+            .and_then(BPTreeDescriptor::try_from)?;
+        let root = relocate(relocation, desc.root());
+        let spills = desc.is_var_sized();
+        desc.set_root(root);
+        drop(desc);
+
+        let mut stack = root.into_iter().collect::<Vec<_>>();
+
+        while let Some(pid) = stack.pop() {
+            let page = self.borrow_mut_element(&self.tag().in_page(pid))?;
+
+            if page.as_bytes()[0] == PageKind::BPlusTreeInterior as u8 {
+                let mut interior = BPlusTreeInterior::try_from(page)?;
+                interior.set_parent(relocate(relocation, interior.parent()));
+                interior.set_tail(relocate(relocation, interior.tail()));
+
+                let cids = interior.iter().map(|cell| cell.cid()).collect::<Vec<_>>();
+                for cid in cids {
+                    let left = relocate(relocation, interior[&cid].left());
+                    interior[&cid].set_left(left);
+                    stack.extend(left);
+                }
+
+                stack.extend(interior.tail());
+                continue;
+            }
+
+            let mut leaf = BPlusTreeLeaf::try_from(page)?;
+            leaf.set_parent(relocate(relocation, leaf.get_parent()));
+            leaf.set_prev(relocate(relocation, leaf.get_prev()));
+            leaf.set_next(relocate(relocation, leaf.get_next()));
+
+            if !spills {
+                continue;
+            }
+
+            let cids = leaf.iter().map(|cell| cell.cid()).collect::<Vec<_>>();
+            let mut heads = Vec::default();
+
+            for cid in cids {
+                let var = leaf[&cid].borrow_mut_var();
+                let head = relocate(relocation, var.spill_page());
+                var.set_spill_page(head);
+                heads.extend(head);
+            }
+            drop(leaf);
+
+            for head in heads {
+                self.relocate_spill_chain(head, relocation)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Met à jour les références d'une liste chaînée de pages de débordement.
+    fn relocate_spill_chain(&self, head: PageId, relocation: &Relocation) -> Result<()> {
+        let mut current = Some(head);
+
+        while let Some(pid) = current {
+            let mut spill = self
+                .borrow_mut_element(&self.tag().in_page(pid))
+                .and_then(SpillPage::try_from)?;
+            current = relocate(relocation, spill.get_next());
+            spill.set_next(current);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        borrow::Borrow,
+        io::{Seek, SeekFrom},
+        rc::Rc,
+    };
+
+    use crate::{
+        arena::IArena,
+        bpt::{BPlusTree, BPlusTreeArgs},
+        buffer::{stress::stubs::StressStub, BufferPool},
+        error::ErrorKind,
+        fs::{in_memory::InMemoryFs, FileOpenOptions, IFileSystem},
+        pager::{storage::FsPagerStorage, IPager, Pager},
+        prelude::IntoKnackBuf,
+    };
+
+    #[test]
+    fn test_vacuum() {
+        let fs = Rc::new(InMemoryFs::default());
+        let buf_pool = BufferPool::new(4_000_000, 4096, StressStub::default().into_boxed());
+        let storage = FsPagerStorage::new(fs.clone(), "jar", 4096).into_boxed();
+        let pager = Pager::open(0, &buf_pool, storage).unwrap();
+
+        let fillers = (0..6)
+            .map(|_| *pager.new_element().unwrap().tag())
+            .collect::<Vec<_>>();
+
+        // Une valeur débordant sur plusieurs pages
+        let value = "jarnac".repeat(2_000);
+        let key = 18u64.into_knack_buf();
+        let mut tree = BPlusTree::new(&pager, BPlusTreeArgs::new::<u64, str>(None)).unwrap();
+        tree.insert(key.borrow(), &value.as_str().into_knack_buf()).unwrap();
+        assert_eq!(pager.len(), 12);
+
+        for tag in fillers.iter() {
+            pager.delete_element(tag).unwrap();
+        }
+
+        // La table des générations prend la page libre 6.
+        pager.flush().unwrap();
+
+        // Une page empruntée empêche le compactage.
+        let found = tree.search(key.borrow()).unwrap();
+        let err = pager.vacuum().err().unwrap();
+        assert!(matches!(err.kind, ErrorKind::PageCurrentlyBorrowed));
+        assert_eq!(pager.len(), 12);
+
+        // Une recherche ne doit pas laisser de page empruntée.
+        drop(found);
+
+        // La feuille et ses trois pages de débordement sont déplacées,
+        // la page d'entrée de l'arbre reste en place.
+        assert_eq!(pager.vacuum().unwrap(), 4);
+        assert_eq!(pager.len(), 8);
+        assert_eq!(pager.free_pages().unwrap().into_iter().collect::<Vec<_>>(), vec![5]);
+
+        let size = fs
+            .open(&"jar".into(), FileOpenOptions::new().read(true))
+            .unwrap()
+            .seek(SeekFrom::End(0))
+            .unwrap();
+        assert_eq!(size, 8 * 4096);
+
+        let found = tree.search(key.borrow()).unwrap().unwrap().assert_loaded(&pager).unwrap();
+        assert_eq!(found.cast::<str>().to_string(), value);
+
+        // La page libre restante est réutilisée.
+        assert_eq!(pager.new_element().unwrap().tag().page_id, 5);
+    }
+}
@@ -154,6 +154,11 @@ where
         self.as_meta().has_spilled()
     }
 
+    /// Tête de la liste chaînée des pages de débordement
+    pub fn spill_page(&self) -> Option<PageId> {
+        self.as_meta().get_spill_page()
+    }
+
     pub fn copy_into<S2>(&self, dest: &mut Var<S2>) -> Result<()>
     where
         S2: AsMutPageSlice + ?std::marker::Sized,
@@ -204,6 +209,10 @@ where
         Ok(())
     }
 
+    pub(crate) fn set_spill_page(&mut self, spill_page: Option<PageId>) {
+        self.as_mut_meta().spill_page_id = spill_page.into();
+    }
+
     fn borrow_mut_content(&mut self) -> &mut [u8] {
         let range = self.data_range();
         &mut self.0.as_mut()[range]
@@ -305,7 +314,8 @@ impl<Page> SpillPage<Page>
 where
     Page: AsMutPageSlice,
 {
-    pub fn new(page: Page) -> Self {
+    pub fn new(mut page: Page) -> Self {
+        page.as_mut_bytes()[0] = PageKind::Spill as u8;
         let mut page = Self(page);
         page.as_uinit_meta().write(Default::default());
         page
@@ -381,8 +391,9 @@ pub fn free_overflow_pages<'a, Pager: IPager<'a> + ?std::marker::Sized>(
     let mut current = Some(pager.tag().in_page(head));
 
     while let Some(tag) = current {
-        let page = pager.borrow_element(&tag).and_then(SpillPage::try_from)?;
-        current = page.get_next().map(|pid| pager.tag().in_page(pid));
+        // La page doit être relâchée avant d'être libérée.
+        let next = pager.borrow_element(&tag).and_then(SpillPage::try_from)?.get_next();
+        current = next.map(|pid| pager.tag().in_page(pid));
         pager.delete_element(&tag)?;
     }
 
@@ -399,12 +410,12 @@ pub fn read_var<'a, Pager: IPager<'a>, W: Write>(
     let in_page_data = &src[..meta.in_page_size.try_into().unwrap()];
     dest.write_all(in_page_data)?;
 
-    let mut current = meta.spill_page_id;
+    let mut current = meta.get_spill_page();
 
-    while let Some(tag) = current.as_ref().map(|pid| pager.tag().in_page(pid)) {
+    while let Some(tag) = current.map(|pid| pager.tag().in_page(pid)) {
         let page = pager.borrow_element(&tag).and_then(SpillPage::try_from)?;
         page.read(dest);
-        current = page.get_next().into();
+        current = page.get_next();
     }
 
     Ok(())